@@ -0,0 +1,61 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use lafont::{Net, Port};
+
+/// Returns `n` disjoint constructor-duplicator pairs with erasers on their auxiliary ports.
+fn commutations(n: usize) -> Net {
+    let mut net = Net::empty();
+    for _ in 0..n {
+        let c = net.add_construct();
+        let d = net.add_duplicate();
+        net.connect_unchecked(Port::new(c, 0), Port::new(d, 0));
+        for x in [c, d] {
+            for p in 1..3 {
+                let e = net.add_erase();
+                net.connect_unchecked(Port::new(x, p), Port::new(e, 0));
+            }
+        }
+    }
+    net
+}
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("commutations");
+    for n in [1_000, 10_000] {
+        let net = commutations(n);
+        group.bench_with_input(BenchmarkId::new("step_all", n), &net, |b, net| {
+            let run = |mut net: Net| while net.step_all() > 0 {};
+            b.iter_batched(|| net.clone(), run, BatchSize::LargeInput)
+        });
+        group.bench_with_input(BenchmarkId::new("step_parallel", n), &net, |b, net| {
+            let run = |mut net: Net| while net.step_parallel() > 0 {};
+            b.iter_batched(|| net.clone(), run, BatchSize::LargeInput)
+        });
+        group.bench_with_input(BenchmarkId::new("reduce_regions", n), &net, |b, net| {
+            let run = |mut net: Net| net.reduce_regions(32);
+            b.iter_batched(|| net.clone(), run, BatchSize::LargeInput)
+        });
+        #[cfg(feature = "atomic")]
+        group.bench_with_input(BenchmarkId::new("reduce_atomic", n), &net, |b, net| {
+            let run = |mut net: Net| net.reduce_atomic(None);
+            b.iter_batched(|| net.clone(), run, BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
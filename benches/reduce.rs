@@ -0,0 +1,96 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use lafont::gen::church_term;
+use lafont::lambda::{compile, Term};
+use lafont::{Net, Port, Strategy};
+
+/// Returns a complete binary tree of constructors of the given depth, with erasers as leaves.
+///
+/// The principal port of each constructor faces its parent. Returns the root.
+fn tree(net: &mut Net, depth: usize) -> Port {
+    if depth == 0 {
+        return Port::new(net.add_erase(), 0);
+    }
+    let c = net.add_construct();
+    for p in 1..3 {
+        let x = tree(net, depth - 1);
+        net.connect_unchecked(Port::new(c, p), x);
+    }
+    Port::new(c, 0)
+}
+
+/// Returns a tree of the given depth duplicated by a duplicator.
+fn duplication(depth: usize) -> Net {
+    let mut net = Net::empty();
+    let root = tree(&mut net, depth);
+    let d = net.add_duplicate();
+    net.connect_unchecked(Port::new(d, 0), root);
+    for p in 1..3 {
+        let x = net.add_free();
+        net.connect_unchecked(Port::new(d, p), Port::new(x, 0));
+    }
+    net
+}
+
+/// Returns a tree of the given depth erased by an eraser.
+fn erasure(depth: usize) -> Net {
+    let mut net = Net::empty();
+    let root = tree(&mut net, depth);
+    let e = net.add_erase();
+    net.connect_unchecked(Port::new(e, 0), root);
+    net
+}
+
+/// Returns the Church numeral `m` raised to the power `n`.
+fn exponentiation(m: usize, n: usize) -> Net {
+    let term = Term::App(Box::new(church_term(n)), Box::new(church_term(m)));
+    compile(&term).unwrap().0
+}
+
+fn bench(c: &mut Criterion) {
+    let nets = [
+        ("duplication", 10, duplication(10)),
+        ("erasure", 12, erasure(12)),
+        ("exponentiation", 30, exponentiation(2, 30)),
+    ];
+    for (name, size, net) in nets {
+        let mut group = c.benchmark_group(name);
+        // Scanning the net at each step is slow on purpose, so fewer samples are taken.
+        group.sample_size(10);
+        let steps = net.clone().reduce(None).steps;
+        group.throughput(Throughput::Elements(steps as u64));
+        let strategies = [
+            ("worklist", Strategy::PreferErase),
+            ("fifo", Strategy::Fifo),
+            ("annihilate", Strategy::PreferAnnihilate),
+            ("scan", Strategy::Outermost),
+        ];
+        for (id, strategy) in strategies {
+            group.bench_with_input(BenchmarkId::new(id, size), &net, |b, net| {
+                let run = |mut net: Net| net.reduce_with(strategy, None);
+                b.iter_batched(|| net.clone(), run, BatchSize::LargeInput)
+            });
+        }
+        group.bench_with_input(BenchmarkId::new("step_all", size), &net, |b, net| {
+            let run = |mut net: Net| while net.step_all() > 0 {};
+            b.iter_batched(|| net.clone(), run, BatchSize::LargeInput)
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
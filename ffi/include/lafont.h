@@ -0,0 +1,107 @@
+#ifndef LAFONT_H
+#define LAFONT_H
+
+/* Regenerate with cbindgen from src/lib.rs, see cbindgen.toml. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define LAFONT_CONSTRUCT 0
+
+#define LAFONT_DUPLICATE 1
+
+#define LAFONT_ERASE 2
+
+/**
+ * Returned instead of an id on failure.
+ */
+#define LAFONT_ERROR SIZE_MAX
+
+/**
+ * A net with the combinator rules.
+ */
+typedef struct LafontNet LafontNet;
+
+/**
+ * Returns a new empty net.
+ */
+LafontNet *lafont_net_new(void);
+
+/**
+ * Destroys a net.
+ *
+ * # Safety
+ *
+ * `net` must come from `lafont_net_new` and not be used afterwards. It may be null.
+ */
+void lafont_net_free(LafontNet *net);
+
+/**
+ * Adds an agent with `symbol`, one of the `LAFONT_` symbols, and returns its id.
+ *
+ * # Safety
+ *
+ * `net` must be a valid net.
+ */
+size_t lafont_net_add_agent(LafontNet *net, size_t symbol);
+
+/**
+ * Adds a free port and returns its id.
+ *
+ * # Safety
+ *
+ * `net` must be a valid net.
+ */
+size_t lafont_net_add_free(LafontNet *net);
+
+/**
+ * Connects port `p` of `a` with port `q` of `b`.
+ *
+ * Free ports only have port 0. Fails if a port does not exist. Wires previously attached to
+ * the ports are overwritten.
+ *
+ * # Safety
+ *
+ * `net` must be a valid net.
+ */
+int lafont_net_connect(LafontNet *net, size_t a, size_t p, size_t b, size_t q);
+
+/**
+ * Reduces at most `limit` active pairs, or all of them if `limit` is `LAFONT_ERROR`.
+ *
+ * Returns the number of reduced active pairs.
+ *
+ * # Safety
+ *
+ * `net` must be a valid net.
+ */
+size_t lafont_net_reduce(LafontNet *net, size_t limit);
+
+/**
+ * Returns the number of agents, not counting free ports.
+ *
+ * # Safety
+ *
+ * `net` must be a valid net.
+ */
+size_t lafont_net_len(const LafontNet *net);
+
+/**
+ * Returns the net in JSON, to free with `lafont_string_free`.
+ *
+ * # Safety
+ *
+ * `net` must be a valid net.
+ */
+char *lafont_net_dump(const LafontNet *net);
+
+/**
+ * Frees a string returned by this library.
+ *
+ * # Safety
+ *
+ * `s` must come from this library and not be used afterwards. It may be null.
+ */
+void lafont_string_free(char *s);
+
+#endif /* LAFONT_H */
@@ -0,0 +1,141 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! C interface for the reduction of interaction nets.
+//!
+//! Nets are created with [`lafont_net_new`] and destroyed with [`lafont_net_free`]. Functions
+//! returning an id return [`LAFONT_ERROR`] on failure, and functions returning an `int` return 0
+//! on success and -1 on failure. The header is `include/lafont.h`, generated by cbindgen.
+
+use lafont::{Net, Port, Symbol};
+use std::ffi::{c_char, c_int, CString};
+
+/// A net with the combinator rules.
+pub struct LafontNet {
+    net: Net,
+}
+
+// Literals, so that cbindgen can write them, equal to the constants of `Symbol`.
+pub const LAFONT_CONSTRUCT: usize = 0;
+pub const LAFONT_DUPLICATE: usize = 1;
+pub const LAFONT_ERASE: usize = 2;
+
+/// Returned instead of an id on failure.
+pub const LAFONT_ERROR: usize = usize::MAX;
+
+/// Returns a new empty net.
+#[no_mangle]
+pub extern "C" fn lafont_net_new() -> *mut LafontNet {
+    Box::into_raw(Box::new(LafontNet { net: Net::empty() }))
+}
+
+/// Destroys a net.
+///
+/// # Safety
+///
+/// `net` must come from [`lafont_net_new`] and not be used afterwards. It may be null.
+#[no_mangle]
+pub unsafe extern "C" fn lafont_net_free(net: *mut LafontNet) {
+    if !net.is_null() {
+        drop(Box::from_raw(net));
+    }
+}
+
+/// Adds an agent with `symbol`, one of the `LAFONT_` symbols, and returns its id.
+///
+/// # Safety
+///
+/// `net` must be a valid net.
+#[no_mangle]
+pub unsafe extern "C" fn lafont_net_add_agent(net: *mut LafontNet, symbol: usize) -> usize {
+    (*net).net.add_agent(Symbol(symbol)).unwrap_or(LAFONT_ERROR)
+}
+
+/// Adds a free port and returns its id.
+///
+/// # Safety
+///
+/// `net` must be a valid net.
+#[no_mangle]
+pub unsafe extern "C" fn lafont_net_add_free(net: *mut LafontNet) -> usize {
+    (*net).net.add_free()
+}
+
+/// Connects port `p` of `a` with port `q` of `b`.
+///
+/// Free ports only have port 0. Fails if a port does not exist. Wires previously attached to
+/// the ports are overwritten.
+///
+/// # Safety
+///
+/// `net` must be a valid net.
+#[no_mangle]
+pub unsafe extern "C" fn lafont_net_connect(
+    net: *mut LafontNet,
+    a: usize,
+    p: usize,
+    b: usize,
+    q: usize,
+) -> c_int {
+    match (*net).net.connect(Port::new(a, p), Port::new(b, q)) {
+        Ok(()) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Reduces at most `limit` active pairs, or all of them if `limit` is [`LAFONT_ERROR`].
+///
+/// Returns the number of reduced active pairs.
+///
+/// # Safety
+///
+/// `net` must be a valid net.
+#[no_mangle]
+pub unsafe extern "C" fn lafont_net_reduce(net: *mut LafontNet, limit: usize) -> usize {
+    let limit = (limit != LAFONT_ERROR).then_some(limit);
+    (*net).net.reduce(limit).steps
+}
+
+/// Returns the number of agents, not counting free ports.
+///
+/// # Safety
+///
+/// `net` must be a valid net.
+#[no_mangle]
+pub unsafe extern "C" fn lafont_net_len(net: *const LafontNet) -> usize {
+    (*net).net.len()
+}
+
+/// Returns the net in JSON, to free with [`lafont_string_free`].
+///
+/// # Safety
+///
+/// `net` must be a valid net.
+#[no_mangle]
+pub unsafe extern "C" fn lafont_net_dump(net: *const LafontNet) -> *mut c_char {
+    // JSON escapes control characters, so there is no nul byte.
+    CString::new((*net).net.to_json()).unwrap().into_raw()
+}
+
+/// Frees a string returned by this library.
+///
+/// # Safety
+///
+/// `s` must come from this library and not be used afterwards. It may be null.
+#[no_mangle]
+pub unsafe extern "C" fn lafont_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
@@ -0,0 +1,63 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the C interface.
+
+use lafont::{Net, Symbol};
+use lafont_ffi::*;
+use std::ffi::CStr;
+
+#[test]
+fn symbols() {
+    assert_eq!(LAFONT_CONSTRUCT, Symbol::CONSTRUCT.0);
+    assert_eq!(LAFONT_DUPLICATE, Symbol::DUPLICATE.0);
+    assert_eq!(LAFONT_ERASE, Symbol::ERASE.0);
+}
+
+#[test]
+fn build_reduce_dump() {
+    unsafe {
+        let net = lafont_net_new();
+        let c = lafont_net_add_agent(net, LAFONT_CONSTRUCT);
+        let d = lafont_net_add_agent(net, LAFONT_DUPLICATE);
+        assert_eq!(lafont_net_add_agent(net, 42), LAFONT_ERROR);
+        assert_eq!(lafont_net_connect(net, c, 0, d, 0), 0);
+        assert_eq!(lafont_net_connect(net, c, 3, d, 1), -1);
+        for (a, p) in [(c, 1), (c, 2), (d, 1), (d, 2)] {
+            let f = lafont_net_add_free(net);
+            assert_eq!(lafont_net_connect(net, a, p, f, 0), 0);
+        }
+        assert_eq!(lafont_net_reduce(net, LAFONT_ERROR), 1);
+        assert_eq!(lafont_net_len(net), 4);
+        let json = lafont_net_dump(net);
+        let dump = Net::from_json(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+        assert_eq!(dump.len(), 4);
+        assert!(dump.active_pairs().is_empty());
+        lafont_string_free(json);
+        lafont_net_free(net);
+    }
+}
+
+#[test]
+fn header_declares_all_functions() {
+    let header = include_str!("../include/lafont.h");
+    let source = include_str!("../src/lib.rs");
+    for line in source.lines() {
+        let name = match line.split_once("extern \"C\" fn ") {
+            Some((_, rest)) => rest.split('(').next().unwrap(),
+            None => continue,
+        };
+        assert!(header.contains(&format!("{}(", name)), "{}", name);
+    }
+}
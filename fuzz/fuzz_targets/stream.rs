@@ -0,0 +1,37 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Checks that streaming reductions reach the same net as regular ones.
+
+#![no_main]
+
+use lafont_fuzz::{check, Input};
+use libfuzzer_sys::fuzz_target;
+
+/// Maximum number of rewrites, since nets may not terminate.
+const MAX_STEPS: usize = 1000;
+
+fuzz_target!(|data: &[u8]| {
+    let mut input = Input(data);
+    let mut expected = match input.net() {
+        Some(net) => net,
+        None => return,
+    };
+    let mut actual = expected.clone();
+    let stats = actual.reduce_streaming(Some(MAX_STEPS));
+    check(&actual);
+    if stats.steps < MAX_STEPS {
+        assert_eq!(stats.steps, expected.reduce(None).steps);
+        assert!(actual.isomorphic(&expected));
+    }
+});
@@ -0,0 +1,99 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Nets built from the bytes given by the fuzzer.
+
+use lafont::{Malformed, Net, Port, Strategy, Symbol};
+
+/// Maximum number of agents of a net.
+const MAX_AGENTS: usize = 64;
+
+/// Reader of the bytes given by the fuzzer, returning zeros once exhausted.
+pub struct Input<'a>(pub &'a [u8]);
+
+impl Input<'_> {
+    pub fn byte(&mut self) -> u8 {
+        match self.0.split_first() {
+            None => 0,
+            Some((&x, rest)) => {
+                self.0 = rest;
+                x
+            }
+        }
+    }
+
+    /// Returns a number below `n`, which must not be zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        let x = u16::from_le_bytes([self.byte(), self.byte()]);
+        x as usize % n
+    }
+
+    pub fn strategy(&mut self) -> Strategy {
+        match self.byte() % 7 {
+            0 => Strategy::PreferErase,
+            1 => Strategy::Fifo,
+            2 => Strategy::Lifo,
+            3 => Strategy::Random,
+            4 => Strategy::Outermost,
+            5 => Strategy::Fair,
+            _ => Strategy::PreferAnnihilate,
+        }
+    }
+
+    /// Returns a net of interaction combinators with all ports connected.
+    ///
+    /// Returns `None` if an agent is connected to itself by its principal port.
+    pub fn net(&mut self) -> Option<Net> {
+        let mut net = Net::empty();
+        let mut ports = Vec::new();
+        for _ in 0..self.below(MAX_AGENTS + 1) {
+            let symbol = match self.byte() % 3 {
+                0 => Symbol::ERASE,
+                1 => Symbol::CONSTRUCT,
+                _ => Symbol::DUPLICATE,
+            };
+            let a = net.add_agent_unchecked(symbol);
+            ports.extend((0..=net.rules().arity(symbol)).map(|p| Port::new(a, p)));
+        }
+        for _ in 0..self.byte() % 4 {
+            ports.push(Port::new(net.add_free(), 0));
+        }
+        if ports.len() % 2 == 1 {
+            ports.push(Port::new(net.add_free(), 0));
+        }
+        // Fisher-Yates shuffle, then consecutive ports are connected.
+        for i in (1..ports.len()).rev() {
+            ports.swap(i, self.below(i + 1));
+        }
+        for wire in ports.chunks(2) {
+            net.connect_unchecked(wire[0], wire[1]);
+        }
+        net.validate().ok()?;
+        net.seed(self.byte() as u64);
+        Some(net)
+    }
+}
+
+/// Panics if the wiring of the net is broken.
+///
+/// Agents connected to themselves are fine, since reduction creates them in vicious circles.
+pub fn check(net: &Net) {
+    let mut errors = match net.validate() {
+        Ok(()) => return,
+        Err(errors) => errors,
+    };
+    errors.retain(|x| !matches!(x, Malformed::SelfConnected(_)));
+    if !errors.is_empty() {
+        panic!("{}\n{errors:?}", net.to_sic());
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Python bindings for the reduction of interaction nets.
+//!
+//! The `lafont` module has a [`Net`] class wrapping [`lafont::Net`] with the combinator rules.
+//! Reductions return [`Stats`], whose fields are easy to plot.
+
+use lafont::{Port, ReductionStats, Strategy};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+fn error(e: impl ToString) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+/// Statistics of a reduction, see [`ReductionStats`].
+#[pyclass(get_all)]
+pub struct Stats {
+    /// Number of reduced active pairs.
+    steps: usize,
+    /// Maximum number of agents during the reduction.
+    peak_agents: usize,
+    /// Number of reduced active pairs per rule, named like `construct-duplicate`.
+    rules: HashMap<String, usize>,
+    /// Sum of the weights of the reduced active pairs.
+    cost: f64,
+}
+
+#[pymethods]
+impl Stats {
+    fn __repr__(&self) -> String {
+        format!(
+            "Stats(steps={}, peak_agents={}, cost={})",
+            self.steps, self.peak_agents, self.cost
+        )
+    }
+}
+
+#[pyclass]
+pub struct Net {
+    net: lafont::Net,
+}
+
+impl Net {
+    fn stats(&self, stats: ReductionStats) -> Stats {
+        let name = |s| self.net.rules().name(s);
+        let rules = stats.rules.iter();
+        let rules = rules.map(|(&(x, y), &n)| (format!("{}-{}", name(x), name(y)), n));
+        Stats {
+            steps: stats.steps,
+            peak_agents: stats.peak_agents,
+            rules: rules.collect(),
+            cost: stats.cost,
+        }
+    }
+}
+
+#[pymethods]
+impl Net {
+    /// Creates an empty net.
+    #[new]
+    fn new() -> Net {
+        Net {
+            net: lafont::Net::empty(),
+        }
+    }
+
+    /// Creates an example net, see [`lafont::gen::example`].
+    #[staticmethod]
+    fn example(name: &str) -> PyResult<Net> {
+        let net = lafont::gen::example(name).map_err(error)?;
+        Ok(Net { net })
+    }
+
+    /// Parses a net in JSON, see [`lafont::Net::from_json`].
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Net> {
+        let net = lafont::Net::from_json(json).map_err(error)?;
+        Ok(Net { net })
+    }
+
+    /// Returns the net in JSON, see [`lafont::Net::to_json`].
+    fn to_json(&self) -> String {
+        self.net.to_json()
+    }
+
+    /// Parses a net in the textual format of interaction combinators.
+    #[staticmethod]
+    fn from_sic(text: &str) -> PyResult<Net> {
+        let net = lafont::Net::from_sic(text).map_err(error)?;
+        Ok(Net { net })
+    }
+
+    /// Returns the net in the textual format of interaction combinators.
+    fn to_sic(&self) -> String {
+        self.net.to_sic()
+    }
+
+    /// Adds a constructor and returns its id.
+    fn add_construct(&mut self) -> usize {
+        self.net.add_construct()
+    }
+
+    /// Adds a duplicator and returns its id.
+    fn add_duplicate(&mut self) -> usize {
+        self.net.add_duplicate()
+    }
+
+    /// Adds an eraser and returns its id.
+    fn add_erase(&mut self) -> usize {
+        self.net.add_erase()
+    }
+
+    /// Adds a free port and returns its id.
+    fn add_free(&mut self) -> usize {
+        self.net.add_free()
+    }
+
+    /// Connects port `p` of `a` with port `q` of `b`.
+    fn connect(&mut self, a: usize, p: usize, b: usize, q: usize) -> PyResult<()> {
+        let (x, y) = (Port::new(a, p), Port::new(b, q));
+        self.net.connect(x, y).map_err(error)
+    }
+
+    /// Returns the agent and port connected to port `p` of `a`.
+    fn get_port(&self, a: usize, p: usize) -> PyResult<(usize, usize)> {
+        let y = self.net.get_port(Port::new(a, p)).map_err(error)?;
+        Ok((y.agent, y.port))
+    }
+
+    /// Returns the name of the symbol of agent `a`, or `None` for free ports.
+    fn symbol(&self, a: usize) -> Option<String> {
+        let symbol = self.net.symbol(a)?;
+        Some(self.net.rules().name(symbol).to_string())
+    }
+
+    /// Returns the ids of the agents.
+    fn agents(&self) -> Vec<usize> {
+        self.net.agents().collect()
+    }
+
+    /// Returns the active pairs.
+    fn active_pairs(&self) -> Vec<(usize, usize)> {
+        self.net.active_pairs()
+    }
+
+    /// Reduces at most `limit` active pairs (all if `None`), erasers first.
+    #[pyo3(signature = (limit=None))]
+    fn reduce(&mut self, limit: Option<usize>) -> Stats {
+        let stats = self.net.reduce_with(Strategy::PreferErase, limit);
+        self.stats(stats)
+    }
+
+    /// Returns whether the net is reducible, in normal form, or deadlocked.
+    fn status(&self) -> String {
+        self.net.status().to_string()
+    }
+
+    /// Returns the number of agents.
+    fn __len__(&self) -> usize {
+        self.net.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Net({} agents)", self.net.len())
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "lafont")]
+fn lafont_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Net>()?;
+    m.add_class::<Stats>()?;
+    Ok(())
+}
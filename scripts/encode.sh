@@ -0,0 +1,44 @@
+#!/bin/sh
+# Copyright 2018-2022 Google LLC
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     https://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+# Encodes the frames saved with --record into a video or a GIF with ffmpeg.
+#
+# Usage: scripts/encode.sh DIR OUTPUT [FPS]
+#
+# The format is given by the extension of OUTPUT, for example lafont.mp4 or lafont.gif.
+
+set -e
+
+if [ $# -lt 2 ]; then
+  echo "usage: $0 DIR OUTPUT [FPS]" >&2
+  exit 1
+fi
+dir=$1
+output=$2
+fps=${3:-30}
+input="$dir/frame-%06d.ppm"
+
+case "$output" in
+  *.gif)
+    # A palette generated from the frames keeps the colors of the agents.
+    ffmpeg -y -framerate "$fps" -i "$input" \
+      -vf "split[a][b];[a]palettegen[p];[b][p]paletteuse" "$output"
+    ;;
+  *)
+    # Odd dimensions are not supported by most H.264 decoders.
+    ffmpeg -y -framerate "$fps" -i "$input" \
+      -vf "scale=trunc(iw/2)*2:trunc(ih/2)*2" -pix_fmt yuv420p "$output"
+    ;;
+esac
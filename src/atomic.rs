@@ -0,0 +1,339 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lock-free parallel reduction with ports linked by atomic words.
+
+use crate::{Agent, CostModel, End, Net, Plan, Port, ReductionStats, RuleSet, Symbol, Wire};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::atomic::{AtomicU64, AtomicUsize};
+
+/// Value of unconnected ports.
+const EMPTY: u64 = u64::MAX;
+/// Value of ports whose wire is claimed by a thread.
+const LOCKED: u64 = u64::MAX - 1;
+/// Symbol of free ports.
+const FREE: usize = usize::MAX;
+/// Symbol of deleted agents and of nodes not yet created.
+const DELETED: usize = usize::MAX - 1;
+
+/// Encodes a port in a word, the node in the high bits and the port in the low 16 bits.
+fn encode(x: Port) -> u64 {
+    ((x.agent as u64) << 16) | x.port as u64
+}
+
+fn decode(x: u64) -> Port {
+    Port::new((x >> 16) as usize, (x & 0xffff) as usize)
+}
+
+/// Agents and free ports of a net, called nodes, each with the port connected to its ports.
+struct Arena<'a> {
+    rules: &'a RuleSet,
+    cost: &'a CostModel,
+    /// Number of ports of each node, which is the maximum arity plus the principal port.
+    stride: usize,
+    ports: Vec<AtomicU64>,
+    /// Symbol of each node, or [`FREE`] or [`DELETED`].
+    symbols: Vec<AtomicUsize>,
+    /// Nodes available to the agents created during a round, taken from `cursor` on.
+    vacant: Vec<usize>,
+    cursor: AtomicUsize,
+}
+
+/// Result of a round, or of the part of a round done by one thread.
+#[derive(Default)]
+struct Round {
+    stats: ReductionStats,
+    /// Active pairs created during the round.
+    pairs: Vec<(usize, usize)>,
+    /// Active pairs whose wires were claimed by another thread.
+    deferred: Vec<(usize, usize)>,
+    /// Number of created agents.
+    created: usize,
+    /// Deleted agents.
+    deleted: Vec<usize>,
+}
+
+impl Round {
+    fn merge(mut self, other: Round) -> Round {
+        self.stats.merge(&other.stats);
+        self.pairs.extend(other.pairs);
+        self.deferred.extend(other.deferred);
+        self.created += other.created;
+        self.deleted.extend(other.deleted);
+        self
+    }
+}
+
+impl Arena<'_> {
+    /// Adds `count` nodes, not yet created.
+    fn grow(&mut self, count: usize) -> std::ops::Range<usize> {
+        let len = self.symbols.len();
+        self.symbols.resize_with(len + count, || DELETED.into());
+        self.ports
+            .resize_with((len + count) * self.stride, || EMPTY.into());
+        len..len + count
+    }
+
+    fn slot(&self, x: Port) -> &AtomicU64 {
+        &self.ports[x.agent * self.stride + x.port]
+    }
+
+    fn symbol(&self, x: usize) -> Option<Symbol> {
+        match self.symbols[x].load(SeqCst) {
+            FREE | DELETED => None,
+            s => Some(Symbol(s)),
+        }
+    }
+
+    fn arity(&self, x: usize) -> usize {
+        self.symbol(x).map_or(0, |s| self.rules.arity(s))
+    }
+
+    /// Locks both ends of the wire `x`-`y`, failing if the wire changed or is already locked.
+    fn lock(&self, x: Port, y: Port) -> bool {
+        // All threads lock the ends of a wire in the same order, so only one of them can lock
+        // both.
+        let (x, y) = (x.min(y), x.max(y));
+        let lock = |x, y| {
+            let slot = self.slot(x);
+            slot.compare_exchange(encode(y), LOCKED, SeqCst, SeqCst)
+                .is_ok()
+        };
+        if !lock(x, y) {
+            return false;
+        }
+        if !lock(y, x) {
+            self.slot(x).store(encode(y), SeqCst);
+            return false;
+        }
+        true
+    }
+
+    /// Claims the wires of the auxiliary ports of `a` and `b` by locking both of their ends, and
+    /// returns the ports they are connected to.
+    ///
+    /// Returns `None` without locking anything if another thread claimed one of the wires first.
+    /// Wires between `a` and `b` are not locked, since no other thread can reach them.
+    fn claim(&self, a: usize, b: usize) -> Option<HashMap<Port, Port>> {
+        let mut peers = HashMap::new();
+        let ports = [a, b]
+            .into_iter()
+            .flat_map(|x| (1..=self.arity(x)).map(move |p| Port::new(x, p)));
+        for x in ports {
+            let y = match self.slot(x).load(SeqCst) {
+                EMPTY => panic!("unconnected port in an active pair"),
+                LOCKED => None,
+                y => Some(decode(y)),
+            };
+            match y {
+                Some(y) if y.agent == a || y.agent == b || self.lock(x, y) => {
+                    peers.insert(x, y);
+                }
+                _ => {
+                    for (&x, &y) in &peers {
+                        if y.agent != a && y.agent != b {
+                            self.slot(x).store(encode(y), SeqCst);
+                            self.slot(y).store(encode(x), SeqCst);
+                        }
+                    }
+                    return None;
+                }
+            }
+        }
+        Some(peers)
+    }
+
+    /// Rewrites the active pair `a`-`b` if its wires can be claimed, and defers it otherwise.
+    fn rewrite(&self, mut round: Round, (a, b): (usize, usize)) -> Round {
+        if self.slot(Port::new(a, 0)).load(SeqCst) != encode(Port::new(b, 0)) {
+            return round;
+        }
+        let peers = match self.claim(a, b) {
+            Some(peers) => peers,
+            None => {
+                round.deferred.push((a, b));
+                return round;
+            }
+        };
+        let symbol = |x| self.symbol(x).unwrap();
+        let plan = Plan::new(self.rules, a, b, symbol, |x| peers[&x]);
+        let start = self.cursor.fetch_add(plan.agents.len(), SeqCst);
+        let agents = &self.vacant[start..start + plan.agents.len()];
+        for (&c, &symbol) in agents.iter().zip(&plan.agents) {
+            self.symbols[c].store(symbol.0, SeqCst);
+        }
+        let port = |x| match x {
+            End::Old(p) => p,
+            End::New(i, p) => Port::new(agents[i], p),
+        };
+        // The old ends are locked by this thread and the new ones are not reachable yet, so
+        // storing releases the wires.
+        for &(x, y) in &plan.wires {
+            let (x, y) = (port(x), port(y));
+            self.slot(x).store(encode(y), SeqCst);
+            self.slot(y).store(encode(x), SeqCst);
+            let (c, d) = (x.agent.min(y.agent), x.agent.max(y.agent));
+            if x.port == 0 && y.port == 0 {
+                if let (Some(s), Some(t)) = (self.symbol(c), self.symbol(d)) {
+                    if self.rules.rule(s, t).is_some() {
+                        round.pairs.push((c, d));
+                    }
+                }
+            }
+        }
+        round.stats.record(symbol(a), symbol(b), 0, self.cost);
+        for x in [a, b] {
+            self.symbols[x].store(DELETED, SeqCst);
+            round.deleted.push(x);
+        }
+        round.created += agents.len();
+        round
+    }
+}
+
+impl Net {
+    /// Same as [`Net::reduce`] but reduces on the rayon thread pool, with each port stored in an
+    /// atomic word linking to the port it is connected to.
+    ///
+    /// The reduction proceeds in rounds, each reducing in parallel the active pairs of the
+    /// previous one. A thread claims the wires around an active pair by swapping both of their
+    /// ends with a compare-and-swap, rewrites the pair, and releases the wires by connecting
+    /// them. Active pairs whose wires were claimed by another thread are deferred to the next
+    /// round, and rounds where all the active pairs were deferred are reduced sequentially.
+    ///
+    /// The normal form is the same as with [`Net::reduce`], but the ids of the created agents
+    /// depend on the scheduling. The peak number of agents is only measured between rounds. The
+    /// reduction is neither journaled nor traced.
+    pub fn reduce_atomic(&mut self, limit: Option<usize>) -> ReductionStats {
+        span!(DEBUG, "reduce_atomic", ?limit);
+        self.forget();
+        let mut ids: Vec<_> = self
+            .agents
+            .keys()
+            .chain(self.free.keys())
+            .copied()
+            .collect();
+        ids.sort_unstable();
+        let index: HashMap<_, _> = ids.iter().enumerate().map(|(i, &x)| (x, i)).collect();
+        let rules = &*self.rules;
+        let arity = rules.symbols().map(|s| rules.arity(s)).max().unwrap_or(0);
+        let mut arena = Arena {
+            rules,
+            cost: &self.cost,
+            stride: arity + 1,
+            ports: Vec::new(),
+            symbols: Vec::new(),
+            vacant: Vec::new(),
+            cursor: AtomicUsize::new(0),
+        };
+        arena.grow(ids.len());
+        for (i, &x) in ids.iter().enumerate() {
+            let (symbol, ports) = self.node(x).unwrap();
+            *arena.symbols[i].get_mut() = symbol.map_or(FREE, |s| s.0);
+            for (p, y) in ports.enumerate() {
+                let y = y.map_or(EMPTY, |y| encode(Port::new(index[&y.agent], y.port)));
+                *arena.ports[i * arena.stride + p].get_mut() = y;
+            }
+        }
+        let pairs = self.active_pairs().into_iter();
+        let mut pairs: Vec<_> = pairs.map(|(a, b)| (index[&a], index[&b])).collect();
+        self.worklist.drain();
+        let mut stats = ReductionStats::new(self.agents.len());
+        let mut len = self.agents.len();
+        let mut sequential = false;
+        let mut ids: Vec<_> = ids.into_iter().map(Some).collect();
+        while !pairs.is_empty() {
+            let left = limit.map_or(usize::MAX, |limit| limit - stats.steps);
+            if left == 0 {
+                break;
+            }
+            let rest = pairs.split_off(left.min(pairs.len()));
+            let created = pairs.iter().map(|&(a, b)| {
+                let (a, b) = (arena.symbol(a).unwrap(), arena.symbol(b).unwrap());
+                rules.rule(a, b).unwrap().agents.len()
+            });
+            let created: usize = created.sum();
+            let vacant = arena.vacant.len() - *arena.cursor.get_mut();
+            let fresh = arena.grow(created.saturating_sub(vacant));
+            arena.vacant.extend(fresh);
+            let shared = &arena;
+            let round = if sequential {
+                pairs
+                    .into_iter()
+                    .fold(Round::default(), |x, y| shared.rewrite(x, y))
+            } else {
+                pairs
+                    .into_par_iter()
+                    .fold(Round::default, |x, y| shared.rewrite(x, y))
+                    .reduce(Round::default, Round::merge)
+            };
+            sequential = round.stats.steps == 0;
+            let cursor = std::mem::take(arena.cursor.get_mut());
+            arena.vacant.drain(..cursor);
+            arena.vacant.extend(&round.deleted);
+            for &x in &round.deleted {
+                if let Some(id) = ids.get_mut(x) {
+                    *id = None;
+                }
+            }
+            len = len + round.created - round.deleted.len();
+            stats.peak_agents = stats.peak_agents.max(len);
+            stats.merge(&round.stats);
+            pairs = rest;
+            pairs.extend(round.deferred);
+            pairs.extend(round.pairs);
+        }
+        let mut next = self.next;
+        let mut back = HashMap::new();
+        for x in 0..arena.symbols.len() {
+            if *arena.symbols[x].get_mut() != DELETED {
+                let id = ids.get(x).copied().flatten().unwrap_or_else(|| {
+                    next += 1;
+                    next - 1
+                });
+                back.insert(x, id);
+            }
+        }
+        self.agents.clear();
+        self.next = next;
+        for x in 0..arena.symbols.len() {
+            let symbol = *arena.symbols[x].get_mut();
+            if symbol == DELETED {
+                continue;
+            }
+            let ports = (0..=arena.arity(x)).map(|p| {
+                let y = *arena.ports[x * arena.stride + p].get_mut();
+                (y != EMPTY).then(|| {
+                    let y = decode(y);
+                    Port::new(back[&y.agent], y.port)
+                })
+            });
+            let ports: Vec<_> = ports.collect();
+            match symbol {
+                FREE => *self.free.get_mut(&back[&x]).unwrap() = Wire::new(ports[0]),
+                s => {
+                    let agent = Agent::new(Symbol(s), ports);
+                    assert!(self.agents.insert(back[&x], agent).is_none());
+                }
+            }
+        }
+        for (a, b) in self.active_pairs() {
+            let queue = self.queue(a, b);
+            self.worklist.push(a, b, queue);
+        }
+        stats
+    }
+}
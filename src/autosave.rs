@@ -0,0 +1,98 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Periodic saves of long reductions.
+
+use lafont::Net;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// How often the net is saved.
+enum Every {
+    Rewrites(usize),
+    Time(Duration),
+}
+
+/// Saves a net in JSON to a file every so often, such that it can be resumed after a crash.
+pub struct Autosave {
+    path: String,
+    every: Every,
+    /// Number of steps and time of the last save.
+    last: (usize, Instant),
+    /// Number of steps of the last tick.
+    steps: usize,
+}
+
+impl Autosave {
+    /// Parses `every` as a number of rewrites, or a number of seconds followed by `s`.
+    pub fn new(path: String, every: &str) -> Result<Autosave, String> {
+        let invalid = |_| format!("invalid autosave period {:?}", every);
+        let every = match every.strip_suffix('s') {
+            Some(secs) => Every::Time(Duration::from_secs(secs.parse().map_err(invalid)?)),
+            None => Every::Rewrites(every.parse().map_err(invalid)?),
+        };
+        Ok(Autosave {
+            path,
+            every,
+            last: (0, Instant::now()),
+            steps: 0,
+        })
+    }
+
+    /// Returns how many steps can be reduced before the next save is due, after `steps` steps.
+    pub fn budget(&self, steps: usize) -> usize {
+        match self.every {
+            Every::Rewrites(n) => (self.last.0 + n).saturating_sub(steps).max(1),
+            Every::Time(_) => usize::MAX,
+        }
+    }
+
+    /// Saves `net` if a save is due after `steps` steps, then returns [`Autosave::budget`].
+    pub fn tick(&mut self, net: &Net, steps: usize) -> usize {
+        self.steps = steps;
+        let due = match self.every {
+            Every::Rewrites(n) => steps >= self.last.0 + n,
+            Every::Time(t) => self.last.1.elapsed() >= t,
+        };
+        if due {
+            self.save(net, steps);
+        }
+        self.budget(steps)
+    }
+
+    /// Saves `net` at the end of the reduction.
+    pub fn finish(&mut self, net: &Net) {
+        self.save(net, self.steps);
+    }
+
+    fn save(&mut self, net: &Net, steps: usize) {
+        self.last = (steps, Instant::now());
+        match write_atomic(&self.path, net) {
+            Ok(()) => println!("saved to {} after {} steps", self.path, steps),
+            Err(e) => println!("error: {}: {}", self.path, e),
+        }
+    }
+}
+
+/// Writes `net` to a temporary file next to `path`, then renames it to `path`.
+///
+/// The rename replaces the file at once, so `path` has either the previous or the new net even
+/// if the process dies while writing.
+fn write_atomic(path: &str, net: &Net) -> std::io::Result<()> {
+    let temp = format!("{}.tmp", path);
+    let mut file = std::fs::File::create(&temp)?;
+    file.write_all(net.to_json().as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&temp, path)
+}
@@ -0,0 +1,494 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Command-line interface, and the subcommands that do not open a window.
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use lafont::{Net, Strategy};
+use std::io::Write;
+use std::time::Instant;
+
+/// Animation of Yves Lafont's Interaction Combinators
+#[derive(Parser)]
+#[command(version, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Options of `view`, which runs when no subcommand is given.
+    #[command(flatten)]
+    pub view: ViewArgs,
+}
+
+// Commands are parsed once, so their size does not matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand)]
+pub enum Command {
+    /// Animate the reduction of nets in a window
+    View(ViewArgs),
+    /// Reduce nets without opening a window and print statistics
+    Reduce(ReduceArgs),
+    /// Measure how fast nets reduce with several strategies
+    Bench(BenchArgs),
+    /// Reduce nets with several strategies in parallel and compare them
+    Compare(CompareArgs),
+    /// Convert a net to another format
+    Convert(ConvertArgs),
+    /// Reduce a net step by step in an interactive debugger
+    Debug(Inputs),
+    /// Serve a net over HTTP on localhost
+    Serve(ServeArgs),
+}
+
+/// Nets given on the command line.
+#[derive(Args)]
+pub struct Inputs {
+    /// Nets to reduce, in the format given by --from or by their extension: .sic, .hvm, .json, or
+    /// .dot, or - for the standard input
+    #[arg(value_name = "FILE")]
+    pub files: Vec<String>,
+    /// Example net to reduce: loop (default), church:N, ackermann:M,N, fibonacci:N, or
+    /// random:N,SEED
+    #[arg(long, value_name = "NAME")]
+    pub example: Vec<String>,
+    /// Format of the files
+    #[arg(long, value_name = "FORMAT")]
+    pub from: Option<Format>,
+}
+
+#[derive(Args)]
+pub struct ViewArgs {
+    #[command(flatten)]
+    pub inputs: Inputs,
+    /// Speed factor, every Nth step is displayed
+    #[arg(short, default_value_t = 1)]
+    pub n: i32,
+    /// Show principal edges
+    #[arg(short)]
+    pub v: bool,
+    /// Show all wires, colored by port index
+    #[arg(short)]
+    pub w: bool,
+    /// Label agents with their id and symbol
+    #[arg(long)]
+    pub labels: bool,
+    /// Color agents by how recently they were rewritten
+    #[arg(long)]
+    pub heatmap: bool,
+    /// Tint the agents of each connected component with a distinct hue
+    #[arg(long)]
+    pub components: bool,
+    /// Lay the net out in a plane
+    #[arg(long = "2d")]
+    pub planar: bool,
+    /// How agents move: force, frozen, or hierarchical
+    #[arg(long, value_name = "NAME", default_value = "force")]
+    pub layout: String,
+    /// Time step of the layout
+    #[arg(long, default_value_t = 0.1)]
+    pub dt: f32,
+    /// Barnes-Hut opening angle of the force layout, 0 for exact
+    #[arg(long, default_value_t = 0.5)]
+    pub theta: f32,
+    /// Constants of the force layout: collision, damping, attraction, commute, erase, jitter
+    #[arg(long, value_name = "NAME=VALUE,...")]
+    pub physics: Option<String>,
+    /// When active pairs reduce: proximity, timer[:N], instant, or settled
+    #[arg(long, value_name = "MODE", default_value = "proximity")]
+    pub react_mode: String,
+    /// Steps each rule waits for like timer:N, from a TOML file of RULE = N like erase-erase = 0
+    #[arg(long, value_name = "FILE")]
+    pub delays: Option<String>,
+    /// Where rewrites create agents: midpoint (default), jittered, or along-wire
+    #[arg(long, value_name = "POLICY")]
+    pub spawn: Option<String>,
+    /// Kinetic energy under which the layout stops, 0 to never stop (default 0.001)
+    #[arg(long, value_name = "ENERGY")]
+    pub settle: Option<f32>,
+    /// Seed of the random layout and demo nets, such that runs are reproducible
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Frames to animate created and deleted agents, 0 to disable
+    #[arg(long, value_name = "FRAMES", default_value_t = 10)]
+    pub animate: usize,
+    /// Colors and sizes: dark, light, or a TOML file
+    #[arg(long, default_value = "dark")]
+    pub theme: String,
+    /// Fix the free ports evenly on a ring of RADIUS around the net
+    #[arg(long, value_name = "RADIUS")]
+    pub anchor_ring: Option<f32>,
+    /// Draw far agents as points when the net has more than N agents (default 10000)
+    #[arg(long, value_name = "N")]
+    pub lod: Option<usize>,
+    /// Dump the net in DOT format every N steps
+    #[arg(short, value_name = "N")]
+    pub d: Option<usize>,
+    /// Save each frame to DIR in PPM format
+    #[arg(long, value_name = "DIR")]
+    pub record: Option<String>,
+    /// Save an image every N rewrites: a PNG of the window, or an SVG of the net with --2d
+    #[arg(long, value_name = "N")]
+    pub snapshot_every: Option<usize>,
+    /// Pause when the net has more than N agents
+    #[arg(long, value_name = "N")]
+    pub max_nodes: Option<usize>,
+    /// File where Ctrl+S saves and Ctrl+L loads
+    #[arg(long, value_name = "FILE", default_value = "lafont.json")]
+    pub save: String,
+    /// Start from a file saved with Ctrl+S
+    #[arg(long, value_name = "FILE")]
+    pub load: Option<String>,
+    /// Write statistics to FILE in CSV every frame
+    #[arg(long, value_name = "FILE")]
+    pub stats: Option<String>,
+    /// Explain each rewrite in words, and pause after it with pause
+    #[arg(
+        long,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "print"
+    )]
+    pub explain: Option<Explain>,
+    /// Reduce random nets of AGENTS agents (200 by default) one after the other, forever
+    #[arg(
+        long,
+        value_name = "AGENTS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "200",
+        conflicts_with = "load"
+    )]
+    pub demo: Option<usize>,
+}
+
+#[derive(Args)]
+pub struct ReduceArgs {
+    #[command(flatten)]
+    pub inputs: Inputs,
+    /// Stop after N reductions
+    #[arg(long, value_name = "N")]
+    pub steps: Option<usize>,
+    /// Stop when the net has more than N agents
+    #[arg(long, value_name = "N")]
+    pub max_nodes: Option<usize>,
+    /// Reduce all the active pairs in passes and print how many each pass had
+    #[arg(long)]
+    pub sweeps: bool,
+    /// Explain each rewrite in words
+    #[arg(long, conflicts_with = "sweeps")]
+    pub explain: bool,
+    /// Print how many times each rule fired, including the rules that never fired
+    #[arg(long)]
+    pub coverage: bool,
+    /// Write the reduced net to FILE, in the format given by --to or by its extension, or to the
+    /// standard output for - (the default when reading the standard input, with its format)
+    #[arg(long, value_name = "FILE")]
+    pub out: Option<String>,
+    /// Format of the reduced net
+    #[arg(long, value_name = "FORMAT")]
+    pub to: Option<Format>,
+    /// Write statistics to FILE in CSV every 1000 steps
+    #[arg(long, value_name = "FILE")]
+    pub stats: Option<String>,
+    /// Save the net to FILE periodically
+    #[arg(long, value_name = "FILE")]
+    pub autosave: Option<String>,
+    /// Rewrites between autosaves, or seconds with an s suffix
+    #[arg(long, value_name = "N", default_value = "60s")]
+    pub autosave_every: String,
+    /// Net to reduce, saved by --autosave, which it keeps saving to by default
+    #[arg(long, value_name = "FILE")]
+    pub resume: Option<String>,
+}
+
+#[derive(Args)]
+pub struct BenchArgs {
+    #[command(flatten)]
+    pub inputs: Inputs,
+    /// Strategies to compare
+    #[arg(
+        long,
+        value_name = "NAME,...",
+        value_delimiter = ',',
+        default_value = "erase"
+    )]
+    pub strategies: Vec<StrategyName>,
+    /// Stop each reduction after N steps
+    #[arg(long, value_name = "N")]
+    pub steps: Option<usize>,
+}
+
+#[derive(Args)]
+pub struct CompareArgs {
+    #[command(flatten)]
+    pub inputs: Inputs,
+    /// Strategies to compare, each in its own thread
+    #[arg(
+        long,
+        value_name = "NAME,...",
+        value_delimiter = ',',
+        default_value = "erase,annihilate,outermost,random"
+    )]
+    pub strategies: Vec<StrategyName>,
+    /// Stop each reduction after N steps
+    #[arg(long, value_name = "N")]
+    pub steps: Option<usize>,
+}
+
+#[derive(Args)]
+pub struct ConvertArgs {
+    /// Net to convert, or - for the standard input
+    pub file: String,
+    /// Format of the net, given by its extension by default
+    #[arg(long, value_name = "FORMAT")]
+    pub from: Option<Format>,
+    /// Format to convert to
+    #[arg(long, value_name = "FORMAT")]
+    pub to: Format,
+    /// Write the converted net to FILE instead of the standard output
+    #[arg(long, value_name = "FILE")]
+    pub out: Option<String>,
+}
+
+#[derive(Args)]
+pub struct ServeArgs {
+    /// Port to listen on
+    #[arg(long, default_value_t = 8080)]
+    pub port: u16,
+    #[command(flatten)]
+    pub inputs: Inputs,
+}
+
+/// How the viewer explains rewrites.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum Explain {
+    /// Print each rewrite
+    Print,
+    /// Print each rewrite and pause after it
+    Pause,
+}
+
+/// Formats of nets.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum Format {
+    /// Textual format of interaction combinators
+    Sic,
+    /// HVM2 programs, which can only be read
+    Hvm,
+    /// JSON of the library
+    Json,
+    /// Graphviz, as written by the library
+    Dot,
+}
+
+impl Format {
+    /// Returns the format of `path` after its extension.
+    pub fn of(path: &str) -> Result<Format, String> {
+        match path.rsplit_once('.').map(|x| x.1) {
+            Some("sic") => Ok(Format::Sic),
+            Some("hvm") => Ok(Format::Hvm),
+            Some("json") => Ok(Format::Json),
+            Some("dot") => Ok(Format::Dot),
+            _ => Err(format!("unknown format of {}", path)),
+        }
+    }
+
+    pub fn parse(self, text: &str) -> Result<Net, String> {
+        match self {
+            Format::Sic => Net::from_sic(text),
+            Format::Hvm => Ok(lafont::hvm::import(text)?.0),
+            #[cfg(feature = "serde")]
+            Format::Json => Net::from_json(text).map_err(|e| e.to_string()),
+            #[cfg(not(feature = "serde"))]
+            Format::Json => Err("JSON requires the serde feature".to_string()),
+            Format::Dot => Net::from_dot(text),
+        }
+    }
+
+    pub fn print(self, net: &Net) -> Result<String, String> {
+        match self {
+            Format::Sic => Ok(net.to_sic()),
+            Format::Hvm => Err("HVM programs can only be read".to_string()),
+            #[cfg(feature = "serde")]
+            Format::Json => Ok(net.to_json()),
+            #[cfg(not(feature = "serde"))]
+            Format::Json => Err("JSON requires the serde feature".to_string()),
+            Format::Dot => Ok(net.to_dot()),
+        }
+    }
+}
+
+/// Path standing for the standard input or output.
+pub const STDIO: &str = "-";
+
+/// Reads the net in `path`, in `format` or the format of its extension.
+pub fn read(path: &str, format: Option<Format>) -> Result<Net, String> {
+    Ok(read_format(path, format)?.0)
+}
+
+/// Same as [`read`] but also returns the format.
+///
+/// The standard input is read if `path` is [`STDIO`]. Its format is then JSON if it starts with a
+/// brace and the textual format otherwise.
+pub fn read_format(path: &str, format: Option<Format>) -> Result<(Net, Format), String> {
+    let text = match path {
+        STDIO => std::io::read_to_string(std::io::stdin()),
+        _ => std::fs::read_to_string(path),
+    };
+    let text = text.map_err(|e| format!("{}: {}", path, e))?;
+    let format = match format {
+        Some(format) => format,
+        None if path == STDIO && text.trim_start().starts_with('{') => Format::Json,
+        None if path == STDIO => Format::Sic,
+        None => Format::of(path)?,
+    };
+    let net = format
+        .parse(&text)
+        .map_err(|e| format!("{}: {}", path, e))?;
+    Ok((net, format))
+}
+
+/// Writes `net` to `path`, in `format` or the format of its extension.
+///
+/// The standard output is written if `path` is [`STDIO`].
+pub fn write(path: &str, format: Option<Format>, net: &Net) -> Result<(), String> {
+    let format = format.map_or_else(|| Format::of(path), Ok)?;
+    let text = format.print(net)?;
+    match path {
+        STDIO => writeln!(std::io::stdout(), "{}", text.trim_end()),
+        _ => std::fs::write(path, text),
+    }
+    .map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Names of the strategies, like in the debugger.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum StrategyName {
+    Erase,
+    Annihilate,
+    Fifo,
+    Lifo,
+    Random,
+    Outermost,
+    Fair,
+}
+
+impl From<StrategyName> for Strategy {
+    fn from(name: StrategyName) -> Strategy {
+        match name {
+            StrategyName::Erase => Strategy::PreferErase,
+            StrategyName::Annihilate => Strategy::PreferAnnihilate,
+            StrategyName::Fifo => Strategy::Fifo,
+            StrategyName::Lifo => Strategy::Lifo,
+            StrategyName::Random => Strategy::Random,
+            StrategyName::Outermost => Strategy::Outermost,
+            StrategyName::Fair => Strategy::Fair,
+        }
+    }
+}
+
+impl Inputs {
+    /// Returns the nets with their name, examples first.
+    pub fn nets(&self) -> Result<Vec<(String, Net)>, String> {
+        let mut nets = Vec::new();
+        for name in &self.example {
+            nets.push((name.clone(), lafont::gen::example(name)?));
+        }
+        for path in &self.files {
+            nets.push((path.clone(), read(path, self.from)?));
+        }
+        Ok(nets)
+    }
+
+    /// Same as [`Inputs::nets`] but returns the `loop` example if there are no nets.
+    pub fn nets_or_loop(&self) -> Result<Vec<(String, Net)>, String> {
+        let mut nets = self.nets()?;
+        if nets.is_empty() {
+            nets.push(("loop".to_string(), lafont::gen::example("loop")?));
+        }
+        Ok(nets)
+    }
+}
+
+/// Reduces each net with each strategy and prints the number of steps and their rate.
+pub fn bench(args: &BenchArgs) -> Result<(), String> {
+    println!("net,strategy,steps,peak agents,seconds,steps per second");
+    for (name, net) in args.inputs.nets_or_loop()? {
+        for &strategy in &args.strategies {
+            let mut net = net.clone();
+            let start = Instant::now();
+            let stats = net.reduce_with(strategy.into(), args.steps);
+            let seconds = start.elapsed().as_secs_f64();
+            let rate = stats.steps as f64 / seconds;
+            let strategy = strategy.to_possible_value().unwrap();
+            let strategy = strategy.get_name();
+            let peak = stats.peak_agents;
+            println!(
+                "{name},{strategy},{},{peak},{seconds:.3},{rate:.0}",
+                stats.steps
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Reduces each net with all the strategies at once, one thread each, and prints a table of the
+/// number of steps, the peak number of agents, and the time of each strategy.
+///
+/// Interaction nets are strongly confluent, so the table ends with whether all the strategies
+/// reached the same net, which they do unless a reduction stopped early.
+pub fn compare(args: &CompareArgs) -> Result<(), String> {
+    for (name, net) in args.inputs.nets_or_loop()? {
+        let results: Vec<_> = std::thread::scope(|scope| {
+            let threads: Vec<_> = (args.strategies.iter())
+                .map(|&strategy| {
+                    let mut net = net.clone();
+                    scope.spawn(move || {
+                        let start = Instant::now();
+                        let stats = net.reduce_with(strategy.into(), args.steps);
+                        (strategy, stats, start.elapsed().as_secs_f64(), net)
+                    })
+                })
+                .collect();
+            threads.into_iter().map(|t| t.join().unwrap()).collect()
+        });
+        println!("{}", name);
+        println!(
+            "  {:<12}{:>12}{:>14}{:>10}",
+            "strategy", "steps", "peak agents", "seconds"
+        );
+        for (strategy, stats, seconds, _) in &results {
+            let strategy = strategy.to_possible_value().unwrap();
+            println!(
+                "  {:<12}{:>12}{:>14}{:>10.3}",
+                strategy.get_name(),
+                stats.steps,
+                stats.peak_agents,
+                seconds
+            );
+        }
+        let mut nets = results.iter().map(|x| &x.3);
+        let first = nets.next();
+        match nets.all(|net| first.is_some_and(|first| first.isomorphic(net))) {
+            true => println!("  all strategies reached the same net"),
+            false => println!("  strategies reached different nets"),
+        }
+    }
+    Ok(())
+}
+
+/// Reads a net in a format and writes it in another.
+pub fn convert(args: &ConvertArgs) -> Result<(), String> {
+    let net = read(&args.file, args.from)?;
+    write(args.out.as_deref().unwrap_or(STDIO), Some(args.to), &net)
+}
@@ -0,0 +1,123 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Agent, Net, NetError, Port, Wire};
+use std::collections::HashMap;
+
+impl Net {
+    /// Glues `self` and `other` along their free ports.
+    ///
+    /// Each pair `(x, y)` of `wiring` glues the free port `x` of `self` with the free port `y` of
+    /// `other`: what was connected to `x` gets connected to what was connected to `y`, and both
+    /// free ports disappear. What was connected to a free port glued to an unconnected one is left
+    /// unconnected. The ids of `self` are kept while the ids of `other` are shifted by
+    /// the [`Net::next_id`] of `self`. Free ports that are not glued stay free. The result writes
+    /// to the tracer of `self` if any, see [`Net::set_tracer`].
+    ///
+    /// Fails if the nets have different rule sets, or if `wiring` refers to a free port that
+    /// does not exist or more than once.
+    pub fn compose(&self, other: &Net, wiring: &[(usize, usize)]) -> Result<Net, NetError> {
+        if self.rules != other.rules {
+            return Err(NetError::DifferentRules);
+        }
+        let shift = self.next;
+        let moved = |x: Port| Port::new(x.agent + shift, x.port);
+        let mut net = Net {
+            rules: self.rules.clone(),
+            agents: self.agents.clone(),
+            free: self.free.clone(),
+            names: self.names.clone(),
+            next: shift + other.next,
+            worklist: Default::default(),
+            journal: None,
+            cost: self.cost.clone(),
+            pool: Vec::new(),
+            tracer: self.tracer.clone(),
+            engine: self.engine,
+        };
+        for (&a, agent) in &other.agents {
+            let agent = Agent::new(agent.symbol, agent.ports().map(|x| x.map(moved)));
+            net.agents.insert(a + shift, agent);
+        }
+        for (&a, &x) in &other.free {
+            net.free.insert(a + shift, Wire::new(x.get().map(moved)));
+        }
+        for (&a, x) in &other.names {
+            net.names.insert(a + shift, x.clone());
+        }
+        for (a, b) in net.active_pairs() {
+            let queue = net.queue(a, b);
+            net.worklist.push(a, b, queue);
+        }
+        let mut link = HashMap::new();
+        for &(x, y) in wiring {
+            if !self.free.contains_key(&x) {
+                return Err(NetError::NotFree(x));
+            }
+            if !other.free.contains_key(&y) {
+                return Err(NetError::NotFree(y));
+            }
+            for (x, y) in [(x, y + shift), (y + shift, x)] {
+                if link.insert(x, y).is_some() {
+                    return Err(NetError::GluedTwice(x));
+                }
+            }
+        }
+        // A glued free port may be connected to another glued free port, so we follow the chain
+        // until reaching a port that stays. Each wire is found from both of its ends. A chain
+        // ending at an unconnected free port leaves its start unconnected.
+        let mut wires = Vec::new();
+        let mut unconnected = Vec::new();
+        for &g in link.keys() {
+            let p = match net.free[&g].get() {
+                Some(p) if !link.contains_key(&p.agent) => p,
+                _ => continue,
+            };
+            let mut h = link[&g];
+            loop {
+                match net.free[&h].get() {
+                    None => {
+                        unconnected.push(p);
+                        break;
+                    }
+                    Some(q) => match link.get(&q.agent) {
+                        Some(&k) => h = k,
+                        None => {
+                            if p < q {
+                                wires.push((p, q));
+                            }
+                            break;
+                        }
+                    },
+                }
+            }
+        }
+        for g in link.keys() {
+            net.free.remove(g);
+            net.names.remove(g);
+        }
+        for p in unconnected {
+            match net.agents.get_mut(&p.agent) {
+                Some(agent) => {
+                    agent.set_port(p.port, None);
+                }
+                None => *net.free.get_mut(&p.agent).unwrap() = Wire::NONE,
+            }
+        }
+        for (x, y) in wires {
+            net.connect_unchecked(x, y);
+        }
+        Ok(net)
+    }
+}
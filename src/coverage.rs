@@ -0,0 +1,77 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Coverage of the rules of an interaction system by reductions.
+//!
+//! When developing a new interaction system, rules that never fire over a test suite are either
+//! untested or unreachable. The statistics of all the reductions of the suite are merged with
+//! [`ReductionStats::merge`] and compared to the rule set.
+
+use crate::{ReductionStats, RuleSet, Symbol};
+use std::fmt::Write;
+
+/// Number of times each rule of a rule set fired, see [`RuleSet::coverage`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Coverage {
+    /// Number of reduced active pairs per rule, smallest symbol first, in order.
+    pub counts: Vec<((Symbol, Symbol), usize)>,
+}
+
+impl Coverage {
+    /// Returns the rules that never fired, smallest symbol first, in order.
+    pub fn unused(&self) -> impl Iterator<Item = (Symbol, Symbol)> + '_ {
+        self.counts.iter().filter(|x| x.1 == 0).map(|x| x.0)
+    }
+
+    /// Returns the number of rules that fired at least once.
+    pub fn fired(&self) -> usize {
+        self.counts.iter().filter(|x| x.1 > 0).count()
+    }
+
+    /// Returns whether all rules fired.
+    pub fn is_complete(&self) -> bool {
+        self.unused().next().is_none()
+    }
+
+    /// Returns a report of the coverage with the symbol names of `rules`.
+    ///
+    /// The first line tells how many rules fired, and the following lines how many times each
+    /// rule fired, or never.
+    pub fn report(&self, rules: &RuleSet) -> String {
+        let mut report = String::new();
+        let (fired, total) = (self.fired(), self.counts.len());
+        writeln!(report, "coverage: {} of {} rules fired", fired, total).unwrap();
+        for &((x, y), count) in &self.counts {
+            let (x, y) = (rules.name(x), rules.name(y));
+            match count {
+                0 => writeln!(report, "  {}-{}: never", x, y).unwrap(),
+                _ => writeln!(report, "  {}-{}: {}", x, y, count).unwrap(),
+            }
+        }
+        report
+    }
+}
+
+impl RuleSet {
+    /// Returns how many times each rule of the rule set fired in the reductions of `stats`.
+    pub fn coverage(&self, stats: &ReductionStats) -> Coverage {
+        let mut counts: Vec<_> = self
+            .rules()
+            .filter(|&(x, y, _)| x <= y)
+            .map(|(x, y, _)| ((x, y), stats.count(x, y)))
+            .collect();
+        counts.sort_unstable();
+        Coverage { counts }
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Time series of statistics in CSV.
+
+use lafont::{Net, Symbol};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::Instant;
+
+/// Writes a row of statistics every so often.
+///
+/// The columns are the step, the seconds since the start, the frame rate (empty in headless
+/// mode), the number of agents in total and per symbol, the number of active pairs, and the
+/// number of reductions in total and per rule since the start.
+pub struct StatsCsv {
+    file: BufWriter<File>,
+    start: Instant,
+    /// Symbols of the columns, empty until the header is written.
+    symbols: Vec<Symbol>,
+    /// Pairs of symbols with a rule of the columns.
+    pairs: Vec<(Symbol, Symbol)>,
+}
+
+impl StatsCsv {
+    pub fn create(path: &str) -> Result<StatsCsv, String> {
+        let file = File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(StatsCsv {
+            file: BufWriter::new(file),
+            start: Instant::now(),
+            symbols: Vec::new(),
+            pairs: Vec::new(),
+        })
+    }
+
+    /// Writes the header with the symbols and rules of `net`.
+    fn header(&mut self, net: &Net) -> std::io::Result<()> {
+        let rules = net.rules();
+        let mut symbols: Vec<_> = rules.symbols().collect();
+        symbols.sort();
+        let mut pairs = Vec::new();
+        for (i, &x) in symbols.iter().enumerate() {
+            pairs.extend(symbols[i..].iter().map(|&y| (x, y)));
+        }
+        pairs.retain(|&(x, y)| rules.rule(x, y).is_some());
+        write!(self.file, "step,seconds,fps,agents")?;
+        for &x in &symbols {
+            write!(self.file, ",{}", rules.name(x))?;
+        }
+        write!(self.file, ",active_pairs,reductions")?;
+        for &(x, y) in &pairs {
+            write!(self.file, ",{}-{}", rules.name(x), rules.name(y))?;
+        }
+        writeln!(self.file)?;
+        self.symbols = symbols;
+        self.pairs = pairs;
+        Ok(())
+    }
+
+    /// Writes the statistics of `net` after `step` steps.
+    ///
+    /// The `rules` count the reductions per pair of symbols, smallest symbol first.
+    pub fn row(
+        &mut self,
+        net: &Net,
+        step: usize,
+        rules: &HashMap<(Symbol, Symbol), usize>,
+        fps: Option<f32>,
+    ) -> std::io::Result<()> {
+        if self.symbols.is_empty() {
+            self.header(net)?;
+        }
+        let mut agents = HashMap::new();
+        for a in net.agents() {
+            *agents.entry(net.symbol(a).unwrap()).or_insert(0) += 1;
+        }
+        let seconds = self.start.elapsed().as_secs_f32();
+        let fps = fps.map_or(String::new(), |x| format!("{:.1}", x));
+        write!(self.file, "{},{:.3},{},{}", step, seconds, fps, net.len())?;
+        for x in &self.symbols {
+            write!(self.file, ",{}", agents.get(x).unwrap_or(&0))?;
+        }
+        let reductions: usize = rules.values().sum();
+        let pairs_count = net.active_pairs().len();
+        write!(self.file, ",{},{}", pairs_count, reductions)?;
+        for x in &self.pairs {
+            write!(self.file, ",{}", rules.get(x).unwrap_or(&0))?;
+        }
+        writeln!(self.file)
+    }
+}
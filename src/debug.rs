@@ -0,0 +1,215 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interactive debugger.
+
+use lafont::{Net, NetObserver, PersistentNet, Port, Strategy, Symbol};
+use std::collections::BTreeSet;
+use std::io::{BufRead, Write};
+
+const HELP: &str = "commands:
+  step [N]     reduce N active pairs (default 1)
+  show         print the agents and their wires
+  active       print the active pairs
+  watch NODE   toggle reporting changes to NODE
+  undo         undo the last step
+  strategy S   select active pairs with S: erase, annihilate, fifo, lifo, random, outermost, or fair
+  fork         save the current state as a new branch
+  branches     list the branches
+  switch N     continue from branch N
+  quit         exit";
+
+/// What happened during a reduction step.
+#[derive(Default)]
+struct Events {
+    rewrites: Vec<((Symbol, Symbol), usize, usize)>,
+    created: Vec<usize>,
+    deleted: Vec<usize>,
+    connected: Vec<(Port, Port)>,
+}
+
+impl NetObserver for Events {
+    fn on_create(&mut self, a: usize, _symbol: Symbol) {
+        self.created.push(a);
+    }
+
+    fn on_delete(&mut self, a: usize) {
+        self.deleted.push(a);
+    }
+
+    fn on_connect(&mut self, x: Port, y: Port) {
+        self.connected.push((x, y));
+    }
+
+    fn on_rewrite(&mut self, rule: (Symbol, Symbol), a: usize, b: usize) {
+        self.rewrites.push((rule, a, b));
+    }
+}
+
+struct Debugger {
+    net: PersistentNet,
+    watched: BTreeSet<usize>,
+    strategy: Strategy,
+    /// States before each step, to undo them.
+    history: Vec<PersistentNet>,
+    /// Saved states, with their history.
+    branches: Vec<(PersistentNet, Vec<PersistentNet>)>,
+}
+
+impl Debugger {
+    fn port(x: Port) -> String {
+        format!("{}.{}", x.agent, x.port)
+    }
+
+    fn name(&self, symbol: Symbol) -> &str {
+        self.net.rules().name(symbol)
+    }
+
+    fn step(&mut self, count: usize) {
+        for _ in 0..count {
+            let mut events = Events::default();
+            let before = self.net.clone();
+            self.net
+                .reduce_observed(self.strategy, Some(1), &mut events);
+            let ((x, y), a, b) = match events.rewrites.pop() {
+                None => {
+                    println!("no active pair: {}", self.net.status());
+                    return;
+                }
+                Some(rewrite) => rewrite,
+            };
+            self.history.push(before);
+            println!("{}-{} on {}-{}", self.name(x), self.name(y), a, b);
+            println!("  created {:?}", events.created);
+            println!("  deleted {:?}", events.deleted);
+            for &w in &self.watched {
+                if events.deleted.contains(&w) {
+                    println!("  watched {} was deleted", w);
+                }
+                for &(x, y) in &events.connected {
+                    if x.agent == w || y.agent == w {
+                        let (x, y) = (Debugger::port(x), Debugger::port(y));
+                        println!("  watched {} was connected: {} - {}", w, x, y);
+                    }
+                }
+            }
+        }
+    }
+
+    fn show(&self) {
+        let mut agents: Vec<_> = self.net.agents().collect();
+        agents.sort_unstable();
+        for a in agents {
+            let symbol = self.net.symbol(a).unwrap();
+            print!("{} {}:", a, self.name(symbol));
+            for p in 0..=self.net.rules().arity(symbol) {
+                match self.net.get_port(Port::new(a, p)) {
+                    Ok(y) => print!(" {}", Debugger::port(y)),
+                    Err(_) => print!(" -"),
+                }
+            }
+            println!();
+        }
+    }
+
+    fn active(&self) {
+        for (a, b) in self.net.active_pairs() {
+            let name = |x| self.name(self.net.symbol(x).unwrap());
+            println!("{}-{} ({}-{})", a, b, name(a), name(b));
+        }
+    }
+
+    fn execute(&mut self, line: &str) -> Result<bool, String> {
+        let words: Vec<_> = line.split_whitespace().collect();
+        let number = |x: &str| x.parse().map_err(|_| format!("invalid number {:?}", x));
+        match words[..] {
+            [] => (),
+            ["step"] => self.step(1),
+            ["step", n] => self.step(number(n)?),
+            ["show"] => self.show(),
+            ["active"] => self.active(),
+            ["watch", a] => {
+                let a = number(a)?;
+                if !self.watched.insert(a) {
+                    self.watched.remove(&a);
+                }
+            }
+            ["undo"] => match self.history.pop() {
+                None => println!("nothing to undo"),
+                Some(net) => self.net = net,
+            },
+            ["strategy", name] => {
+                self.strategy = match name {
+                    "erase" => Strategy::PreferErase,
+                    "annihilate" => Strategy::PreferAnnihilate,
+                    "fifo" => Strategy::Fifo,
+                    "lifo" => Strategy::Lifo,
+                    "random" => Strategy::Random,
+                    "outermost" => Strategy::Outermost,
+                    "fair" => Strategy::Fair,
+                    _ => return Err(format!("unknown strategy {:?}", name)),
+                }
+            }
+            // Forking only copies the pending active pairs, the agents are shared.
+            ["fork"] => {
+                let branch = (self.net.clone(), self.history.clone());
+                self.branches.push(branch);
+                println!("branch {}", self.branches.len() - 1);
+            }
+            ["branches"] => {
+                for (i, (net, history)) in self.branches.iter().enumerate() {
+                    println!("{}: {} steps, {} agents", i, history.len(), net.len());
+                }
+            }
+            ["switch", n] => {
+                let (net, history) = self
+                    .branches
+                    .get(number(n)?)
+                    .ok_or_else(|| format!("no branch {}", n))?;
+                self.net = net.clone();
+                self.history = history.clone();
+            }
+            ["quit"] => return Ok(false),
+            ["help"] => println!("{}", HELP),
+            _ => return Err(format!("unknown command {:?}, try help", line.trim())),
+        }
+        Ok(true)
+    }
+}
+
+/// Runs the debugger on `net` until `quit` or the end of the input.
+pub fn run(net: Net) {
+    let mut debugger = Debugger {
+        net: PersistentNet::from(&net),
+        watched: BTreeSet::new(),
+        strategy: Strategy::PreferErase,
+        history: Vec::new(),
+        branches: Vec::new(),
+    };
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("> ");
+        std::io::stdout().flush().unwrap();
+        let line = match lines.next() {
+            None => break,
+            Some(line) => line.unwrap(),
+        };
+        match debugger.execute(&line) {
+            Ok(true) => (),
+            Ok(false) => break,
+            Err(e) => println!("error: {}", e),
+        }
+    }
+}
@@ -0,0 +1,441 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! SVG and TikZ export of 2D diagrams.
+//!
+//! Agents are drawn as usual in the literature: triangles whose apex is the principal port,
+//! marked with a dot, and whose base holds the auxiliary ports from left to right. Agents
+//! without auxiliary ports are circles. Each agent points its principal port toward the node it
+//! is connected to, such that active pairs face each other. Wires are curves leaving the ports
+//! in the direction of the agent. Free ports are small circles labeled with their name.
+
+use crate::{Net, Port};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+
+/// How [`Net::to_svg`] and [`Net::to_tikz`] place the nodes in the plane.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DiagramLayout {
+    /// Nodes in rows by distance to the free ports, which are in the top row.
+    ///
+    /// Components without free ports start from their smallest agent.
+    #[default]
+    Layered,
+    /// Nodes evenly on a circle, by id.
+    Circle,
+}
+
+/// Distance between nodes, in the unit of the diagram.
+const SPACING: f64 = 1.5;
+
+/// Distance from the center of a triangle to its apex.
+const APEX: f64 = 0.35;
+
+/// Distance from the center of a triangle to its base.
+const BASE: f64 = 0.25;
+
+/// Half width of the base of a triangle.
+const HALF: f64 = 0.3;
+
+/// Radius of the circle of agents without auxiliary ports.
+const RADIUS: f64 = 0.2;
+
+/// Radius of the dot marking principal ports, and of the circle of free ports.
+const DOT: f64 = 0.05;
+
+/// Pixels per unit in SVG.
+const SCALE: f64 = 40.;
+
+type Point = (f64, f64);
+
+/// Outline of a node.
+enum Shape {
+    Triangle([Point; 3]),
+    Circle(f64),
+    /// Free port.
+    Free,
+}
+
+/// Node of the diagram.
+struct Figure {
+    center: Point,
+    shape: Shape,
+    label: String,
+    /// Principal port, marked with a dot, for agents.
+    principal: Option<Point>,
+}
+
+/// Nodes and wires in the plane.
+struct Diagram {
+    figures: Vec<Figure>,
+    /// Cubic Bézier curves from a port to the other.
+    wires: Vec<[Point; 4]>,
+}
+
+impl Diagram {
+    /// Returns the smallest and largest coordinates of the diagram.
+    fn bounds(&self) -> (Point, Point) {
+        let mut min = (f64::INFINITY, f64::INFINITY);
+        let mut max = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        let mut add = |(x, y): Point| {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        };
+        for f in &self.figures {
+            let r = match f.shape {
+                Shape::Triangle(_) => APEX,
+                Shape::Circle(r) => r,
+                Shape::Free => DOT,
+            };
+            add((f.center.0 - r - 0.5, f.center.1 - r));
+            add((f.center.0 + r + 0.5, f.center.1 + r + 0.3));
+        }
+        for wire in &self.wires {
+            wire.iter().for_each(|&p| add(p));
+        }
+        if self.figures.is_empty() {
+            return ((0., 0.), (0., 0.));
+        }
+        (min, max)
+    }
+}
+
+impl Net {
+    /// Returns a 2D diagram of the net in SVG.
+    ///
+    /// The diagram is black and white, and agents are labeled with γ, δ, and ε for the
+    /// combinators, and with their symbol name otherwise.
+    pub fn to_svg(&self, layout: DiagramLayout) -> String {
+        let diagram = self.diagram(layout);
+        let ((x0, y0), (x1, y1)) = diagram.bounds();
+        // The y axis of SVG goes down.
+        let p = |(x, y): Point| (SCALE * (x - x0 + 0.5), SCALE * (y1 - y + 0.5));
+        let mut svg = String::new();
+        let (width, height) = (SCALE * (x1 - x0 + 1.), SCALE * (y1 - y0 + 1.));
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" \
+             font-family=\"serif\" font-size=\"{}\" text-anchor=\"middle\" \
+             dominant-baseline=\"central\">",
+            width,
+            height,
+            0.4 * SCALE
+        )
+        .unwrap();
+        writeln!(svg, "<g fill=\"none\" stroke=\"black\">").unwrap();
+        for &[a, b, c, d] in &diagram.wires {
+            let [a, b, c, d] = [p(a), p(b), p(c), p(d)];
+            writeln!(
+                svg,
+                "<path d=\"M {:.1} {:.1} C {:.1} {:.1} {:.1} {:.1} {:.1} {:.1}\"/>",
+                a.0, a.1, b.0, b.1, c.0, c.1, d.0, d.1
+            )
+            .unwrap();
+        }
+        writeln!(svg, "</g>").unwrap();
+        for f in &diagram.figures {
+            let (x, y) = p(f.center);
+            match &f.shape {
+                Shape::Triangle(corners) => {
+                    let points: Vec<_> = corners
+                        .iter()
+                        .map(|&c| p(c))
+                        .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+                        .collect();
+                    writeln!(
+                        svg,
+                        "<polygon points=\"{}\" fill=\"white\" stroke=\"black\"/>",
+                        points.join(" ")
+                    )
+                    .unwrap();
+                }
+                Shape::Circle(r) => writeln!(
+                    svg,
+                    "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"white\" stroke=\"black\"/>",
+                    x,
+                    y,
+                    SCALE * r
+                )
+                .unwrap(),
+                Shape::Free => writeln!(
+                    svg,
+                    "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"white\" stroke=\"black\"/>",
+                    x,
+                    y,
+                    SCALE * DOT
+                )
+                .unwrap(),
+            }
+            if let Some(principal) = f.principal {
+                let (x, y) = p(principal);
+                writeln!(
+                    svg,
+                    "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\"/>",
+                    x,
+                    y,
+                    SCALE * DOT
+                )
+                .unwrap();
+            }
+            // Free ports are labeled above.
+            let y = match f.shape {
+                Shape::Free => y - 0.3 * SCALE,
+                _ => y,
+            };
+            let label = escape(&f.label);
+            writeln!(svg, "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>", x, y, label).unwrap();
+        }
+        writeln!(svg, "</svg>").unwrap();
+        svg
+    }
+
+    /// Returns a 2D diagram of the net as a TikZ picture, in centimeters.
+    ///
+    /// The picture is meant to be included in a LaTeX document using the `tikz` package, like
+    /// [`Net::to_svg`] otherwise.
+    pub fn to_tikz(&self, layout: DiagramLayout) -> String {
+        let diagram = self.diagram(layout);
+        let mut tikz = String::new();
+        writeln!(tikz, "\\begin{{tikzpicture}}").unwrap();
+        for [a, b, c, d] in &diagram.wires {
+            writeln!(
+                tikz,
+                "  \\draw ({:.2},{:.2}) .. controls ({:.2},{:.2}) and ({:.2},{:.2}) .. ({:.2},{:.2});",
+                a.0, a.1, b.0, b.1, c.0, c.1, d.0, d.1
+            )
+            .unwrap();
+        }
+        for f in &diagram.figures {
+            let (x, y) = f.center;
+            match &f.shape {
+                Shape::Triangle([a, b, c]) => writeln!(
+                    tikz,
+                    "  \\filldraw[fill=white] ({:.2},{:.2}) -- ({:.2},{:.2}) -- ({:.2},{:.2}) -- cycle;",
+                    a.0, a.1, b.0, b.1, c.0, c.1
+                )
+                .unwrap(),
+                Shape::Circle(r) => writeln!(
+                    tikz,
+                    "  \\filldraw[fill=white] ({:.2},{:.2}) circle ({:.2});",
+                    x, y, r
+                )
+                .unwrap(),
+                Shape::Free => writeln!(
+                    tikz,
+                    "  \\filldraw[fill=white] ({:.2},{:.2}) circle ({:.2});",
+                    x, y, DOT
+                )
+                .unwrap(),
+            }
+            if let Some((x, y)) = f.principal {
+                writeln!(tikz, "  \\fill ({:.2},{:.2}) circle ({:.2});", x, y, DOT).unwrap();
+            }
+            let label = match f.label.as_str() {
+                "γ" => "$\\gamma$".to_string(),
+                "δ" => "$\\delta$".to_string(),
+                "ε" => "$\\varepsilon$".to_string(),
+                label => label.replace('_', "\\_"),
+            };
+            let anchor = match f.shape {
+                Shape::Free => "[above]",
+                _ => "",
+            };
+            writeln!(
+                tikz,
+                "  \\node{} at ({:.2},{:.2}) {{\\small {}}};",
+                anchor, x, y, label
+            )
+            .unwrap();
+        }
+        writeln!(tikz, "\\end{{tikzpicture}}").unwrap();
+        tikz
+    }
+
+    /// Places the nodes and wires of the net in the plane.
+    fn diagram(&self, layout: DiagramLayout) -> Diagram {
+        let positions = match layout {
+            DiagramLayout::Layered => self.layers(),
+            DiagramLayout::Circle => self.circle(),
+        };
+        // Agents point their principal port toward the node it is connected to, and up otherwise.
+        let direction = |a: usize| {
+            let (x, y) = positions[&a];
+            let target = match self.symbol(a) {
+                Some(_) => self.agents[&a].port(0),
+                None => self.free[&a].get(),
+            };
+            let (dx, dy) = match target {
+                Some(t) => (positions[&t.agent].0 - x, positions[&t.agent].1 - y),
+                None => (0., 1.),
+            };
+            let norm = dx.hypot(dy);
+            if norm < 1e-9 {
+                (0., 1.)
+            } else {
+                (dx / norm, dy / norm)
+            }
+        };
+        // Returns the position of a port and the direction of its wire.
+        let port = |Port { agent, port }: Port| {
+            let (x, y) = positions[&agent];
+            let d = direction(agent);
+            // The auxiliary ports go from left to right when the agent points up.
+            let at = |u: f64, v: f64| (x + u * d.1 + v * d.0, y - u * d.0 + v * d.1);
+            match self.symbol(agent).map(|s| self.rules.arity(s)) {
+                None => ((x, y), d),
+                Some(0) => (at(0., RADIUS), d),
+                Some(_) if port == 0 => (at(0., APEX), d),
+                Some(n) => {
+                    let u = -HALF + 2. * HALF * port as f64 / (n + 1) as f64;
+                    (at(u, -BASE), (-d.0, -d.1))
+                }
+            }
+        };
+        let mut ids: Vec<_> = positions.keys().copied().collect();
+        ids.sort_unstable();
+        let mut diagram = Diagram {
+            figures: Vec::new(),
+            wires: Vec::new(),
+        };
+        for &a in &ids {
+            let center = positions[&a];
+            let (x, y) = center;
+            let d = direction(a);
+            let at = |u: f64, v: f64| (x + u * d.1 + v * d.0, y - u * d.0 + v * d.1);
+            let figure = match self.symbol(a) {
+                None => Figure {
+                    center,
+                    shape: Shape::Free,
+                    label: self.name(a).unwrap_or_default().to_string(),
+                    principal: None,
+                },
+                Some(symbol) => {
+                    let shape = match self.rules.arity(symbol) {
+                        0 => Shape::Circle(RADIUS),
+                        _ => Shape::Triangle([at(0., APEX), at(-HALF, -BASE), at(HALF, -BASE)]),
+                    };
+                    Figure {
+                        center,
+                        shape,
+                        label: self.rules.letter(symbol).to_string(),
+                        principal: Some(port(Port::new(a, 0)).0),
+                    }
+                }
+            };
+            diagram.figures.push(figure);
+            let ports: Vec<_> = match self.symbol(a) {
+                Some(_) => self.agents[&a].ports().collect(),
+                None => vec![self.free[&a].get()],
+            };
+            for (p, y) in ports.into_iter().enumerate() {
+                let x = Port::new(a, p);
+                let y = match y {
+                    Some(y) if x < y => y,
+                    _ => continue,
+                };
+                let ((start, u), (end, v)) = (port(x), port(y));
+                let length = (end.0 - start.0).hypot(end.1 - start.1).clamp(0.5, SPACING) / 2.;
+                diagram.wires.push([
+                    start,
+                    (start.0 + length * u.0, start.1 + length * u.1),
+                    (end.0 + length * v.0, end.1 + length * v.1),
+                    end,
+                ]);
+            }
+        }
+        diagram
+    }
+
+    /// Places the nodes in rows by distance to the free ports, top row first.
+    fn layers(&self) -> HashMap<usize, (f64, f64)> {
+        let neighbors = |a: usize| -> Vec<usize> {
+            match self.agents.get(&a) {
+                Some(agent) => agent.ports().flatten().map(|x| x.agent).collect(),
+                None => self.free[&a].get().map(|x| x.agent).into_iter().collect(),
+            }
+        };
+        let mut depths = HashMap::new();
+        let mut todo: VecDeque<_> = self.free.keys().copied().collect();
+        for &f in &todo {
+            depths.insert(f, 0);
+        }
+        let mut agents: Vec<_> = self.agents.keys().copied().collect();
+        agents.sort_unstable();
+        let mut agents = agents.into_iter();
+        loop {
+            while let Some(a) = todo.pop_front() {
+                let depth = depths[&a] + 1;
+                for b in neighbors(a) {
+                    depths.entry(b).or_insert_with(|| {
+                        todo.push_back(b);
+                        depth
+                    });
+                }
+            }
+            // Components without free ports go below, from their smallest agent.
+            let Some(root) = agents.find(|a| !depths.contains_key(a)) else {
+                break;
+            };
+            let depth = depths.values().max().map_or(0, |&d| d + 1);
+            depths.insert(root, depth);
+            todo.push_back(root);
+        }
+        let mut rows: Vec<Vec<usize>> = Vec::new();
+        let mut ids: Vec<_> = depths.keys().copied().collect();
+        ids.sort_unstable();
+        for a in ids {
+            let depth = depths[&a];
+            if rows.len() <= depth {
+                rows.resize(depth + 1, Vec::new());
+            }
+            rows[depth].push(a);
+        }
+        let mut positions = HashMap::new();
+        for (depth, row) in rows.iter().enumerate() {
+            let offset = (row.len() as f64 - 1.) / 2.;
+            for (i, &a) in row.iter().enumerate() {
+                let position = ((i as f64 - offset) * SPACING, -(depth as f64) * SPACING);
+                positions.insert(a, position);
+            }
+        }
+        positions
+    }
+
+    /// Places the nodes evenly on a circle by id, starting at the top and going clockwise.
+    fn circle(&self) -> HashMap<usize, (f64, f64)> {
+        let mut ids: Vec<_> = self
+            .free
+            .keys()
+            .chain(self.agents.keys())
+            .copied()
+            .collect();
+        ids.sort_unstable();
+        let n = ids.len() as f64;
+        // Neighbors on the circle are SPACING apart.
+        let radius = (SPACING * n / std::f64::consts::TAU).max(SPACING / 2.);
+        let mut positions = HashMap::new();
+        for (i, a) in ids.into_iter().enumerate() {
+            let angle = std::f64::consts::TAU * i as f64 / n;
+            positions.insert(a, (radius * angle.sin(), radius * angle.cos()));
+        }
+        positions
+    }
+}
+
+/// Escapes the characters of `text` with a meaning in XML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,199 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Comparison of nets.
+//!
+//! Nets are compared either up to renaming with [`Net::isomorphic`], or id by id with
+//! [`Net::diff`]. Their normal forms are compared with [`Net::equiv`].
+
+use crate::{Net, Port, Symbol};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// A difference between two nets, as returned by [`Net::diff`].
+///
+/// Nodes are agents given by their symbol, or free ports given by `None`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum Difference {
+    /// The node only exists in the first net.
+    Removed(usize, Option<Symbol>),
+    /// The node only exists in the second net.
+    Added(usize, Option<Symbol>),
+    /// The node has a different symbol in each net.
+    Changed(usize, Option<Symbol>, Option<Symbol>),
+    /// The port is connected differently in each net.
+    Rewired(Port, Option<Port>, Option<Port>),
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let node = |x: Option<Symbol>| match x {
+            None => "free port".to_string(),
+            Some(Symbol(s)) => format!("symbol {}", s),
+        };
+        let port = |x: Option<Port>| match x {
+            None => "nothing".to_string(),
+            Some(x) => format!("{}.{}", x.agent, x.port),
+        };
+        match *self {
+            Difference::Removed(a, x) => write!(f, "{} {} was removed", node(x), a),
+            Difference::Added(a, x) => write!(f, "{} {} was added", node(x), a),
+            Difference::Changed(a, x, y) => {
+                write!(f, "node {} changed from {} to {}", a, node(x), node(y))
+            }
+            Difference::Rewired(x, y, z) => write!(
+                f,
+                "port {}.{} moved from {} to {}",
+                x.agent,
+                x.port,
+                port(y),
+                port(z)
+            ),
+        }
+    }
+}
+
+/// Element of the canonical description of a connected component.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
+enum Token {
+    /// A node with its symbol, or `None` for free ports, followed by its ports.
+    Node(Option<Symbol>),
+    /// A port connected to the given port of the node at the given traversal index.
+    Port(usize, usize),
+    /// A port connected to nothing.
+    Unconnected,
+    /// A port connected to a node that does not exist.
+    Missing,
+}
+
+impl Net {
+    /// Returns whether the nets are equal up to renaming of agents and free ports.
+    ///
+    /// Nets with different rule sets are never isomorphic.
+    pub fn isomorphic(&self, other: &Net) -> bool {
+        self.rules == other.rules
+            && self.agents.len() == other.agents.len()
+            && self.free.len() == other.free.len()
+            && self.canonical() == other.canonical()
+    }
+
+    /// Returns whether the nets reduce to isomorphic nets.
+    ///
+    /// Returns `None` if one of them still has active pairs after `budget` steps. The nets are
+    /// not modified.
+    pub fn equiv(&self, other: &Net, budget: usize) -> Option<bool> {
+        let mut nets = [self.clone(), other.clone()];
+        for net in &mut nets {
+            net.reduce(Some(budget));
+            if !net.active_pairs().is_empty() {
+                return None;
+            }
+        }
+        Some(nets[0].isomorphic(&nets[1]))
+    }
+
+    /// Returns a description of the net that does not depend on the ids of its nodes.
+    ///
+    /// A connected component is described by a traversal starting from one of its nodes. The
+    /// smallest description among starting nodes with the smallest symbol is kept.
+    fn canonical(&self) -> Vec<Vec<Token>> {
+        let mut nodes: Vec<usize> = self
+            .agents
+            .keys()
+            .chain(self.free.keys())
+            .copied()
+            .collect();
+        nodes.sort_unstable();
+        let mut seen = HashSet::new();
+        let mut components = Vec::new();
+        for a in nodes {
+            if seen.contains(&a) {
+                continue;
+            }
+            let (_, component) = self.traverse(a);
+            let kind = |a: usize| self.node(a).unwrap().0;
+            let smallest = component.iter().map(|&a| kind(a)).min();
+            let starts = component.iter().filter(|&&a| kind(a) == smallest.unwrap());
+            components.push(starts.map(|&a| self.traverse(a).0).min().unwrap());
+            seen.extend(component);
+        }
+        components.sort();
+        components
+    }
+
+    /// Traverses the connected component of `a` breadth-first, following ports in order.
+    ///
+    /// Returns the description of the component and its nodes in traversal order.
+    fn traverse(&self, a: usize) -> (Vec<Token>, Vec<usize>) {
+        let mut tokens = Vec::new();
+        let mut order = vec![a];
+        let mut index = HashMap::new();
+        index.insert(a, 0);
+        let mut i = 0;
+        while i < order.len() {
+            let (kind, ports) = self.node(order[i]).unwrap();
+            tokens.push(Token::Node(kind));
+            for y in ports {
+                tokens.push(match y {
+                    None => Token::Unconnected,
+                    Some(y) if self.node(y.agent).is_none() => Token::Missing,
+                    Some(y) => {
+                        let j = *index.entry(y.agent).or_insert_with(|| {
+                            order.push(y.agent);
+                            order.len() - 1
+                        });
+                        Token::Port(j, y.port)
+                    }
+                });
+            }
+            i += 1;
+        }
+        (tokens, order)
+    }
+
+    /// Returns the differences between the nets, comparing nodes with the same id.
+    ///
+    /// Symbols are compared by index, so the rule sets should be the same. A port is only
+    /// reported as rewired if its node has the same symbol in both nets.
+    pub fn diff(&self, other: &Net) -> Vec<Difference> {
+        let mut nodes: Vec<usize> = self
+            .agents
+            .keys()
+            .chain(self.free.keys())
+            .copied()
+            .collect();
+        nodes.extend(other.agents.keys().chain(other.free.keys()));
+        nodes.sort_unstable();
+        nodes.dedup();
+        let mut differences = Vec::new();
+        for a in nodes {
+            match (self.node(a), other.node(a)) {
+                (None, None) => unreachable!(),
+                (Some((x, _)), None) => differences.push(Difference::Removed(a, x)),
+                (None, Some((y, _))) => differences.push(Difference::Added(a, y)),
+                (Some((x, _)), Some((y, _))) if x != y => {
+                    differences.push(Difference::Changed(a, x, y))
+                }
+                (Some((_, p)), Some((_, q))) => {
+                    for (i, (y, z)) in p.zip(q).enumerate() {
+                        if y != z {
+                            differences.push(Difference::Rewired(Port::new(a, i), y, z));
+                        }
+                    }
+                }
+            }
+        }
+        differences
+    }
+}
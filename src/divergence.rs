@@ -0,0 +1,114 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Estimation of whether a net reaches a normal form.
+//!
+//! Whether a net normalizes is undecidable, so the estimate is a guess: it first looks for
+//! patterns known to diverge, then reduces a copy of the net for a budget of steps and watches
+//! its size. Interaction nets are strongly confluent, so if a net diverges with one strategy,
+//! it diverges with all of them.
+
+use crate::Net;
+use std::fmt;
+
+/// Number of samples of the size of the net during the probe of [`Net::estimate`].
+const SAMPLES: usize = 10;
+
+/// Whether a net reaches a normal form, see [`Net::estimate`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Estimate {
+    /// The net reaches a normal form after `steps` rewrites.
+    Normalizes { steps: usize },
+    /// The net probably never reaches a normal form.
+    ProbablyDiverges(Divergence),
+    /// The net did not reach a normal form within the budget, but did not grow steadily either.
+    Unknown,
+}
+
+/// Why a net probably diverges.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Divergence {
+    /// The agents of the active pair `a`-`b` commute and an auxiliary port of one is connected
+    /// to an auxiliary port of the other, like `(a b) = {a b}`.
+    ///
+    /// The commutation creates the same pattern again, so the net grows forever.
+    SelfCommutation { a: usize, b: usize },
+    /// The number of agents grew at every sample of the probe, from `from` to `to`.
+    Growth { from: usize, to: usize },
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Estimate::Normalizes { steps } => write!(f, "normalizes in {} steps", steps),
+            Estimate::ProbablyDiverges(Divergence::SelfCommutation { a, b }) => {
+                write!(
+                    f,
+                    "probably diverges: agents {} and {} commute forever",
+                    a, b
+                )
+            }
+            Estimate::ProbablyDiverges(Divergence::Growth { from, to }) => {
+                write!(f, "probably diverges: grew from {} to {} agents", from, to)
+            }
+            Estimate::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+impl Net {
+    /// Estimates whether the net reaches a normal form within `budget` steps.
+    ///
+    /// Active pairs that commute forever are reported first, without reducing. Otherwise, a copy
+    /// of the net is reduced for at most `budget` steps, and the net probably diverges if its
+    /// number of agents grew at each of the last samples and at least doubled. The net is not
+    /// modified.
+    pub fn estimate(&self, budget: usize) -> Estimate {
+        if let Some((a, b)) = self.self_commutation() {
+            return Estimate::ProbablyDiverges(Divergence::SelfCommutation { a, b });
+        }
+        let mut net = self.clone();
+        net.journal = None;
+        net.tracer = None;
+        let from = net.len();
+        let mut sizes = vec![from];
+        let mut steps = 0;
+        for i in 0..SAMPLES {
+            let chunk = budget / SAMPLES + usize::from(i < budget % SAMPLES);
+            steps += net.reduce(Some(chunk)).steps;
+            if net.active_pairs().is_empty() {
+                return Estimate::Normalizes { steps };
+            }
+            sizes.push(net.len());
+        }
+        let to = net.len();
+        let growing = sizes[SAMPLES / 2..].windows(2).all(|w| w[0] < w[1]);
+        if growing && to >= 2 * from.max(1) {
+            return Estimate::ProbablyDiverges(Divergence::Growth { from, to });
+        }
+        Estimate::Unknown
+    }
+
+    /// Returns an active pair that commutes with an auxiliary port of one agent connected to an
+    /// auxiliary port of the other.
+    fn self_commutation(&self) -> Option<(usize, usize)> {
+        self.active_pairs().into_iter().find(|&(a, b)| {
+            let (x, y) = (&self.agents[&a], &self.agents[&b]);
+            self.rules.commutes(x.symbol, y.symbol)
+                && x.ports()
+                    .skip(1)
+                    .any(|p| p.is_some_and(|p| p.agent == b && p.port > 0))
+        })
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Graphviz export.
+//!
+//! Agents are drawn with their symbol name and id, free ports as points. Wires attached to a
+//! principal port are bold, and active pairs are red. Such graphs can be read back.
+
+use crate::{Net, Port, Ports, RuleSet, Symbol};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+impl Net {
+    /// Returns the net in Graphviz DOT format.
+    pub fn to_dot(&self) -> String {
+        let mut agents: Vec<_> = self.agents.iter().collect();
+        agents.sort_by_key(|&(&a, _)| a);
+        let mut free: Vec<_> = self.free.iter().map(|(&a, x)| (a, x.get())).collect();
+        free.sort();
+        let agents = agents.into_iter().map(|(&a, x)| (a, x.symbol, x.ports()));
+        to_dot(&self.rules, agents, free)
+    }
+
+    /// Parses a net of interaction combinators written by [`Net::to_dot`].
+    pub fn from_dot(input: &str) -> Result<Net, String> {
+        Net::from_dot_with(RuleSet::combinators(), input)
+    }
+
+    /// Parses a net written by [`Net::to_dot`] for the interaction system `rules`.
+    ///
+    /// Only the labels of the nodes and of the ends of the edges are read, so the ids of the
+    /// agents and free ports are not kept.
+    pub fn from_dot_with(rules: RuleSet, input: &str) -> Result<Net, String> {
+        let mut net = Net::with_rules(rules);
+        let mut ids = HashMap::new();
+        for (i, line) in input.lines().enumerate() {
+            let error = |message: &str| format!("{} at line {}", message, i + 1);
+            let (node, attrs) = match line.trim().split_once(" [") {
+                Some(x) => x,
+                None => continue,
+            };
+            let attr = |name: &str| {
+                let start = attrs.find(&format!("{}=\"", name))? + name.len() + 2;
+                let len = attrs[start..].find('"')?;
+                Some(&attrs[start..start + len])
+            };
+            let id = |node: &str| {
+                let id = node.strip_prefix('n').and_then(|x| x.parse::<usize>().ok());
+                id.ok_or_else(|| error(&format!("invalid node {:?}", node)))
+            };
+            match node.split_once(" -- ") {
+                None if node == "node" => (),
+                None => {
+                    let a = match attr("label").map(|x| x.split_once(' ')) {
+                        Some(None) => net.add_free(),
+                        Some(Some((name, _))) => {
+                            let rules = net.rules();
+                            let symbol = rules.symbols().find(|&s| rules.name(s) == name);
+                            let symbol = symbol.ok_or_else(|| error("unknown symbol"))?;
+                            net.add_agent_unchecked(symbol)
+                        }
+                        None => return Err(error("expected a label")),
+                    };
+                    ids.insert(id(node)?, a);
+                }
+                Some((x, y)) => {
+                    let port = |node: &str, label: Option<&str>| {
+                        let a = ids
+                            .get(&id(node)?)
+                            .ok_or_else(|| error("undeclared node"))?;
+                        let p = match label {
+                            None | Some("") => 0,
+                            Some(p) => p.parse().map_err(|_| error("invalid port"))?,
+                        };
+                        Ok::<_, String>(Port::new(*a, p))
+                    };
+                    let x = port(x, attr("taillabel"))?;
+                    let y = port(y, attr("headlabel"))?;
+                    net.connect(x, y).map_err(|e| error(&e.to_string()))?;
+                }
+            }
+        }
+        Ok(net)
+    }
+}
+
+/// Writes a DOT graph of agents given by their id, symbol, and ports, and of free ports.
+fn to_dot<'a>(
+    rules: &RuleSet,
+    agents: impl IntoIterator<Item = (usize, Symbol, Ports<'a>)>,
+    free: impl IntoIterator<Item = (usize, Option<Port>)>,
+) -> String {
+    let mut dot = String::new();
+    let mut wires = Vec::new();
+    let mut points = HashSet::new();
+    writeln!(dot, "graph net {{").unwrap();
+    writeln!(dot, "  node [style=filled, fillcolor=white];").unwrap();
+    for (a, symbol, ports) in agents {
+        let (shape, color) = match symbol {
+            Symbol::CONSTRUCT => ("triangle", "blue"),
+            Symbol::DUPLICATE => ("invtriangle", "green"),
+            Symbol::ERASE => ("circle", "red"),
+            _ => ("box", "white"),
+        };
+        let name = rules.name(symbol);
+        let attrs = format!("shape={}, fillcolor={}", shape, color);
+        writeln!(dot, "  n{} [label=\"{} {}\", {}];", a, name, a, attrs).unwrap();
+        for (p, x) in ports.enumerate() {
+            wires.extend(x.map(|x| (Port::new(a, p), x)));
+        }
+    }
+    for (a, x) in free {
+        writeln!(dot, "  n{} [label=\"\", shape=point];", a).unwrap();
+        points.insert(a);
+        wires.extend(x.map(|x| (Port::new(a, 0), x)));
+    }
+    let label = |x: Port| {
+        if points.contains(&x.agent) {
+            String::new()
+        } else {
+            x.port.to_string()
+        }
+    };
+    let principal = |x: Port| x.port == 0 && !points.contains(&x.agent);
+    for (x, y) in wires {
+        if x > y {
+            continue;
+        }
+        let mut attrs = format!("taillabel=\"{}\", headlabel=\"{}\"", label(x), label(y));
+        if principal(x) || principal(y) {
+            attrs.push_str(", penwidth=2");
+        }
+        if principal(x) && principal(y) {
+            attrs.push_str(", color=red");
+        }
+        writeln!(dot, "  n{} -- n{} [{}];", x.agent, y.agent, attrs).unwrap();
+    }
+    writeln!(dot, "}}").unwrap();
+    dot
+}
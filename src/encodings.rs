@@ -0,0 +1,263 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Data encoded as lambda terms.
+//!
+//! Values are built as [`Term`]s, combined with the constructors and destructors below, then
+//! compiled with [`lambda::compile`]. Once the net is reduced, the result is read back to a Rust
+//! value. The caveats of [`lambda`] apply: terms that duplicate overlapping terms may not reduce
+//! to the expected value.
+//!
+//! All encodings take the recursive case first and the base case last:
+//!
+//! - Booleans are `\t f. t` and `\t f. f`.
+//! - Church naturals iterate their first argument: 2 is `\s z. s (s z)`.
+//! - Scott naturals give their predecessor to their first argument: 2 is `\s z. s (\s z. s (\s
+//!   z. z))`.
+//! - Church lists fold their first argument: `[a, b]` is `\c n. c a (c b n)`.
+//! - Scott lists give their head and tail to their first argument: `[a, b]` is `\c n. c a (\c
+//!   n. c b (\c n. n))`.
+
+use crate::lambda::{self, Term};
+use crate::{Net, Port};
+
+/// How naturals and lists are encoded.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Encoding {
+    Church,
+    Scott,
+}
+
+fn term(input: &str) -> Term {
+    input.parse().unwrap()
+}
+
+fn var(x: &str) -> Term {
+    Term::Var(x.to_string())
+}
+
+fn lam(x: &str, b: Term) -> Term {
+    Term::Lam(x.to_string(), Box::new(b))
+}
+
+/// Applies `f` to the arguments `xs`.
+pub fn apply(f: Term, xs: impl IntoIterator<Item = Term>) -> Term {
+    xs.into_iter()
+        .fold(f, |f, x| Term::App(Box::new(f), Box::new(x)))
+}
+
+/// The boolean `b`.
+pub fn boolean(b: bool) -> Term {
+    term(if b { r"\t f. t" } else { r"\t f. f" })
+}
+
+/// `\b t f. b f t`
+pub fn not() -> Term {
+    term(r"\b t f. b f t")
+}
+
+/// `\a b. a b false`
+pub fn and() -> Term {
+    term(r"\a b. a b (\t f. f)")
+}
+
+/// `\a b. a true b`
+pub fn or() -> Term {
+    term(r"\a b. a (\t f. t) b")
+}
+
+/// Decodes a boolean in normal form.
+pub fn decode_bool(term: &Term) -> Option<bool> {
+    let (t, f, body) = binders(term)?;
+    match body {
+        Term::Var(x) if x == t && x != f => Some(true),
+        Term::Var(x) if x == f => Some(false),
+        _ => None,
+    }
+}
+
+/// Decodes the boolean connected to the free port `root` of a reduced net.
+pub fn read_bool(net: &Net, root: usize) -> Option<bool> {
+    decode_bool(&net.readback(Port::new(root, 0)).ok()?)
+}
+
+/// Returns the 2 binders and the body of `\x y. b`.
+fn binders(term: &Term) -> Option<(&str, &str, &Term)> {
+    match term {
+        Term::Lam(x, b) => match &**b {
+            Term::Lam(y, b) => Some((x, y, b)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns `x` if `term` is the application `s x` of the variable `s`.
+fn unary<'a>(term: &'a Term, s: &str) -> Option<&'a Term> {
+    match term {
+        Term::App(f, x) if **f == var(s) => Some(x),
+        _ => None,
+    }
+}
+
+impl Encoding {
+    /// The natural `n`.
+    pub fn nat(self, n: u64) -> Term {
+        match self {
+            Encoding::Church => {
+                let body = (0..n).fold(var("z"), |z, _| apply(var("s"), [z]));
+                lam("s", lam("z", body))
+            }
+            Encoding::Scott => {
+                (0..n).fold(self.zero(), |p, _| lam("s", lam("z", apply(var("s"), [p]))))
+            }
+        }
+    }
+
+    pub fn zero(self) -> Term {
+        term(r"\s z. z")
+    }
+
+    pub fn succ(self) -> Term {
+        match self {
+            Encoding::Church => term(r"\n s z. s (n s z)"),
+            Encoding::Scott => term(r"\n s z. s n"),
+        }
+    }
+
+    /// Returns the predecessor of a natural, zero for zero.
+    pub fn pred(self) -> Term {
+        match self {
+            Encoding::Church => term(r"\n s z. n (\g h. h (g s)) (\u. z) (\u. u)"),
+            Encoding::Scott => term(r"\n. n (\p. p) (\s z. z)"),
+        }
+    }
+
+    /// Returns whether a natural is zero, as a boolean.
+    pub fn is_zero(self) -> Term {
+        term(r"\n. n (\p t f. f) (\t f. t)")
+    }
+
+    /// Decodes a natural in normal form.
+    pub fn decode_nat(self, term: &Term) -> Option<u64> {
+        let (s, z, mut body) = binders(term)?;
+        if s == z {
+            return None;
+        }
+        let mut n = 0;
+        loop {
+            match body {
+                Term::Var(x) if x == z => return Some(n),
+                _ => (),
+            }
+            let x = unary(body, s)?;
+            n += 1;
+            body = match self {
+                Encoding::Church => x,
+                Encoding::Scott => return Some(n + self.decode_nat(x)?),
+            };
+        }
+    }
+
+    /// Decodes the natural connected to the free port `root` of a reduced net.
+    pub fn read_nat(self, net: &Net, root: usize) -> Option<u64> {
+        self.decode_nat(&net.readback(Port::new(root, 0)).ok()?)
+    }
+
+    /// The list of `items`.
+    pub fn list(self, items: impl IntoIterator<Item = Term>) -> Term {
+        let items: Vec<_> = items.into_iter().collect();
+        match self {
+            Encoding::Church => {
+                let body = items
+                    .into_iter()
+                    .rev()
+                    .fold(var("n"), |tail, head| apply(var("c"), [head, tail]));
+                lam("c", lam("n", body))
+            }
+            Encoding::Scott => items.into_iter().rev().fold(self.nil(), |tail, head| {
+                lam("c", lam("n", apply(var("c"), [head, tail])))
+            }),
+        }
+    }
+
+    pub fn nil(self) -> Term {
+        term(r"\c n. n")
+    }
+
+    pub fn cons(self) -> Term {
+        match self {
+            Encoding::Church => term(r"\h t c n. c h (t c n)"),
+            Encoding::Scott => term(r"\h t c n. c h t"),
+        }
+    }
+
+    /// Returns whether a list is empty, as a boolean.
+    pub fn is_nil(self) -> Term {
+        term(r"\l. l (\h t u f. f) (\t f. t)")
+    }
+
+    /// Decodes a list in normal form, decoding its items with `item`.
+    ///
+    /// Items must be closed terms.
+    pub fn decode_list<T>(self, term: &Term, item: impl Fn(&Term) -> Option<T>) -> Option<Vec<T>> {
+        let mut items = Vec::new();
+        self.decode_items(term, &item, &mut items)?;
+        Some(items)
+    }
+
+    fn decode_items<T>(
+        self,
+        term: &Term,
+        item: &impl Fn(&Term) -> Option<T>,
+        items: &mut Vec<T>,
+    ) -> Option<()> {
+        let (c, n, mut body) = binders(term)?;
+        if c == n {
+            return None;
+        }
+        loop {
+            match body {
+                Term::Var(x) if x == n => return Some(()),
+                Term::App(f, tail) => {
+                    items.push(item(unary(f, c)?)?);
+                    match self {
+                        Encoding::Church => body = tail,
+                        Encoding::Scott => return self.decode_items(tail, item, items),
+                    }
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Decodes the list connected to the free port `root` of a reduced net.
+    pub fn read_list<T>(
+        self,
+        net: &Net,
+        root: usize,
+        item: impl Fn(&Term) -> Option<T>,
+    ) -> Option<Vec<T>> {
+        self.decode_list(&net.readback(Port::new(root, 0)).ok()?, item)
+    }
+}
+
+/// Compiles `f` applied to `xs` and reduces it.
+///
+/// Returns the net and the free port connected to the result, to read it back.
+pub fn evaluate(f: Term, xs: impl IntoIterator<Item = Term>) -> Result<(Net, usize), String> {
+    let (mut net, root) = lambda::compile(&apply(f, xs))?;
+    net.reduce(None);
+    Ok((net, root))
+}
@@ -0,0 +1,49 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Selection of the engine reducing a net.
+
+use crate::Net;
+
+/// How [`Net::reduce`] rewrites the active pairs.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Engine {
+    /// One active pair at a time, selected by the strategy.
+    #[default]
+    Sequential,
+    /// Many active pairs at a time on the rayon thread pool, see [`Net::reduce_atomic`].
+    #[cfg(feature = "atomic")]
+    Atomic,
+}
+
+impl Net {
+    /// Creates an interaction combinators net without agents reduced by `engine`.
+    pub fn with_engine(engine: Engine) -> Net {
+        let mut net = Net::empty();
+        net.set_engine(engine);
+        net
+    }
+
+    /// Sets the engine of [`Net::reduce`] and [`Net::reduce_with`].
+    ///
+    /// Other engines than [`Engine::Sequential`] ignore the strategy. Nets with a journal or a
+    /// tracer are always reduced sequentially.
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = engine;
+    }
+
+    pub fn engine(&self) -> Engine {
+        self.engine
+    }
+}
@@ -0,0 +1,79 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Net, Port, ReductionStats, Symbol};
+use std::fmt;
+
+/// Errors of the checked operations on a [`Net`](crate::Net).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NetError {
+    /// The symbol is not declared by the rule set of the net.
+    UnknownSymbol(Symbol),
+    /// There is no agent nor free port with this id.
+    MissingAgent(usize),
+    /// The port index exceeds the arity of its agent.
+    InvalidPort(Port),
+    /// The port is not connected.
+    Unconnected(Port),
+    /// The id is not a free port.
+    NotFree(usize),
+    /// The free port is glued more than once.
+    GluedTwice(usize),
+    /// The nets have different rule sets.
+    DifferentRules,
+    /// The name of a free port has invalid characters.
+    InvalidName(String),
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetError::UnknownSymbol(s) => write!(f, "unknown symbol {}", s.0),
+            NetError::MissingAgent(a) => write!(f, "missing agent {}", a),
+            NetError::InvalidPort(x) => write!(f, "invalid port {}.{}", x.agent, x.port),
+            NetError::Unconnected(x) => write!(f, "port {}.{} is not connected", x.agent, x.port),
+            NetError::NotFree(a) => write!(f, "{} is not a free port", a),
+            NetError::GluedTwice(a) => write!(f, "free port {} is glued twice", a),
+            NetError::DifferentRules => write!(f, "nets have different rules"),
+            NetError::InvalidName(x) => write!(f, "invalid name {:?}", x),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+/// Error of [`Net::reduce_bounded`] and [`Net::reduce_sweeps_bounded`] when the net grows too
+/// large.
+#[derive(Clone, Debug)]
+pub struct Overflow {
+    /// The maximum number of agents that was exceeded.
+    pub max_agents: usize,
+    /// Statistics of the reduction until it stopped.
+    pub stats: ReductionStats,
+    /// The net when the reduction stopped.
+    pub snapshot: Box<Net>,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (agents, steps) = (self.snapshot.len(), self.stats.steps);
+        write!(
+            f,
+            "net has {} agents, more than {}, after {} steps",
+            agents, self.max_agents, steps
+        )
+    }
+}
+
+impl std::error::Error for Overflow {}
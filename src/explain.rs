@@ -0,0 +1,103 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Explanations of rewrites in plain words, for teaching.
+
+use crate::{RuleSet, Symbol};
+
+impl RuleSet {
+    /// Returns the short name of `symbol`: γ, δ, and ε for the combinators, and its name
+    /// otherwise.
+    pub(crate) fn letter(&self, symbol: Symbol) -> &str {
+        match self.name(symbol) {
+            "construct" => "γ",
+            "duplicate" => "δ",
+            "erase" => "ε",
+            name => name,
+        }
+    }
+
+    /// Returns the name of an agent with `symbol`.
+    fn noun(&self, symbol: Symbol) -> &str {
+        match self.name(symbol) {
+            "construct" => "constructor",
+            "duplicate" => "duplicator",
+            "erase" => "eraser",
+            name => name,
+        }
+    }
+
+    /// Returns whether the rule for `x`-`y` copies each agent to the auxiliary ports of the
+    /// other, like the commutation of a constructor and a duplicator.
+    pub(crate) fn commutes(&self, x: Symbol, y: Symbol) -> bool {
+        let agents = match self.rule(x, y) {
+            Some(rule) if x != y => &rule.agents,
+            _ => return false,
+        };
+        let copies = |symbol| agents.iter().filter(|&&s| s == symbol).count();
+        copies(x) == self.arity(y)
+            && copies(y) == self.arity(x)
+            && copies(x) + copies(y) == agents.len()
+    }
+
+    /// Explains the rewrite of the active pair `a`-`b` with the rule for `rule`, which created
+    /// the agents `created`.
+    ///
+    /// For example: "γδ commutation: constructor 5 passes through duplicator 9, creating agents
+    /// 17, 18, 19 and 20". Rules are named after what they do: annihilations remove two agents
+    /// with the same symbol, erasures copy an agent without auxiliary ports to the auxiliary
+    /// ports of the other agent, and commutations copy each agent to the auxiliary ports of the
+    /// other agent. Other rules are named after their symbols.
+    pub fn explain(&self, rule: (Symbol, Symbol), a: usize, b: usize, created: &[usize]) -> String {
+        let (x, y) = rule;
+        let name = match (self.letter(x), self.letter(y)) {
+            (l, r) if l.chars().count() == 1 && r.chars().count() == 1 => format!("{}{}", l, r),
+            (l, r) => format!("{}-{}", l, r),
+        };
+        let (a, b) = (
+            format!("{} {}", self.noun(x), a),
+            format!("{} {}", self.noun(y), b),
+        );
+        let created = match created {
+            [] => String::new(),
+            [c] => format!(", creating agent {}", c),
+            [init @ .., last] => {
+                let init: Vec<_> = init.iter().map(|c| c.to_string()).collect();
+                format!(", creating agents {} and {}", init.join(", "), last)
+            }
+        };
+        let agents = self.rule(x, y).map_or(&[][..], |rule| &rule.agents);
+        let copies = |symbol| agents.iter().filter(|&&s| s == symbol).count();
+        let erases =
+            |x, y| self.arity(x) == 0 && copies(x) == agents.len() && copies(x) == self.arity(y);
+        if x == y && agents.is_empty() {
+            let what = match self.arity(x) {
+                0 => "disappear",
+                _ => "disappear and connect their neighbors",
+            };
+            format!("{} annihilation: {} and {} {}", name, a, b, what)
+        } else if erases(y, x) {
+            format!("{} erasure: {} erases {}{}", name, b, a, created)
+        } else if erases(x, y) {
+            format!("{} erasure: {} erases {}{}", name, a, b, created)
+        } else if self.commutes(x, y) {
+            format!(
+                "{} commutation: {} passes through {}{}",
+                name, a, b, created
+            )
+        } else {
+            format!("{} rule: {} and {} rewrite{}", name, a, b, created)
+        }
+    }
+}
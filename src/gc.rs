@@ -0,0 +1,71 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Net, Port, Ports};
+use std::collections::HashSet;
+
+/// Statistics of [`Net::collect_garbage`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GarbageStats {
+    /// Number of deleted agents.
+    pub agents: usize,
+    /// Number of deleted connected components.
+    pub components: usize,
+}
+
+impl Net {
+    /// Deletes the agents that are not connected, even through other agents, to a free port.
+    ///
+    /// Such agents cannot change what is connected to the interface of the net, but they may
+    /// still have active pairs, possibly reducing forever. Erasing a subnet whose root is free
+    /// typically leaves such garbage, for example a cycle of duplicators.
+    pub fn collect_garbage(&mut self) -> GarbageStats {
+        let mut seen = HashSet::new();
+        for &a in self.free.keys() {
+            self.visit(a, &mut seen);
+        }
+        let mut stats = GarbageStats::default();
+        let garbage: Vec<usize> = self.agents().filter(|a| !seen.contains(a)).collect();
+        for &a in &garbage {
+            if !seen.contains(&a) {
+                self.visit(a, &mut seen);
+                stats.components += 1;
+            }
+        }
+        if garbage.is_empty() {
+            return stats;
+        }
+        self.forget();
+        stats.agents = garbage.len();
+        for a in garbage {
+            self.delete(a);
+        }
+        stats
+    }
+
+    /// Marks the agents and free ports connected to `a` as seen.
+    fn visit(&self, a: usize, seen: &mut HashSet<usize>) {
+        let mut todo = vec![a];
+        while let Some(a) = todo.pop() {
+            if !seen.insert(a) {
+                continue;
+            }
+            let ports = match self.agents.get(&a) {
+                None => Ports::new(std::slice::from_ref(&self.free[&a])),
+                Some(agent) => agent.ports(),
+            };
+            todo.extend(ports.flatten().map(|Port { agent, .. }| agent));
+        }
+    }
+}
@@ -0,0 +1,140 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmark nets.
+//!
+//! The nets are compiled from lambda terms over Church numerals with [`lambda::compile`], so
+//! the caveats of [`lambda`] apply: they are meant to exercise the reduction, not to compute the
+//! expected numbers. Each function returns the net and the free port connected to the term,
+//! except [`example`] which selects a net by name.
+
+use crate::lambda::{self, Term};
+use crate::{Net, Port, Symbol};
+
+/// Weights of the symbols of the `random` example.
+const RANDOM_WEIGHTS: [(Symbol, f64); 3] = [
+    (Symbol::CONSTRUCT, 2.),
+    (Symbol::DUPLICATE, 2.),
+    (Symbol::ERASE, 1.),
+];
+
+fn var(x: &str) -> Term {
+    Term::Var(x.to_string())
+}
+
+fn lam(xs: &[&str], b: Term) -> Term {
+    let lam = |b, x: &&str| Term::Lam(x.to_string(), Box::new(b));
+    xs.iter().rev().fold(b, lam)
+}
+
+fn app(f: Term, xs: impl IntoIterator<Item = Term>) -> Term {
+    xs.into_iter()
+        .fold(f, |f, x| Term::App(Box::new(f), Box::new(x)))
+}
+
+fn compile(term: &Term) -> (Net, usize) {
+    lambda::compile(term).unwrap()
+}
+
+/// The Church numeral `n`, that is `\f x. f (f (... x))`.
+pub fn church_term(n: usize) -> Term {
+    let body = (0..n).fold(var("x"), |x, _| app(var("f"), [x]));
+    lam(&["f", "x"], body)
+}
+
+/// `\n f x. f (n f x)`
+fn succ() -> Term {
+    let nfx = app(var("n"), [var("f"), var("x")]);
+    lam(&["n", "f", "x"], app(var("f"), [nfx]))
+}
+
+/// `\m n f x. m f (n f x)`
+fn add() -> Term {
+    let nfx = app(var("n"), [var("f"), var("x")]);
+    lam(&["m", "n", "f", "x"], app(var("m"), [var("f"), nfx]))
+}
+
+/// The Church numeral `n`.
+pub fn church(n: usize) -> (Net, usize) {
+    compile(&church_term(n))
+}
+
+/// The Ackermann function applied to the Church numerals `m` and `n`.
+///
+/// It is `\m. m (\f k. k f (f 1)) succ` applied to `m` and `n`.
+pub fn ackermann_net(m: usize, n: usize) -> (Net, usize) {
+    let step = lam(
+        &["f", "k"],
+        app(var("k"), [var("f"), app(var("f"), [church_term(1)])]),
+    );
+    let ackermann = lam(&["m"], app(var("m"), [step, succ()]));
+    compile(&app(ackermann, [church_term(m), church_term(n)]))
+}
+
+/// The Fibonacci function applied to the Church numeral `n`.
+///
+/// Pairs of consecutive Fibonacci numbers are iterated `n` times from `(0, 1)` and the first
+/// component is returned.
+pub fn fibonacci_net(n: usize) -> (Net, usize) {
+    let pair = |a, b| lam(&["s"], app(var("s"), [a, b]));
+    let next = pair(var("b"), app(add(), [var("a"), var("b")]));
+    let step = lam(&["p"], app(var("p"), [lam(&["a", "b"], next)]));
+    let start = pair(church_term(0), church_term(1));
+    let first = lam(&["a", "b"], var("a"));
+    let fibonacci = lam(&["n"], app(var("n"), [step, start, first]));
+    compile(&app(fibonacci, [church_term(n)]))
+}
+
+/// Returns the example net `name`, with optional comma-separated arguments after a colon.
+///
+/// The examples are `loop`, a net that never normalizes, `church:N`, `ackermann:M,N`,
+/// `fibonacci:N`, and `random:N,SEED`, a random net of about N agents, half of which start in an
+/// active pair (see [`Net::random`]).
+pub fn example(name: &str) -> Result<Net, String> {
+    let (name, args) = name.split_once(':').unwrap_or((name, ""));
+    let args = args
+        .split(',')
+        .filter(|x| !x.is_empty())
+        .map(|x| x.parse().map_err(|_| format!("invalid argument {:?}", x)))
+        .collect::<Result<Vec<usize>, _>>()?;
+    let (net, _) = match (name, &args[..]) {
+        ("loop", []) => return Ok(example_loop()),
+        ("church", &[n]) => church(n),
+        ("ackermann", &[m, n]) => ackermann_net(m, n),
+        ("fibonacci", &[n]) => fibonacci_net(n),
+        ("random", &[n, seed]) => return Net::random(n, &RANDOM_WEIGHTS, 0.5, seed as u64),
+        _ => {
+            return Err(format!(
+                "unknown example {:?} with {} arguments",
+                name,
+                args.len()
+            ))
+        }
+    };
+    Ok(net)
+}
+
+/// Returns the loop example, which never normalizes.
+fn example_loop() -> Net {
+    let mut net = Net::empty();
+    let a = net.add_erase();
+    let b = net.add_erase();
+    let c = net.add_construct();
+    let d = net.add_duplicate();
+    net.connect_unchecked(Port::new(a, 0), Port::new(c, 1));
+    net.connect_unchecked(Port::new(c, 2), Port::new(d, 1));
+    net.connect_unchecked(Port::new(d, 2), Port::new(b, 0));
+    net.connect_unchecked(Port::new(c, 0), Port::new(d, 0));
+    net
+}
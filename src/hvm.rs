@@ -0,0 +1,228 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Import of HVM2 programs.
+//!
+//! An HVM2 file (usually `.hvm`) is a list of definitions `@name = tree & tree ~ tree ...`,
+//! where the first tree is the root of the definition and the others are its active pairs.
+//! Trees are written as in the [textual format](crate::Net::to_sic) of interaction combinators,
+//! with `@name` referring to another definition. Nets use the [symmetric](RuleSet::symmetric)
+//! rules of HVM.
+//!
+//! Only the combinator subset is supported: erasers, constructors, duplicators, variables, and
+//! references. References are expanded when importing, so recursive definitions are rejected.
+//! Numbers, operators, and switches are rejected too.
+
+use crate::sic::{Builder, End};
+use crate::{Net, Port, RuleSet};
+use std::collections::HashMap;
+
+enum Tree {
+    Era,
+    Con(Box<Tree>, Box<Tree>),
+    Dup(Box<Tree>, Box<Tree>),
+    Ref(String),
+    Var(String),
+}
+
+struct Def {
+    root: Tree,
+    redexes: Vec<(Tree, Tree)>,
+}
+
+/// Imports the `@main` definition of an HVM2 program.
+///
+/// Returns the net and the free port connected to the root of `@main`. Variables occurring
+/// once in a definition are free ports too.
+pub fn import(input: &str) -> Result<(Net, usize), String> {
+    let mut parser = Parser { input, pos: 0 };
+    let mut book = HashMap::new();
+    loop {
+        parser.skip();
+        if parser.peek().is_none() {
+            break;
+        }
+        parser.expect('@')?;
+        let name = parser.name()?;
+        parser.expect('=')?;
+        let root = parser.tree()?;
+        let mut redexes = Vec::new();
+        while parser.eat('&') {
+            let left = parser.tree()?;
+            parser.expect('~')?;
+            redexes.push((left, parser.tree()?));
+        }
+        if book
+            .insert(name.to_string(), Def { root, redexes })
+            .is_some()
+        {
+            return Err(format!("@{} is defined twice", name));
+        }
+    }
+    let mut expander = Expander {
+        book: &book,
+        builder: Builder::new(RuleSet::symmetric()),
+        stack: Vec::new(),
+        instances: 0,
+    };
+    let free = expander.builder.net.add_free();
+    let root = expander.expand("main")?;
+    expander.builder.link(End::Port(Port::new(free, 0)), root)?;
+    Ok((expander.builder.finish(), free))
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn skip(&mut self) {
+        loop {
+            let rest = &self.input[self.pos..];
+            if rest.starts_with("//") {
+                self.pos += rest.find('\n').unwrap_or(rest.len());
+            } else {
+                match self.peek() {
+                    Some(c) if c.is_whitespace() => self.pos += c.len_utf8(),
+                    _ => break,
+                }
+            }
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at offset {}", message, self.pos)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip();
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += c.len_utf8();
+        }
+        found
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        match self.eat(c) {
+            true => Ok(()),
+            false => Err(self.error(&format!("expected '{}'", c))),
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, String> {
+        self.skip();
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !c.is_alphanumeric() && !"_.-/".contains(c) {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+        match self.pos > start {
+            true => Ok(&self.input[start..self.pos]),
+            false => Err(self.error("expected a name")),
+        }
+    }
+
+    fn pair(&mut self, close: char) -> Result<(Box<Tree>, Box<Tree>), String> {
+        let left = self.tree()?;
+        let right = self.tree()?;
+        self.expect(close)?;
+        Ok((Box::new(left), Box::new(right)))
+    }
+
+    fn tree(&mut self) -> Result<Tree, String> {
+        self.skip();
+        match self.peek() {
+            Some('*') => {
+                self.pos += 1;
+                Ok(Tree::Era)
+            }
+            Some('(') => {
+                self.pos += 1;
+                let (left, right) = self.pair(')')?;
+                Ok(Tree::Con(left, right))
+            }
+            Some('{') => {
+                self.pos += 1;
+                let (left, right) = self.pair('}')?;
+                Ok(Tree::Dup(left, right))
+            }
+            Some('@') => {
+                self.pos += 1;
+                Ok(Tree::Ref(self.name()?.to_string()))
+            }
+            Some('#') | Some('$') | Some('?') => {
+                Err(self.error("numbers, operators, and switches are not supported"))
+            }
+            _ => Ok(Tree::Var(self.name()?.to_string())),
+        }
+    }
+}
+
+struct Expander<'a> {
+    book: &'a HashMap<String, Def>,
+    builder: Builder,
+    /// Definitions being expanded.
+    stack: Vec<&'a str>,
+    /// Number of expanded definitions, to rename their variables apart.
+    instances: usize,
+}
+
+impl<'a> Expander<'a> {
+    /// Adds a copy of the definition `name` and returns its root.
+    fn expand(&mut self, name: &'a str) -> Result<End, String> {
+        let def = match self.book.get(name) {
+            Some(def) => def,
+            None => return Err(format!("@{} is not defined", name)),
+        };
+        if self.stack.contains(&name) {
+            return Err(format!("@{} is recursive", name));
+        }
+        self.stack.push(name);
+        // Names can't contain spaces, so variables of different copies don't clash.
+        let scope = format!("in @{} ({})", name, self.instances);
+        self.instances += 1;
+        let root = self.tree(&def.root, &scope)?;
+        for (left, right) in &def.redexes {
+            let left = self.tree(left, &scope)?;
+            let right = self.tree(right, &scope)?;
+            self.builder.link(left, right)?;
+        }
+        self.stack.pop();
+        Ok(root)
+    }
+
+    /// Adds the agents of `tree` and returns its root, renaming its variables with `scope`.
+    fn tree(&mut self, tree: &'a Tree, scope: &str) -> Result<End, String> {
+        let (a, children) = match tree {
+            Tree::Era => return Ok(End::Port(Port::new(self.builder.net.add_erase(), 0))),
+            Tree::Con(left, right) => (self.builder.net.add_construct(), [left, right]),
+            Tree::Dup(left, right) => (self.builder.net.add_duplicate(), [left, right]),
+            Tree::Ref(name) => return self.expand(name),
+            Tree::Var(x) => return Ok(self.builder.var(&format!("{} {}", x, scope))),
+        };
+        for (p, child) in children.into_iter().enumerate() {
+            let child = self.tree(child, scope)?;
+            self.builder.link(End::Port(Port::new(a, p + 1)), child)?;
+        }
+        Ok(End::Port(Port::new(a, 0)))
+    }
+}
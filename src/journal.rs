@@ -0,0 +1,138 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::strategy::{Popped, Queues, Turns};
+use crate::{Agent, Net, Port, Wire};
+
+/// Changes made by reduction steps, to undo them.
+///
+/// See [`Net::enable_journal`].
+#[derive(Clone, Debug, Default)]
+pub struct Journal {
+    steps: Vec<Vec<Change>>,
+    /// Changes of the step in progress, if any.
+    current: Option<Vec<Change>>,
+}
+
+impl Journal {
+    /// Returns the number of steps that can be undone.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Change {
+    Create(usize),
+    Delete(usize, Agent),
+    /// The port had the given connection before.
+    SetPort(Port, Option<Port>),
+    Push(usize),
+    Pop(Popped),
+    Drain(Queues),
+    Turns(Turns),
+}
+
+impl Net {
+    /// Starts recording reduction steps to undo them with [`Net::undo_step`].
+    ///
+    /// Each call to a reduction function is a step, except [`Net::reduce`] and its variants which
+    /// record a step per reduced active pair. Modifying the net otherwise forgets the recorded
+    /// steps. The random generator of [`Strategy::Random`](crate::Strategy::Random) is not
+    /// restored.
+    pub fn enable_journal(&mut self) {
+        self.journal.get_or_insert_with(Journal::default);
+    }
+
+    /// Stops recording reduction steps and forgets the recorded ones.
+    pub fn disable_journal(&mut self) {
+        self.journal = None;
+    }
+
+    pub fn journal(&self) -> Option<&Journal> {
+        self.journal.as_ref()
+    }
+
+    /// Restores the net before the last recorded step.
+    ///
+    /// Returns whether there was a step to undo.
+    pub fn undo_step(&mut self) -> bool {
+        let changes = match self.journal.as_mut().and_then(|j| j.steps.pop()) {
+            None => return false,
+            Some(changes) => changes,
+        };
+        for change in changes.into_iter().rev() {
+            match change {
+                Change::Create(a) => {
+                    assert!(self.agents.remove(&a).is_some());
+                    self.next = a;
+                }
+                Change::Delete(a, agent) => assert!(self.agents.insert(a, agent).is_none()),
+                Change::SetPort(x, y) => match self.agents.get_mut(&x.agent) {
+                    Some(agent) => {
+                        agent.set_port(x.port, y);
+                    }
+                    None => *self.free.get_mut(&x.agent).unwrap() = Wire::new(y),
+                },
+                Change::Push(queue) => self.worklist.unpush(queue),
+                Change::Pop(popped) => self.worklist.unpop(popped),
+                Change::Drain(queues) => self.worklist.restore(queues),
+                Change::Turns(turns) => self.worklist.restore_turns(turns),
+            }
+        }
+        true
+    }
+
+    pub(crate) fn journaling(&self) -> bool {
+        self.journal.as_ref().is_some_and(|j| j.current.is_some())
+    }
+
+    pub(crate) fn log(&mut self, change: Change) {
+        if let Some(current) = self.journal.as_mut().and_then(|j| j.current.as_mut()) {
+            current.push(change);
+        }
+    }
+
+    pub(crate) fn begin_step(&mut self) {
+        if let Some(journal) = &mut self.journal {
+            journal.current = Some(Vec::new());
+        }
+    }
+
+    /// Ends the step started by [`Net::begin_step`].
+    ///
+    /// A step that did not reduce anything may still have removed stale pairs from the worklist.
+    /// Those changes are appended to the previous step, so that undoing it stays consistent.
+    pub(crate) fn end_step(&mut self, reduced: bool) {
+        if let Some(journal) = &mut self.journal {
+            let mut current = journal.current.take().unwrap();
+            match journal.steps.last_mut() {
+                Some(last) if !reduced => last.append(&mut current),
+                _ if !reduced => (),
+                _ => journal.steps.push(current),
+            }
+        }
+    }
+
+    /// Forgets the recorded steps after a modification outside of reduction.
+    pub(crate) fn forget(&mut self) {
+        if let Some(journal) = &mut self.journal {
+            journal.steps.clear();
+        }
+    }
+}
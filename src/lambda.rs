@@ -0,0 +1,352 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lambda calculus front-end.
+//!
+//! Abstractions and applications are constructors, shared variables are trees of duplicators,
+//! and unused variables are erasers:
+//!
+//! - `\x. b` is a constructor with port 0 as the term, port 1 as `x`, and port 2 as `b`.
+//! - `f a` is a constructor with port 0 facing `f`, port 1 as the term, and port 2 as `a`.
+//!
+//! Since there is a single kind of duplicator, duplicators meeting each other always
+//! annihilate. Reduction is thus only correct for terms where duplications don't overlap, like
+//! elementary affine terms.
+
+use crate::{Net, Port, Symbol};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Term {
+    Var(String),
+    Lam(String, Box<Term>),
+    App(Box<Term>, Box<Term>),
+}
+
+impl FromStr for Term {
+    type Err = String;
+
+    /// Parses `\x y. b` (or `λx y. b`) abstractions, applications by juxtaposition, and
+    /// parentheses.
+    fn from_str(input: &str) -> Result<Term, String> {
+        let mut parser = Parser { input, pos: 0 };
+        let term = parser.term()?;
+        parser.skip();
+        match parser.peek() {
+            None => Ok(term),
+            Some(c) => Err(parser.error(&format!("unexpected {:?}", c))),
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn skip(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at offset {}", message, self.pos)
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        self.skip();
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !c.is_alphanumeric() && c != '_' && c != '\'' {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+        (self.pos > start).then(|| self.input[start..self.pos].to_string())
+    }
+
+    fn term(&mut self) -> Result<Term, String> {
+        let mut term: Option<Term> = None;
+        let mut lambda = false;
+        while !lambda {
+            self.skip();
+            let arg = match self.peek() {
+                Some('\\') | Some('λ') => {
+                    self.pos += self.peek().unwrap().len_utf8();
+                    lambda = true;
+                    self.lambda()?
+                }
+                Some('(') => {
+                    self.pos += 1;
+                    let arg = self.term()?;
+                    self.skip();
+                    if self.peek() != Some(')') {
+                        return Err(self.error("expected ')'"));
+                    }
+                    self.pos += 1;
+                    arg
+                }
+                _ => match self.ident() {
+                    Some(x) => Term::Var(x),
+                    None => break,
+                },
+            };
+            term = Some(match term {
+                None => arg,
+                Some(f) => Term::App(Box::new(f), Box::new(arg)),
+            });
+        }
+        term.ok_or_else(|| self.error("expected a term"))
+    }
+
+    fn lambda(&mut self) -> Result<Term, String> {
+        let mut vars = Vec::new();
+        while let Some(x) = self.ident() {
+            vars.push(x);
+        }
+        self.skip();
+        if vars.is_empty() || self.peek() != Some('.') {
+            return Err(self.error("expected variables followed by '.'"));
+        }
+        self.pos += 1;
+        let mut body = self.term()?;
+        for x in vars.into_iter().rev() {
+            body = Term::Lam(x, Box::new(body));
+        }
+        Ok(body)
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Term::Var(x) => write!(f, "{}", x),
+            Term::Lam(x, b) => write!(f, "\\{}. {}", x, b),
+            Term::App(g, a) => {
+                match **g {
+                    Term::Lam(..) => write!(f, "({})", g)?,
+                    _ => write!(f, "{}", g)?,
+                }
+                match **a {
+                    Term::Var(_) => write!(f, " {}", a),
+                    _ => write!(f, " ({})", a),
+                }
+            }
+        }
+    }
+}
+
+/// Compiles a closed term to a net.
+///
+/// Returns the net and the free port connected to the term, named `result`.
+pub fn compile(term: &Term) -> Result<(Net, usize), String> {
+    let mut compiler = Compiler {
+        net: Net::empty(),
+        scope: HashMap::new(),
+    };
+    let root = compiler.net.add_free();
+    compiler.net.set_name(root, "result").unwrap();
+    compiler.compile(term, Port::new(root, 0))?;
+    Ok((compiler.net, root))
+}
+
+struct Compiler {
+    net: Net,
+    scope: HashMap<String, Vec<Vec<Port>>>,
+}
+
+impl Compiler {
+    /// Compiles `term` and connects it to `parent`.
+    fn compile(&mut self, term: &Term, parent: Port) -> Result<(), String> {
+        match term {
+            Term::Var(x) => match self.scope.get_mut(x).and_then(|uses| uses.last_mut()) {
+                None => return Err(format!("free variable {}", x)),
+                Some(uses) => uses.push(parent),
+            },
+            Term::Lam(x, b) => {
+                let a = self.net.add_construct();
+                self.net.connect_unchecked(Port::new(a, 0), parent);
+                self.scope.entry(x.clone()).or_default().push(Vec::new());
+                self.compile(b, Port::new(a, 2))?;
+                let uses = self.scope.get_mut(x).unwrap().pop().unwrap();
+                self.share(Port::new(a, 1), &uses);
+            }
+            Term::App(g, b) => {
+                let a = self.net.add_construct();
+                self.net.connect_unchecked(Port::new(a, 1), parent);
+                self.compile(g, Port::new(a, 0))?;
+                self.compile(b, Port::new(a, 2))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Connects `var` to all its `uses`.
+    fn share(&mut self, var: Port, uses: &[Port]) {
+        match uses {
+            [] => {
+                let e = self.net.add_erase();
+                self.net.connect_unchecked(var, Port::new(e, 0));
+            }
+            [x] => self.net.connect_unchecked(var, *x),
+            [x, rest @ ..] => {
+                let d = self.net.add_duplicate();
+                self.net.connect_unchecked(var, Port::new(d, 0));
+                self.net.connect_unchecked(Port::new(d, 1), *x);
+                self.share(Port::new(d, 2), rest);
+            }
+        }
+    }
+}
+
+/// Decodes the term connected to the free port `root`.
+///
+/// Returns `None` if the net does not look like a compiled term in normal form, see
+/// [`Net::readback`] for the reason.
+pub fn readback(net: &Net, root: usize) -> Option<Term> {
+    net.readback(Port::new(root, 0)).ok()
+}
+
+/// Why a net could not be decoded as a term.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ReadbackError {
+    /// Decoding came back to this port, so the term would be infinite.
+    Cycle(Port),
+    /// The port is not connected.
+    Unconnected(Port),
+    /// The term reaches this free port.
+    Free(usize),
+    /// The port of an agent that is not part of a term, like an eraser or a duplicator whose
+    /// copies are not being decoded.
+    Unexpected(Port),
+}
+
+impl fmt::Display for ReadbackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReadbackError::Cycle(x) => write!(f, "cycle through port {}.{}", x.agent, x.port),
+            ReadbackError::Unconnected(x) => {
+                write!(f, "port {}.{} is not connected", x.agent, x.port)
+            }
+            ReadbackError::Free(a) => write!(f, "free port {} in the term", a),
+            ReadbackError::Unexpected(x) => {
+                write!(f, "unexpected port {}.{} in a term", x.agent, x.port)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReadbackError {}
+
+impl Net {
+    /// Decodes the term connected to `root`, usually the port of a free port.
+    ///
+    /// The net is read as compiled by [`compile`]: constructors are abstractions and
+    /// applications, and duplicators share variables or copy the terms at their principal
+    /// port. Reduction must have completed for the term to be in normal form, but decoding
+    /// doesn't require it.
+    pub fn readback(&self, root: Port) -> Result<Term, ReadbackError> {
+        let mut reader = Reader {
+            net: self,
+            names: HashMap::new(),
+            stack: Vec::new(),
+            path: HashSet::new(),
+            fresh: 0,
+        };
+        reader.read(follow(self, root)?)
+    }
+}
+
+fn follow(net: &Net, x: Port) -> Result<Port, ReadbackError> {
+    net.get_port(x).map_err(|_| ReadbackError::Unconnected(x))
+}
+
+struct Reader<'a> {
+    net: &'a Net,
+    names: HashMap<Port, String>,
+    /// Auxiliary ports of the duplicators whose copy is being decoded, innermost last.
+    stack: Vec<usize>,
+    /// Ports being decoded, with the stack at that time.
+    path: HashSet<(Port, Vec<usize>)>,
+    fresh: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn read(&mut self, x: Port) -> Result<Term, ReadbackError> {
+        // Cycles through duplicators may grow the stack forever instead of coming back to the
+        // same state, but a finite term never visits more states than there are ports.
+        let state = (x, self.stack.clone());
+        if self.path.len() > 3 * self.net.len() || !self.path.insert(state.clone()) {
+            return Err(ReadbackError::Cycle(x));
+        }
+        let term = self.read_agent(x);
+        self.path.remove(&state);
+        term
+    }
+
+    fn read_agent(&mut self, x: Port) -> Result<Term, ReadbackError> {
+        let net = self.net;
+        let follow = |p| follow(net, Port::new(x.agent, p));
+        let symbol = match self.net.symbol(x.agent) {
+            Some(symbol) => symbol,
+            None => return Err(ReadbackError::Free(x.agent)),
+        };
+        match (symbol, x.port) {
+            (Symbol::CONSTRUCT, 0) => {
+                let var = Port::new(x.agent, 1);
+                let name = format!("x{}", self.fresh);
+                self.fresh += 1;
+                let shadowed = self.names.insert(var, name.clone());
+                let body = follow(2).and_then(|y| self.read(y));
+                match shadowed {
+                    None => self.names.remove(&var),
+                    Some(old) => self.names.insert(var, old),
+                };
+                Ok(Term::Lam(name, Box::new(body?)))
+            }
+            (Symbol::CONSTRUCT, 1) => match self.names.get(&x) {
+                Some(name) => Ok(Term::Var(name.clone())),
+                None => {
+                    let g = self.read(follow(0)?)?;
+                    let a = self.read(follow(2)?)?;
+                    Ok(Term::App(Box::new(g), Box::new(a)))
+                }
+            },
+            (Symbol::DUPLICATE, 0) => {
+                let p = self.stack.pop().ok_or(ReadbackError::Unexpected(x))?;
+                let term = follow(p).and_then(|y| self.read(y));
+                self.stack.push(p);
+                term
+            }
+            (Symbol::DUPLICATE, p) => {
+                self.stack.push(p);
+                let term = follow(0).and_then(|y| self.read(y));
+                self.stack.pop();
+                term
+            }
+            _ => Err(ReadbackError::Unexpected(x)),
+        }
+    }
+}
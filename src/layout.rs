@@ -0,0 +1,397 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Placement of the agents in the viewer.
+
+use crate::octree::Octree;
+use crate::{Node, Nodes};
+use kiss3d::nalgebra::core::Vector3;
+use lafont::{Net, Port, Symbol};
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
+/// Moves the nodes of the agents of a net over time.
+///
+/// Layouts are updated independently of reduction: they only decide when an active pair is
+/// close enough to be reduced.
+pub trait Layout {
+    /// Moves the nodes of the agents of `net` by a time step `dt`.
+    ///
+    /// Randomness comes from `rng` only, such that the layout is reproducible. Returns the kinetic
+    /// energy of the nodes, which is zero once they stop moving.
+    fn update(&mut self, net: &Net, nodes: &mut Nodes, dt: f32, rng: &mut StdRng) -> f32;
+
+    /// Returns whether the active pair of nodes `a` and `b` may be reduced.
+    fn ready(&self, _a: &Node, _b: &Node) -> bool {
+        true
+    }
+
+    /// Whether the layout keeps the nodes in the z=0 plane.
+    fn planar(&self) -> bool {
+        false
+    }
+
+    /// Returns the physics constants of the layout, if any.
+    fn physics(&mut self) -> Option<&mut PhysicsConfig> {
+        None
+    }
+}
+
+/// Constants of the [`ForceDirected`] layout.
+#[derive(Clone, Debug)]
+pub struct PhysicsConfig {
+    /// Distance under which an active pair collides and is reduced.
+    ///
+    /// Larger values reduce pairs sooner, before their agents overlap.
+    pub collision: f32,
+    /// Fraction of the velocity lost at each step.
+    ///
+    /// Smaller values make the layout livelier but may keep it from settling.
+    pub damping: f32,
+    /// Strength of the attraction between the agents of an active pair.
+    pub attraction: f32,
+    /// Attraction factor of constructor-duplicator pairs.
+    ///
+    /// Those pairs grow the net, so they are attracted more weakly than others.
+    pub commute: f32,
+    /// Attraction factor of eraser-eraser pairs.
+    pub erase: f32,
+    /// Strength of the random push separating overlapping agents.
+    pub jitter: f32,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> PhysicsConfig {
+        PhysicsConfig {
+            collision: 0.1,
+            damping: 0.1,
+            attraction: 0.1,
+            commute: 0.67,
+            erase: 1.5,
+            jitter: 10.,
+        }
+    }
+}
+
+impl PhysicsConfig {
+    /// Returns the constant with the given name.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut f32> {
+        Some(match name {
+            "collision" => &mut self.collision,
+            "damping" => &mut self.damping,
+            "attraction" => &mut self.attraction,
+            "commute" => &mut self.commute,
+            "erase" => &mut self.erase,
+            "jitter" => &mut self.jitter,
+            _ => return None,
+        })
+    }
+
+    /// Sets constants from a comma-separated list of `NAME=VALUE`.
+    pub fn parse(&mut self, settings: &str) -> Result<(), String> {
+        for setting in settings.split(',') {
+            let (name, value) = setting
+                .split_once('=')
+                .ok_or_else(|| format!("expected NAME=VALUE, found {:?}", setting))?;
+            let value = value
+                .parse()
+                .map_err(|e| format!("invalid value for {}: {}", name, e))?;
+            *self
+                .get_mut(name)
+                .ok_or_else(|| format!("unknown physics constant {:?}", name))? = value;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the agents connected to the auxiliary ports of `a`.
+fn auxiliary(net: &Net, a: usize) -> Vec<usize> {
+    let symbol = net.symbol(a).unwrap();
+    let ports = (1..=net.rules().arity(symbol)).map(|p| Port::new(a, p));
+    ports
+        .filter_map(|x| Some(net.get_port(x).ok()?.agent))
+        .collect()
+}
+
+/// Positions, velocities, and accelerations of nodes, in a flat array per coordinate.
+///
+/// Integration runs over the arrays in lockstep, which the compiler vectorizes, instead of over
+/// the nodes of a hash map.
+struct Bodies {
+    position: [Vec<f32>; 3],
+    velocity: [Vec<f32>; 3],
+    acceleration: [Vec<f32>; 3],
+}
+
+impl Bodies {
+    fn with_capacity(capacity: usize) -> Bodies {
+        let arrays = || std::array::from_fn(|_| Vec::with_capacity(capacity));
+        Bodies {
+            position: arrays(),
+            velocity: arrays(),
+            acceleration: arrays(),
+        }
+    }
+
+    /// Adds the node `n`, accelerated by `acceleration` besides damping.
+    fn push(&mut self, n: &Node, acceleration: Vector3<f32>) {
+        for k in 0..3 {
+            self.position[k].push(n.position[k]);
+            self.velocity[k].push(n.velocity[k]);
+            self.acceleration[k].push(acceleration[k]);
+        }
+    }
+
+    /// Moves the bodies by a time step `dt`, after accelerating and damping them.
+    ///
+    /// Returns the kinetic energy of the bodies, all of unit mass.
+    fn integrate(&mut self, damping: f32, dt: f32, planar: bool) -> f32 {
+        let axes = if planar { 2 } else { 3 };
+        let mut energy = 0.;
+        for k in 0..axes {
+            let position = self.position[k].iter_mut();
+            let velocity = self.velocity[k].iter_mut();
+            let acceleration = self.acceleration[k].iter();
+            for ((x, v), a) in position.zip(velocity).zip(acceleration) {
+                *v += a - damping * *v;
+                *x += dt * *v;
+                energy += *v * *v / 2.;
+            }
+        }
+        if planar {
+            self.position[2].fill(0.);
+            self.velocity[2].fill(0.);
+        }
+        energy
+    }
+}
+
+/// Principal ports attract each other and other agents repel each other.
+///
+/// Active pairs are reduced when their agents collide. Repulsion is approximated with a
+/// Barnes–Hut octree so that large nets stay interactive.
+pub struct ForceDirected {
+    /// Whether agents stay in the z=0 plane.
+    pub planar: bool,
+    /// Barnes–Hut opening angle, 0 for exact forces.
+    ///
+    /// Groups of agents seen under a smaller angle repel as a single agent.
+    pub theta: f32,
+    /// Constants of the forces.
+    pub physics: PhysicsConfig,
+}
+
+impl ForceDirected {
+    /// Returns the repulsion between agents at the given distance.
+    ///
+    /// Overlapping agents are pushed apart in a direction drawn from `rng`.
+    fn repulsion(
+        &self,
+        direction: Vector3<f32>,
+        distance: f32,
+        exact: bool,
+        rng: &mut StdRng,
+    ) -> Vector3<f32> {
+        if exact && distance < 1. {
+            let mut force = Vector3::from_fn(|_, _| 0.5 - rng.gen::<f32>());
+            if self.planar {
+                force.z = 0.;
+            }
+            force.normalize() * self.physics.jitter
+        } else if self.planar {
+            // In the plane, repulsion decays slower so that neighbors stay close without
+            // overlapping.
+            if distance < 20. {
+                direction / distance
+            } else {
+                Vector3::zeros()
+            }
+        } else if distance < 10. {
+            direction / (distance * distance)
+        } else {
+            Vector3::zeros()
+        }
+    }
+
+    /// Returns the distance beyond which agents don't repel.
+    fn cutoff(&self) -> f32 {
+        if self.planar {
+            20.
+        } else {
+            10.
+        }
+    }
+}
+
+impl Layout for ForceDirected {
+    fn update(&mut self, net: &Net, nodes: &mut Nodes, dt: f32, rng: &mut StdRng) -> f32 {
+        let bodies = nodes.iter().map(|(&a, n)| (a, n.position));
+        let tree = Octree::new(bodies.collect());
+        let mut bodies = Bodies::with_capacity(nodes.len());
+        for (&a, n) in nodes.iter() {
+            let mut acceleration = Vector3::zeros();
+            let principal = net.get_port_unchecked(Port::new(a, 0));
+            let partner = |b| principal == Port::new(b, 0);
+            let mut repulsion = |d, r, e| self.repulsion(d, r, e, rng);
+            let cutoff = self.cutoff();
+            acceleration += tree.force(a, n.position, self.theta, cutoff, &partner, &mut repulsion);
+            if let Some(m) = nodes.get(&principal.agent).filter(|_| principal.port == 0) {
+                let b = principal.agent;
+                let mut force = n.position - m.position;
+                let distance = force.normalize_mut();
+                if a != b && distance >= self.physics.collision {
+                    force *= -self.physics.attraction;
+                    let (x, y) = (net.symbol(a).unwrap(), net.symbol(b).unwrap());
+                    match (x.min(y), x.max(y)) {
+                        (Symbol::CONSTRUCT, Symbol::DUPLICATE) => force *= self.physics.commute,
+                        (Symbol::ERASE, Symbol::ERASE) => force *= self.physics.erase,
+                        (_, _) => (),
+                    }
+                    acceleration += force;
+                }
+            }
+            if self.planar {
+                // In the plane, auxiliary wires are springs.
+                let mut auxiliary = auxiliary(net, a);
+                auxiliary.sort_unstable();
+                auxiliary.dedup();
+                for b in auxiliary {
+                    let m = match nodes.get(&b) {
+                        Some(m) if a != b && !partner(b) => m,
+                        _ => continue,
+                    };
+                    let d = n.position - m.position;
+                    let distance = d.norm();
+                    if distance >= 1. {
+                        acceleration -= 0.05 * (distance - 3.) * d / distance;
+                    }
+                }
+            }
+            bodies.push(n, acceleration);
+        }
+        let energy = bodies.integrate(self.physics.damping, dt, self.planar);
+        // The map is not modified since the bodies were pushed, so it iterates in the same order.
+        for (i, n) in nodes.values_mut().enumerate() {
+            n.position = Vector3::from_fn(|k, _| bodies.position[k][i]);
+            n.velocity = Vector3::from_fn(|k, _| bodies.velocity[k][i]);
+        }
+        energy
+    }
+
+    fn ready(&self, a: &Node, b: &Node) -> bool {
+        (a.position - b.position).norm() < self.physics.collision
+    }
+
+    fn planar(&self) -> bool {
+        self.planar
+    }
+
+    fn physics(&mut self) -> Option<&mut PhysicsConfig> {
+        Some(&mut self.physics)
+    }
+}
+
+/// Nodes don't move.
+pub struct Frozen;
+
+impl Layout for Frozen {
+    fn update(&mut self, _: &Net, _: &mut Nodes, _: f32, _: &mut StdRng) -> f32 {
+        0.
+    }
+}
+
+/// Nodes are placed in rows by distance to the free ports.
+///
+/// Without free ports, the distance is to the agent with the smallest id. Nodes move towards
+/// their place instead of jumping to it.
+pub struct Hierarchical;
+
+impl Hierarchical {
+    /// Returns the row of each agent reachable from the roots.
+    fn rows(net: &Net, nodes: &Nodes) -> HashMap<usize, usize> {
+        let mut agents: Vec<_> = nodes.keys().copied().collect();
+        agents.sort_unstable();
+        let mut roots = Vec::new();
+        for &a in &agents {
+            let symbol = net.symbol(a).unwrap();
+            for p in 0..=net.rules().arity(symbol) {
+                match net.get_port(Port::new(a, p)) {
+                    Ok(y) if !nodes.contains_key(&y.agent) => roots.push(a),
+                    _ => (),
+                }
+            }
+        }
+        roots.extend(agents.first().filter(|_| roots.is_empty()));
+        let mut rows = HashMap::new();
+        let mut todo = VecDeque::new();
+        for a in roots {
+            if rows.insert(a, 0).is_none() {
+                todo.push_back(a);
+            }
+        }
+        while let Some(a) = todo.pop_front() {
+            let row = rows[&a] + 1;
+            let symbol = net.symbol(a).unwrap();
+            for p in 0..=net.rules().arity(symbol) {
+                let b = match net.get_port(Port::new(a, p)) {
+                    Ok(y) if nodes.contains_key(&y.agent) => y.agent,
+                    _ => continue,
+                };
+                if let Entry::Vacant(e) = rows.entry(b) {
+                    e.insert(row);
+                    todo.push_back(b);
+                }
+            }
+        }
+        rows
+    }
+}
+
+impl Layout for Hierarchical {
+    fn update(&mut self, net: &Net, nodes: &mut Nodes, dt: f32, _: &mut StdRng) -> f32 {
+        let rows = Hierarchical::rows(net, nodes);
+        let unreachable = rows.values().max().map_or(0, |&row| row + 1);
+        let mut layers: Vec<Vec<usize>> = Vec::new();
+        let mut agents: Vec<_> = nodes.keys().copied().collect();
+        agents.sort_unstable();
+        for a in agents {
+            let row = rows.get(&a).copied().unwrap_or(unreachable);
+            if layers.len() <= row {
+                layers.resize(row + 1, Vec::new());
+            }
+            layers[row].push(a);
+        }
+        let rate = (5. * dt).min(1.);
+        let mut energy = 0.;
+        for (row, layer) in layers.iter().enumerate() {
+            let width = layer.len() as f32 - 1.;
+            for (i, a) in layer.iter().enumerate() {
+                let n = nodes.get_mut(a).unwrap();
+                let target = Vector3::new(4. * (i as f32 - width / 2.), -4. * row as f32, 0.);
+                let offset = rate * (target - n.position);
+                n.position += offset;
+                n.velocity = Vector3::zeros();
+                // Nodes don't keep their velocity but still move.
+                energy += (offset / dt).norm_squared() / 2.;
+            }
+        }
+        energy
+    }
+
+    fn planar(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,762 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interaction nets as a library.
+//!
+//! A [`Net`] is built with [`Net::empty`], the `add_*` functions, and [`Net::connect`], then
+//! reduced with [`Net::step`]. It follows Lafont's interaction combinators by default, and any
+//! interaction system given by a [`RuleSet`] otherwise.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Enters a span until the end of the enclosing block if the `tracing` feature is enabled.
+///
+/// Takes a level like `DEBUG`, then the arguments of [`tracing::span!`].
+macro_rules! span {
+    ($level:ident, $($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::span!(tracing::Level::$level, $($args)*).entered();
+    };
+}
+
+#[cfg(feature = "atomic")]
+mod atomic;
+mod compose;
+mod coverage;
+mod diagram;
+mod diff;
+mod divergence;
+mod dot;
+pub mod encodings;
+mod engine;
+mod error;
+mod explain;
+mod gc;
+pub mod gen;
+pub mod hvm;
+mod journal;
+pub mod lambda;
+mod metrics;
+mod observer;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod persistent;
+mod random;
+#[cfg(feature = "parallel")]
+mod regions;
+pub mod rules;
+mod sic;
+mod simplify;
+#[cfg(feature = "serde")]
+mod snapshot;
+mod stats;
+mod status;
+mod strategy;
+mod stream;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod trace;
+mod types;
+mod validate;
+mod wire;
+
+pub use coverage::Coverage;
+pub use diagram::DiagramLayout;
+pub use diff::Difference;
+pub use divergence::{Divergence, Estimate};
+pub use engine::Engine;
+pub use error::{NetError, Overflow};
+pub use gc::GarbageStats;
+use journal::Change;
+pub use journal::Journal;
+pub use observer::NetObserver;
+pub use persistent::PersistentNet;
+pub use rules::{Rule, RuleSet, Slot, Symbol};
+pub use stats::{CostModel, ReductionStats};
+pub use status::Status;
+pub use strategy::Strategy;
+use strategy::Worklist;
+use trace::Tracer;
+pub use types::{Signature, Type, TypeError};
+pub use validate::Malformed;
+pub use wire::Ports;
+use wire::Wire;
+
+/// Port `port` of agent `agent`. Port 0 is the principal port.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Port {
+    pub agent: usize,
+    pub port: usize,
+}
+
+impl Port {
+    pub fn new(agent: usize, port: usize) -> Port {
+        Port { agent, port }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Agent {
+    symbol: Symbol,
+    wires: Vec<Wire>,
+}
+
+impl Agent {
+    fn new(symbol: Symbol, ports: impl IntoIterator<Item = Option<Port>>) -> Agent {
+        let wires = ports.into_iter().map(Wire::new).collect();
+        Agent { symbol, wires }
+    }
+
+    fn port(&self, p: usize) -> Option<Port> {
+        self.wires[p].get()
+    }
+
+    fn get_port(&self, p: usize) -> Port {
+        self.port(p).unwrap()
+    }
+
+    /// Connects port `p` to `y` and returns the port it was connected to.
+    fn set_port(&mut self, p: usize, y: Option<Port>) -> Option<Port> {
+        std::mem::replace(&mut self.wires[p], Wire::new(y)).get()
+    }
+
+    fn ports(&self) -> Ports<'_> {
+        Ports::new(&self.wires)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Net {
+    rules: Arc<RuleSet>,
+    agents: HashMap<usize, Agent>,
+    free: HashMap<usize, Wire>,
+    /// Names of some free ports, see [`Net::set_name`].
+    names: HashMap<usize, String>,
+    next: usize,
+    worklist: Worklist,
+    journal: Option<Journal>,
+    cost: CostModel,
+    /// Ports of deleted agents, reused by created agents to avoid allocations.
+    pool: Vec<Vec<Wire>>,
+    tracer: Option<Tracer>,
+    engine: Engine,
+}
+
+impl Default for Net {
+    fn default() -> Net {
+        Net::empty()
+    }
+}
+
+impl Net {
+    /// Creates an interaction combinators net without agents.
+    pub fn empty() -> Net {
+        Net::with_rules(RuleSet::combinators())
+    }
+
+    /// Creates a net without agents for the interaction system `rules`.
+    pub fn with_rules(rules: RuleSet) -> Net {
+        Net {
+            rules: Arc::new(rules),
+            agents: HashMap::new(),
+            free: HashMap::new(),
+            names: HashMap::new(),
+            next: 0,
+            worklist: Worklist::default(),
+            journal: None,
+            cost: CostModel::default(),
+            pool: Vec::new(),
+            tracer: None,
+            engine: Engine::default(),
+        }
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    /// Returns the id that the next added agent or free port gets.
+    pub fn next_id(&self) -> usize {
+        self.next
+    }
+
+    /// Adds an agent and returns its id.
+    pub fn add_agent(&mut self, symbol: Symbol) -> Result<usize, NetError> {
+        if self.rules.symbols().all(|s| s != symbol) {
+            return Err(NetError::UnknownSymbol(symbol));
+        }
+        Ok(self.add_agent_unchecked(symbol))
+    }
+
+    /// Same as [`Net::add_agent`] but panics if `symbol` is unknown.
+    pub fn add_agent_unchecked(&mut self, symbol: Symbol) -> usize {
+        self.forget();
+        self.create(symbol)
+    }
+
+    fn create(&mut self, symbol: Symbol) -> usize {
+        let mut wires = self.pool.pop().unwrap_or_default();
+        wires.clear();
+        wires.resize(self.rules.arity(symbol) + 1, Wire::NONE);
+        let agent = Agent { symbol, wires };
+        let a = self.next;
+        assert!(self.agents.insert(a, agent).is_none());
+        self.next += 1;
+        self.log(Change::Create(a));
+        a
+    }
+
+    /// Adds a constructor and returns its agent id.
+    pub fn add_construct(&mut self) -> usize {
+        self.add_agent_unchecked(Symbol::CONSTRUCT)
+    }
+
+    /// Adds a duplicator and returns its agent id.
+    pub fn add_duplicate(&mut self) -> usize {
+        self.add_agent_unchecked(Symbol::DUPLICATE)
+    }
+
+    /// Adds an eraser and returns its agent id.
+    pub fn add_erase(&mut self) -> usize {
+        self.add_agent_unchecked(Symbol::ERASE)
+    }
+
+    /// Adds a free port and returns its id.
+    ///
+    /// A free port is part of the interface of the net. It has a single port `Port::new(id, 0)`
+    /// and never interacts.
+    pub fn add_free(&mut self) -> usize {
+        self.forget();
+        let a = self.next;
+        assert!(self.free.insert(a, Wire::NONE).is_none());
+        self.next += 1;
+        a
+    }
+
+    /// Names the free port `f`, for example `result` or `input0`.
+    ///
+    /// Names are kept by [`Net::to_sic`] and [`Net::to_json`], to find the interface of the net
+    /// after reduction. They are made of alphanumeric characters and `_.'-`. Free ports parsed by
+    /// [`Net::from_sic`] are named after their variable.
+    pub fn set_name(&mut self, f: usize, name: &str) -> Result<(), NetError> {
+        if !self.free.contains_key(&f) {
+            return Err(NetError::NotFree(f));
+        }
+        if !sic::is_name(name) {
+            return Err(NetError::InvalidName(name.to_string()));
+        }
+        self.names.insert(f, name.to_string());
+        Ok(())
+    }
+
+    /// Returns the name of the free port `f`, if any.
+    pub fn name(&self, f: usize) -> Option<&str> {
+        self.names.get(&f).map(|x| x.as_str())
+    }
+
+    /// Returns a free port named `name`, if any.
+    pub fn find_free(&self, name: &str) -> Option<usize> {
+        let free = self.names.iter().filter(|(_, x)| *x == name);
+        free.map(|(&f, _)| f).min()
+    }
+
+    /// Returns the ids of the agents of the net, in no particular order.
+    pub fn agents(&self) -> impl Iterator<Item = usize> + '_ {
+        self.agents.keys().copied()
+    }
+
+    /// Returns the ids of the free ports of the net, in no particular order.
+    pub fn free(&self) -> impl Iterator<Item = usize> + '_ {
+        self.free.keys().copied()
+    }
+
+    /// Returns the number of agents of the net.
+    pub fn len(&self) -> usize {
+        self.agents.len()
+    }
+
+    /// Returns whether the net has no agents.
+    pub fn is_empty(&self) -> bool {
+        self.agents.is_empty()
+    }
+
+    /// Returns the symbol and ports of agent or free port `a`, with `None` for free ports.
+    ///
+    /// Free ports have a single port. Unconnected ports are `None`.
+    pub fn node(&self, a: usize) -> Option<(Option<Symbol>, Ports<'_>)> {
+        match self.agents.get(&a) {
+            Some(agent) => Some((Some(agent.symbol), agent.ports())),
+            None => self
+                .free
+                .get(&a)
+                .map(|x| (None, Ports::new(std::slice::from_ref(x)))),
+        }
+    }
+
+    /// Returns the agents and free ports of the net like [`Net::node`], in no particular order.
+    pub fn nodes(&self) -> impl Iterator<Item = (usize, Option<Symbol>, Ports<'_>)> + '_ {
+        let agents = self.agents.iter();
+        let agents = agents.map(|(&a, agent)| (a, Some(agent.symbol), agent.ports()));
+        let free = self.free.iter();
+        agents.chain(free.map(|(&a, x)| (a, None, Ports::new(std::slice::from_ref(x)))))
+    }
+
+    /// Connects two ports with a wire.
+    ///
+    /// Both ports must exist. Any wire previously attached to them is overwritten.
+    pub fn connect(&mut self, x: Port, y: Port) -> Result<(), NetError> {
+        self.check_port(x)?;
+        self.check_port(y)?;
+        self.connect_unchecked(x, y);
+        Ok(())
+    }
+
+    /// Same as [`Net::connect`] but panics if a port does not exist.
+    pub fn connect_unchecked(&mut self, x: Port, y: Port) {
+        self.forget();
+        self.connect_observed(x, y, &mut ());
+    }
+
+    fn check_port(&self, x: Port) -> Result<(), NetError> {
+        let len = match self.agents.get(&x.agent) {
+            Some(agent) => agent.wires.len(),
+            None if self.free.contains_key(&x.agent) => 1,
+            None => return Err(NetError::MissingAgent(x.agent)),
+        };
+        if x.port >= len {
+            return Err(NetError::InvalidPort(x));
+        }
+        Ok(())
+    }
+
+    fn connect_observed(&mut self, x: Port, y: Port, observer: &mut dyn NetObserver) {
+        observer.on_connect(x, y);
+        self.set_port(x, y);
+        self.set_port(y, x);
+        let (a, b) = (x.agent.min(y.agent), x.agent.max(y.agent));
+        if x.port == 0 && y.port == 0 && self.partner(a) == Some(b) {
+            let queue = self.worklist.push(a, b, self.queue(a, b));
+            self.log(Change::Push(queue));
+        }
+    }
+
+    /// Reduces one active pair, preferring the ones involving an eraser.
+    ///
+    /// Erasers are agents without auxiliary ports. Active pairs without rule never interact.
+    /// Returns whether an active pair was reduced.
+    pub fn step(&mut self) -> bool {
+        self.step_with(Strategy::PreferErase)
+    }
+
+    /// Reduces one active pair selected by `strategy`.
+    pub fn step_with(&mut self, strategy: Strategy) -> bool {
+        self.step_rule(strategy, &mut ()).is_some()
+    }
+
+    /// Reduces all the active pairs of the net in one pass.
+    ///
+    /// Active pairs created during the pass are not reduced. Returns the number of reduced
+    /// active pairs.
+    pub fn step_all(&mut self) -> usize {
+        self.sweep(&mut ReductionStats::default())
+    }
+
+    /// Same as [`Net::reduce`] but reduces all the active pairs in one pass like
+    /// [`Net::step_all`], recording the number of active pairs of each pass in
+    /// [`ReductionStats::sweeps`].
+    ///
+    /// The active pairs of a pass are independent, so the profile of the passes measures how
+    /// much a parallel reducer could gain. The last pass may exceed `limit`.
+    pub fn reduce_sweeps(&mut self, limit: Option<usize>) -> ReductionStats {
+        self.reduce_sweeps_until(limit, usize::MAX)
+    }
+
+    /// Same as [`Net::reduce_sweeps`] but stops after the first pass leaving more than
+    /// `max_agents` agents, like [`Net::reduce_bounded`].
+    ///
+    /// The net may thus exceed `max_agents` by the agents created by one pass.
+    pub fn reduce_sweeps_bounded(
+        &mut self,
+        limit: Option<usize>,
+        max_agents: usize,
+    ) -> Result<ReductionStats, Overflow> {
+        let stats = self.reduce_sweeps_until(limit, max_agents);
+        self.bounded(stats, max_agents)
+    }
+
+    fn reduce_sweeps_until(&mut self, limit: Option<usize>, max_agents: usize) -> ReductionStats {
+        span!(DEBUG, "reduce_sweeps", ?limit);
+        let mut stats = ReductionStats::new(self.agents.len());
+        while limit.is_none_or(|limit| stats.steps < limit) && self.agents.len() <= max_agents {
+            let count = self.sweep(&mut stats);
+            if count == 0 {
+                break;
+            }
+            stats.sweeps.push(count);
+        }
+        stats
+    }
+
+    /// Same as [`Net::step_all`] but records the reduced active pairs in `stats`.
+    fn sweep(&mut self, stats: &mut ReductionStats) -> usize {
+        span!(DEBUG, "sweep");
+        self.begin_step();
+        if self.journaling() {
+            self.log(Change::Drain(self.worklist.save()));
+        }
+        let mut count = 0;
+        for (a, b) in self.worklist.drain() {
+            if self.partner(a) == Some(b) {
+                let rule = (self.agents[&a].symbol, self.agents[&b].symbol);
+                self.interact(a, b, &mut ());
+                stats.record(rule.0, rule.1, self.agents.len(), &self.cost);
+                count += 1;
+            }
+        }
+        self.end_step(count > 0);
+        count
+    }
+
+    /// Reduces the active pair `a`-`b` and returns the created agents.
+    ///
+    /// Returns `None` if `a` and `b` do not form an active pair with a rule.
+    pub fn reduce_pair(&mut self, a: usize, b: usize) -> Option<Vec<usize>> {
+        self.reduce_pair_observed(a, b, &mut ())
+    }
+
+    /// Same as [`Net::reduce_pair`] but notifies `observer` of the rewrite.
+    pub fn reduce_pair_observed(
+        &mut self,
+        a: usize,
+        b: usize,
+        observer: &mut dyn NetObserver,
+    ) -> Option<Vec<usize>> {
+        let (a, b) = (a.min(b), a.max(b));
+        if self.partner(a) != Some(b) {
+            return None;
+        }
+        self.begin_step();
+        let agents = self.interact(a, b, observer);
+        self.end_step(true);
+        Some(agents)
+    }
+
+    /// Reduces active pairs until there are none left or `limit` pairs were reduced.
+    pub fn reduce(&mut self, limit: Option<usize>) -> ReductionStats {
+        self.reduce_with(Strategy::PreferErase, limit)
+    }
+
+    /// Same as [`Net::reduce`] but selects active pairs with `strategy`.
+    pub fn reduce_with(&mut self, strategy: Strategy, limit: Option<usize>) -> ReductionStats {
+        #[cfg(feature = "atomic")]
+        if self.engine == Engine::Atomic && !self.journaling() && self.tracer.is_none() {
+            return self.reduce_atomic(limit);
+        }
+        self.reduce_observed(strategy, limit, &mut ())
+    }
+
+    /// Same as [`Net::reduce_with`] but notifies `observer` of each rewrite.
+    pub fn reduce_observed(
+        &mut self,
+        strategy: Strategy,
+        limit: Option<usize>,
+        observer: &mut dyn NetObserver,
+    ) -> ReductionStats {
+        self.reduce_until(strategy, limit, usize::MAX, observer)
+    }
+
+    /// Same as [`Net::reduce_with`] but stops as soon as the net has more than `max_agents`
+    /// agents.
+    ///
+    /// The net may thus exceed `max_agents` by the agents created by one rule. When it does,
+    /// the returned error has a copy of the net at that point, for example to save it.
+    pub fn reduce_bounded(
+        &mut self,
+        strategy: Strategy,
+        limit: Option<usize>,
+        max_agents: usize,
+    ) -> Result<ReductionStats, Overflow> {
+        self.reduce_bounded_observed(strategy, limit, max_agents, &mut ())
+    }
+
+    /// Same as [`Net::reduce_bounded`] but notifies `observer` of each rewrite.
+    pub fn reduce_bounded_observed(
+        &mut self,
+        strategy: Strategy,
+        limit: Option<usize>,
+        max_agents: usize,
+        observer: &mut dyn NetObserver,
+    ) -> Result<ReductionStats, Overflow> {
+        let stats = self.reduce_until(strategy, limit, max_agents, observer);
+        self.bounded(stats, max_agents)
+    }
+
+    /// Returns `stats` if the net has at most `max_agents` agents, and an overflow otherwise.
+    fn bounded(
+        &self,
+        stats: ReductionStats,
+        max_agents: usize,
+    ) -> Result<ReductionStats, Overflow> {
+        if self.agents.len() <= max_agents {
+            return Ok(stats);
+        }
+        Err(Overflow {
+            max_agents,
+            stats,
+            snapshot: Box::new(self.clone()),
+        })
+    }
+
+    fn reduce_until(
+        &mut self,
+        strategy: Strategy,
+        limit: Option<usize>,
+        max_agents: usize,
+        observer: &mut dyn NetObserver,
+    ) -> ReductionStats {
+        span!(DEBUG, "reduce", ?strategy, ?limit);
+        let mut stats = ReductionStats::new(self.agents.len());
+        while limit.is_none_or(|limit| stats.steps < limit) && self.agents.len() <= max_agents {
+            match self.step_rule(strategy, observer) {
+                None => break,
+                Some((a, b)) => stats.record(a, b, self.agents.len(), &self.cost),
+            }
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            stats.steps,
+            stats.peak_agents,
+            agents = self.agents.len(),
+            "reduced"
+        );
+        stats
+    }
+
+    /// Same as [`Net::step_with`] but returns the symbols of the reduced active pair.
+    fn step_rule(
+        &mut self,
+        strategy: Strategy,
+        observer: &mut dyn NetObserver,
+    ) -> Option<(Symbol, Symbol)> {
+        self.begin_step();
+        let pair = {
+            span!(TRACE, "select");
+            self.select(strategy)
+        };
+        let rule = match pair {
+            None => None,
+            Some((a, b)) => {
+                let rule = (self.agents[&a].symbol, self.agents[&b].symbol);
+                self.interact(a, b, observer);
+                Some(rule)
+            }
+        };
+        self.end_step(rule.is_some());
+        rule
+    }
+
+    /// Returns the active pairs of the net, sorted, with the smallest agent first.
+    pub fn active_pairs(&self) -> Vec<(usize, usize)> {
+        span!(TRACE, "active_pairs", agents = self.agents.len());
+        let pairs = self.agents.keys();
+        let mut pairs: Vec<_> = pairs.filter_map(|&a| Some((a, self.partner(a)?))).collect();
+        pairs.sort_unstable();
+        pairs
+    }
+
+    /// Returns the agent forming an active pair with `a` if it has a greater id.
+    fn partner(&self, a: usize) -> Option<usize> {
+        let agent = self.agents.get(&a)?;
+        let Port { agent: b, port: p } = agent.port(0)?;
+        if p != 0 || b < a {
+            return None;
+        }
+        let other = self.agents.get(&b)?;
+        self.rules.rule(agent.symbol, other.symbol)?;
+        Some(b)
+    }
+
+    fn delete(&mut self, a: usize) {
+        let agent = self.agents.remove(&a).unwrap();
+        if self.journaling() {
+            self.log(Change::Delete(a, agent));
+        } else {
+            self.pool.push(agent.wires);
+        }
+    }
+
+    /// Returns the symbol of agent `a`, or `None` if `a` is not an agent.
+    pub fn symbol(&self, a: usize) -> Option<Symbol> {
+        self.agents.get(&a).map(|agent| agent.symbol)
+    }
+
+    /// Returns the port connected to `x`.
+    pub fn get_port(&self, x: Port) -> Result<Port, NetError> {
+        self.check_port(x)?;
+        let y = match self.agents.get(&x.agent) {
+            Some(agent) => agent.port(x.port),
+            None => self.free[&x.agent].get(),
+        };
+        y.ok_or(NetError::Unconnected(x))
+    }
+
+    /// Same as [`Net::get_port`] but panics if `x` does not exist or is not connected.
+    pub fn get_port_unchecked(&self, x: Port) -> Port {
+        match self.agents.get(&x.agent) {
+            Some(agent) => agent.get_port(x.port),
+            None => self.free[&x.agent].get().unwrap(),
+        }
+    }
+
+    fn set_port(&mut self, x: Port, y: Port) {
+        let old = match self.agents.get_mut(&x.agent) {
+            Some(agent) => agent.set_port(x.port, Some(y)),
+            None => {
+                assert_eq!(x.port, 0);
+                let wire = self.free.get_mut(&x.agent).unwrap();
+                std::mem::replace(wire, Wire::new(Some(y))).get()
+            }
+        };
+        self.log(Change::SetPort(x, old));
+    }
+
+    /// Applies the rule of the active pair `a`-`b` and returns the created agents.
+    fn interact(&mut self, a: usize, b: usize, observer: &mut dyn NetObserver) -> Vec<usize> {
+        span!(TRACE, "interact", a, b);
+        let plan = self.plan(a, b);
+        self.apply(plan, observer)
+    }
+
+    /// Computes how to replace the active pair `a`-`b` without modifying the net.
+    ///
+    /// The auxiliary ports of `a` and `b` in the rule stand for whatever they are connected to,
+    /// which may itself be an auxiliary port of `a` or `b`.
+    fn plan(&self, a: usize, b: usize) -> Plan {
+        let symbol = |x| self.agents[&x].symbol;
+        Plan::new(&self.rules, a, b, symbol, |x| self.get_port_unchecked(x))
+    }
+
+    fn apply(&mut self, plan: Plan, observer: &mut dyn NetObserver) -> Vec<usize> {
+        let (a, b) = plan.pair;
+        let rule = (self.agents[&a].symbol, self.agents[&b].symbol);
+        observer.on_rewrite(rule, a, b);
+        let mut agents = Vec::new();
+        for &symbol in &plan.agents {
+            let c = self.create(symbol);
+            observer.on_create(c, symbol);
+            agents.push(c);
+        }
+        let port = |x| match x {
+            End::Old(p) => p,
+            End::New(i, p) => Port::new(agents[i], p),
+        };
+        for (x, y) in plan.wires {
+            self.connect_observed(port(x), port(y), observer);
+        }
+        for x in [a, b] {
+            self.delete(x);
+            observer.on_delete(x);
+        }
+        self.trace(rule, a, b, &agents);
+        agents
+    }
+}
+
+/// End of a wire in a [`Plan`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+enum End {
+    Old(Port),
+    /// Port of the created agent at the given index.
+    New(usize, usize),
+}
+
+/// How to replace an active pair.
+struct Plan {
+    pair: (usize, usize),
+    agents: Vec<Symbol>,
+    wires: Vec<(End, End)>,
+}
+
+impl Plan {
+    /// Computes how to replace the active pair `a`-`b`.
+    ///
+    /// The net is given by the `symbol` of its agents and the `port` connected to each port.
+    fn new(
+        rules: &RuleSet,
+        a: usize,
+        b: usize,
+        symbol: impl Fn(usize) -> Symbol,
+        port: impl Fn(Port) -> Port,
+    ) -> Plan {
+        let rule = rules.rule(symbol(a), symbol(b)).unwrap();
+        let end = |x| match x {
+            Slot::Left(p) => End::Old(Port::new(a, p)),
+            Slot::Right(p) => End::Old(Port::new(b, p)),
+            Slot::Agent(i, p) => End::New(i, p),
+        };
+        let inside = |x| matches!(x, End::Old(p) if p.agent == a || p.agent == b);
+        let mut wires = Vec::new();
+        let mut inner = HashMap::new();
+        let mut outer = HashMap::new();
+        for &(x, y) in &rule.wires {
+            let (x, y) = (end(x), end(y));
+            if !inside(x) && !inside(y) {
+                wires.push((x, y));
+                continue;
+            }
+            inner.insert(x, y);
+            inner.insert(y, x);
+            for z in [x, y] {
+                if let End::Old(p) = z {
+                    outer.insert(z, End::Old(port(p)));
+                }
+            }
+        }
+        let follow = |mut x, mut from_outer: bool| loop {
+            let y = if from_outer { inner[&x] } else { outer[&x] };
+            if !inside(y) {
+                return y;
+            }
+            x = y;
+            from_outer = !from_outer;
+        };
+        // Each wire is found from both of its ends, so we only keep one direction.
+        for (&x, &y) in inner.iter() {
+            if !inside(x) {
+                let y = follow(y, false);
+                if x < y {
+                    wires.push((x, y));
+                }
+            }
+        }
+        for (&x, &y) in outer.iter() {
+            if !inside(y) {
+                let x = follow(x, true);
+                if y < x {
+                    wires.push((y, x));
+                }
+            }
+        }
+        // The maps above iterate in arbitrary order, but the order of the wires decides the order
+        // of the created active pairs in the worklist.
+        wires.sort_unstable();
+        Plan {
+            pair: (a, b),
+            agents: rule.agents.clone(),
+            wires,
+        }
+    }
+}
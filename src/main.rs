@@ -12,271 +12,1633 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use getopts::Options;
-use kiss3d::camera::ArcBall;
+use clap::Parser;
+use cli::{Cli, Command, Explain, Inputs, ReduceArgs, ServeArgs, ViewArgs};
+use csv::StatsCsv;
+use kiss3d::camera::{ArcBall, Camera};
+use kiss3d::event::{Action, Key, Modifiers, MouseButton, WindowEvent};
 use kiss3d::light::Light;
-use kiss3d::nalgebra::core::Vector3;
-use kiss3d::nalgebra::geometry::{Point3, Translation3};
-use kiss3d::scene::SceneNode;
-use kiss3d::window::Window;
-use rand::random;
-use std::collections::HashMap;
+use kiss3d::nalgebra::core::{Vector2, Vector3};
+use kiss3d::nalgebra::geometry::{Point2, Point3};
+use kiss3d::planar_camera::PlanarCamera;
+use kiss3d::post_processing::PostProcessingEffect;
+use kiss3d::renderer::Renderer;
+use kiss3d::text::Font;
+use kiss3d::window::{State, Window};
+use lafont::{Estimate, Net, NetObserver, Port, ReductionStats, RuleSet, Strategy, Symbol};
+use layout::{ForceDirected, Frozen, Hierarchical, Layout, PhysicsConfig};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use spheres::Spheres;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::BuildHasherDefault;
+use std::io::Write;
+use std::rc::Rc;
 use std::time::{Duration, Instant};
+use theme::{Color, Style, Theme};
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-struct Port {
-    agent: usize,
-    port: usize,
+#[cfg(feature = "serde")]
+mod autosave;
+mod cli;
+mod csv;
+mod debug;
+mod layout;
+mod octree;
+#[cfg(feature = "serde")]
+mod save;
+#[cfg(feature = "server")]
+mod server;
+mod spheres;
+mod theme;
+
+/// Radius in pixels of the points drawn instead of far agents.
+const POINT_RADIUS: f32 = 4.;
+
+/// Number of steps the viewer probes the net for before warning that it probably diverges.
+const ESTIMATE_BUDGET: usize = 10_000;
+
+/// Number of frames the demo shows a normal form before fading it out.
+const DEMO_HOLD: usize = 120;
+
+/// Number of random nets the demo draws before giving up on finding one that normalizes.
+const DEMO_ATTEMPTS: usize = 100;
+
+struct Node {
+    symbol: Symbol,
+    position: Vector3<f32>,
+    velocity: Vector3<f32>,
+    radius: f32,
+    /// Radius of the sphere, which differs from `radius` while animated.
+    scale: f32,
+    color: [f32; 3],
+    /// Whether the sphere is drawn, false for far agents.
+    visible: bool,
+    /// Index of the net of the workspace the agent belongs to.
+    part: usize,
+    /// Super-node the agent is collapsed into, drawn instead of its sphere.
+    group: Option<usize>,
+    /// Recency of the last rewrite the agent took part in, from 1 when just rewritten to 0.
+    heat: f32,
 }
 
-impl Port {
-    fn new(agent: usize, port: usize) -> Port {
-        Port { agent, port }
+/// Nodes by agent.
+///
+/// The hasher is deterministic, so that nodes are iterated in the same order every run, and the
+/// layout is reproducible with the same seed.
+type Nodes = HashMap<usize, Node, BuildHasherDefault<DefaultHasher>>;
+
+/// Center and agents by super-node.
+type Groups = BTreeMap<usize, (Vector3<f32>, Vec<usize>)>;
+
+/// Nodes of the agents of a net, kept in sync by observing its reduction.
+struct Scene {
+    window: Window,
+    nodes: Nodes,
+    /// Where created agents appear.
+    spawn: Vector3<f32>,
+    /// Net of the workspace created agents belong to.
+    part: usize,
+    /// Number of frames to animate created and deleted agents, 0 to disable.
+    frames: usize,
+    /// Nodes of deleted agents shrinking, with their remaining frames.
+    fading: Vec<(Node, usize)>,
+    /// Created agents growing, with their remaining frames.
+    growing: HashMap<usize, usize>,
+    /// Agents created since the last rewrite started.
+    created: Vec<usize>,
+    theme: Theme,
+}
+
+impl Scene {
+    fn node(&self, a: usize) -> &Node {
+        self.nodes.get(&a).unwrap()
+    }
+
+    /// Returns the center and the agents of each super-node.
+    fn groups(&self) -> Groups {
+        let mut groups = BTreeMap::new();
+        for (&a, n) in &self.nodes {
+            if let Some(g) = n.group {
+                let (sum, members) = groups
+                    .entry(g)
+                    .or_insert_with(|| (Vector3::zeros(), Vec::new()));
+                *sum += n.position;
+                members.push(a);
+            }
+        }
+        for (center, members) in groups.values_mut() {
+            *center /= members.len() as f32;
+        }
+        groups
+    }
+
+    /// Returns where the wires of agent `a` end, which is the center of its super-node if any.
+    fn tip(&self, a: usize, groups: &Groups) -> Vector3<f32> {
+        let n = self.node(a);
+        n.group.map_or(n.position, |g| groups[&g].0)
+    }
+
+    /// Advances the animations of created and deleted agents by a frame.
+    ///
+    /// Deleted agents shrink. Created agents grow while sliding along their wires toward their
+    /// neighbors.
+    fn animate(&mut self, net: &Net) {
+        let frames = self.frames as f32;
+        for (n, left) in &mut self.fading {
+            *left -= 1;
+            n.scale = n.radius * *left as f32 / frames;
+        }
+        self.fading.retain(|&(_, left)| left > 0);
+        for (&a, left) in &mut self.growing {
+            *left -= 1;
+            let mut neighbors = Vec::new();
+            for p in 0..=net.rules().arity(net.symbol(a).unwrap()) {
+                let y = net.get_port(Port::new(a, p));
+                neighbors.extend(y.ok().and_then(|y| self.nodes.get(&y.agent)));
+            }
+            let mut position = self.nodes[&a].position;
+            if !neighbors.is_empty() {
+                let sum = neighbors
+                    .iter()
+                    .fold(Vector3::zeros(), |s, m| s + m.position);
+                let target = sum / neighbors.len() as f32;
+                position += (target - position) * 0.5 / frames;
+            }
+            let n = self.nodes.get_mut(&a).unwrap();
+            n.position = position;
+            n.scale = n.radius * (1. - *left as f32 / frames);
+        }
+        self.growing.retain(|_, left| *left > 0);
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-enum Agent {
-    Construct([Option<Port>; 3]),
-    Duplicate([Option<Port>; 3]),
-    Erase([Option<Port>; 1]),
+impl NetObserver for Scene {
+    fn on_create(&mut self, a: usize, symbol: Symbol) {
+        let Style { color, radius } = *self.theme.style(symbol);
+        let mut scale = radius;
+        if self.frames > 0 {
+            scale = 0.;
+            self.growing.insert(a, self.frames);
+        }
+        let n = Node {
+            symbol,
+            position: self.spawn,
+            velocity: Vector3::zeros(),
+            radius,
+            scale,
+            color,
+            visible: true,
+            part: self.part,
+            group: None,
+            heat: 0.,
+        };
+        assert!(self.nodes.insert(a, n).is_none());
+        self.created.push(a);
+    }
+
+    fn on_delete(&mut self, a: usize) {
+        let n = self.nodes.remove(&a).unwrap();
+        self.growing.remove(&a);
+        if self.frames > 0 && n.group.is_none() {
+            self.fading.push((n, self.frames));
+        }
+    }
 }
 
-struct Node {
-    agent: Agent,
-    scene: SceneNode,
-    velocity: Vector3<f32>,
+/// When active pairs are reduced in the viewer.
+#[derive(Clone, Copy)]
+enum React {
+    /// When the layout brings their agents close enough.
+    Proximity,
+    /// After this many steps, while their agents are pulled toward each other.
+    Timer(usize),
+    /// At the next step.
+    Instant,
+    /// One at a time, when the layout is settled.
+    Settled,
 }
 
-impl Node {
-    fn get_port(&self, p: usize) -> Port {
-        match self.agent {
-            Agent::Construct(ref ports) => ports[p].unwrap(),
-            Agent::Duplicate(ref ports) => ports[p].unwrap(),
-            Agent::Erase(ref ports) => ports[p].unwrap(),
+impl React {
+    fn parse(name: &str) -> Result<React, String> {
+        let (name, args) = match name.split_once(':') {
+            Some((name, args)) => (name, Some(args)),
+            None => (name, None),
+        };
+        match (name, args) {
+            ("proximity", None) => Ok(React::Proximity),
+            ("instant", None) => Ok(React::Instant),
+            ("settled", None) => Ok(React::Settled),
+            ("timer", None) => Ok(React::Timer(60)),
+            ("timer", Some(n)) => n.parse().map(React::Timer).map_err(|e| e.to_string()),
+            _ => Err(format!("unknown react mode {:?}", name)),
         }
     }
+}
+
+/// Reads the number of steps active pairs wait for per rule, smallest symbol first, from the
+/// TOML file `path`.
+///
+/// Rules are named after their symbols, in any order, like `construct-duplicate = 30`.
+#[cfg(feature = "serde")]
+fn read_delays(path: &str, rules: &RuleSet) -> Result<HashMap<(Symbol, Symbol), usize>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let delays: HashMap<String, usize> =
+        toml::from_str(&content).map_err(|e| format!("{}: {}", path, e))?;
+    let symbol = |name: &str| rules.symbols().find(|&s| rules.name(s) == name);
+    let mut result = HashMap::new();
+    for (rule, delay) in delays {
+        let (x, y) = rule
+            .split_once('-')
+            .and_then(|(x, y)| Some((symbol(x)?, symbol(y)?)))
+            .ok_or_else(|| format!("{}: unknown rule {:?}", path, rule))?;
+        result.insert((x.min(y), x.max(y)), delay);
+    }
+    Ok(result)
+}
+
+#[cfg(not(feature = "serde"))]
+fn read_delays(path: &str, _: &RuleSet) -> Result<HashMap<(Symbol, Symbol), usize>, String> {
+    Err(format!("{}: delay files require the serde feature", path))
+}
 
-    fn set_port(&mut self, p: usize, x: Port) {
-        match self.agent {
-            Agent::Construct(ref mut ports) => ports[p] = Some(x),
-            Agent::Duplicate(ref mut ports) => ports[p] = Some(x),
-            Agent::Erase(ref mut ports) => ports[p] = Some(x),
+/// Returns the sub-net injected with `key`, its name, and its free port glued to the net.
+///
+/// Digits apply the net to a Church numeral, and `E` erases it.
+fn sub_net(key: Key) -> Option<(String, Net, usize)> {
+    let digits = [
+        Key::Key0,
+        Key::Key1,
+        Key::Key2,
+        Key::Key3,
+        Key::Key4,
+        Key::Key5,
+        Key::Key6,
+        Key::Key7,
+        Key::Key8,
+        Key::Key9,
+    ];
+    let (name, sub) = match digits.iter().position(|&k| k == key) {
+        Some(n) => {
+            let (numeral, root) = lafont::gen::church(n);
+            let app = Net::from_sic("fun = (result arg)").unwrap();
+            let arg = app.find_free("arg").unwrap();
+            let sub = app.compose(&numeral, &[(arg, root)]).unwrap();
+            (format!("church:{}", n), sub)
+        }
+        None if key == Key::E => ("eraser".to_string(), Net::from_sic("fun = *").unwrap()),
+        None => return None,
+    };
+    let port = sub.find_free("fun").unwrap();
+    Some((name, sub, port))
+}
+
+/// Random nets reduced one after the other, see `--demo`.
+struct Demo {
+    /// Number of agents of the nets.
+    agents: usize,
+    /// Number of frames since the net reached its normal form.
+    done: usize,
+}
+
+/// Returns a random net of about `agents` agents that normalizes, named after its seed.
+fn demo_net(agents: usize, rng: &mut StdRng) -> Result<(String, Net), String> {
+    for _ in 0..DEMO_ATTEMPTS {
+        let name = format!("random:{},{}", agents, rng.gen::<u32>());
+        let net = lafont::gen::example(&name)?;
+        if let Estimate::Normalizes { .. } = net.estimate(ESTIMATE_BUDGET) {
+            return Ok((name, net));
         }
     }
+    Err(format!("no random net of {} agents normalizes", agents))
+}
+
+/// Where the agents created by a rewrite appear.
+///
+/// Agents appearing on top of each other are pushed apart violently by the force layout, so
+/// the placement affects how stable the layout is.
+#[derive(Clone, Copy)]
+enum SpawnPolicy {
+    /// At the middle of the active pair.
+    Midpoint,
+    /// Around the middle of the active pair, at random.
+    Jittered,
+    /// A third of the way from the middle of the active pair to the agents they connect to.
+    AlongWire,
+}
 
-    fn position(&self) -> Vector3<f32> {
-        self.scene.data().local_translation().vector
+impl SpawnPolicy {
+    fn parse(name: &str) -> Result<SpawnPolicy, String> {
+        match name {
+            "midpoint" => Ok(SpawnPolicy::Midpoint),
+            "jittered" => Ok(SpawnPolicy::Jittered),
+            "along-wire" => Ok(SpawnPolicy::AlongWire),
+            _ => Err(format!("unknown spawn policy {:?}", name)),
+        }
     }
 }
 
-struct Net {
-    window: Window,
-    camera: ArcBall,
-    nodes: HashMap<usize, Node>,
+/// One of the nets shown side by side in the viewer.
+struct Part {
+    name: String,
+    /// Number of reduced active pairs.
+    reductions: usize,
+}
+
+impl Part {
+    fn new(name: String) -> Part {
+        Part {
+            name,
+            reductions: 0,
+        }
+    }
+}
+
+/// Connected components of the agents, to tint each with its own hue.
+///
+/// Rewrites never connect two components, but they may split one. Components are relabeled once
+/// per frame, starting only from the agents next to the rewrites of the frame.
+#[derive(Default)]
+struct Components {
+    /// Label of the component of each agent.
+    labels: HashMap<usize, usize>,
+    /// Agents next to the rewrites since the last update.
+    touched: Vec<usize>,
+    /// Label of the next new component.
     next: usize,
 }
 
-impl Net {
-    fn new() -> Net {
-        let mut net = Net {
+impl Components {
+    /// Labels the components of all the agents of `net`.
+    fn new(net: &Net) -> Components {
+        let mut components = Components::default();
+        for component in net.components() {
+            let label = components.fresh();
+            components
+                .labels
+                .extend(component.into_iter().map(|a| (a, label)));
+        }
+        components
+    }
+
+    fn fresh(&mut self) -> usize {
+        self.next += 1;
+        self.next - 1
+    }
+
+    /// Relabels the components of the touched agents.
+    ///
+    /// When a component splits, its largest part keeps its label, so that its color stays, and
+    /// the other parts get new labels.
+    fn update(&mut self, net: &Net) {
+        self.labels.retain(|&a, _| net.symbol(a).is_some());
+        let mut parts = Vec::new();
+        let mut seen = HashSet::new();
+        for a in std::mem::take(&mut self.touched) {
+            if net.symbol(a).is_some() && !seen.contains(&a) {
+                let part = net.component(a);
+                seen.extend(part.iter().copied());
+                parts.push(part);
+            }
+        }
+        parts.sort_by_key(|part| std::cmp::Reverse(part.len()));
+        let mut kept = HashSet::new();
+        for part in parts {
+            let mut counts = HashMap::new();
+            for a in &part {
+                if let Some(&label) = self.labels.get(a) {
+                    *counts.entry(label).or_insert(0) += 1;
+                }
+            }
+            counts.retain(|label, _| !kept.contains(label));
+            let label = match counts
+                .into_iter()
+                .max_by_key(|&(label, count)| (count, label))
+            {
+                Some((label, _)) => label,
+                None => self.fresh(),
+            };
+            kept.insert(label);
+            self.labels.extend(part.into_iter().map(|a| (a, label)));
+        }
+    }
+
+    /// Returns the number of components.
+    fn len(&self) -> usize {
+        self.labels.values().collect::<HashSet<_>>().len()
+    }
+
+    /// Returns the color of the component of agent `a`, spreading hues by the golden ratio.
+    fn color(&self, a: usize) -> Color {
+        let label = self.labels.get(&a).copied().unwrap_or(0);
+        let h = (label as f32 * 0.618_034).fract() * 6.;
+        let x = 1. - (h % 2. - 1.).abs();
+        match h as usize {
+            0 => [1., x, 0.],
+            1 => [x, 1., 0.],
+            2 => [0., 1., x],
+            3 => [0., x, 1.],
+            4 => [x, 0., 1.],
+            _ => [1., 0., x],
+        }
+    }
+}
+
+/// Weight of the hue of its component in the color of an agent.
+const TINT: f32 = 0.6;
+
+/// Factor by which the heat of agents decreases every frame.
+const COOLING: f32 = 0.97;
+
+/// Returns `color` mixed with `other` by `t` between 0 and 1.
+fn mix(color: Color, other: Color, t: f32) -> Color {
+    std::array::from_fn(|i| color[i] + (other[i] - color[i]) * t)
+}
+
+/// Returns the radius of a super-node of `count` agents, such that its volume grows with them.
+fn group_radius(count: usize) -> f32 {
+    1. + (count as f32).cbrt()
+}
+
+/// Puts `nets` in a single net, returning the first id of each net in the result.
+fn workspace(nets: Vec<(String, Net)>) -> Result<(Net, Vec<usize>), String> {
+    let mut result: Option<Net> = None;
+    let mut starts = Vec::new();
+    for (name, net) in nets {
+        let start = result.as_ref().map_or(0, Net::next_id);
+        result = Some(match result {
+            None => net,
+            Some(result) => result
+                .compose(&net, &[])
+                .map_err(|e| format!("{}: {}", name, e))?,
+        });
+        starts.push(start);
+    }
+    Ok((result.unwrap_or_else(Net::empty), starts))
+}
+
+/// What kiss3d renders a frame with, besides the scene nodes of the window.
+struct View {
+    camera: ArcBall,
+    spheres: Spheres,
+}
+
+impl State for View {
+    fn step(&mut self, _: &mut Window) {}
+
+    fn cameras_and_effect_and_renderer(
+        &mut self,
+    ) -> (
+        Option<&mut dyn Camera>,
+        Option<&mut dyn PlanarCamera>,
+        Option<&mut dyn Renderer>,
+        Option<&mut dyn PostProcessingEffect>,
+    ) {
+        (Some(&mut self.camera), None, Some(&mut self.spheres), None)
+    }
+}
+
+/// Displays nets side by side, rewriting active pairs when their agents collide.
+///
+/// The nets are disjoint parts of a single net, so they reduce in lockstep.
+struct Viewer {
+    scene: Scene,
+    view: View,
+    net: Net,
+    /// Nets of the workspace, in the order of their ids.
+    parts: Vec<Part>,
+    /// Where Ctrl+S saves and from where Ctrl+L loads.
+    file: String,
+    /// Number of steps per frame.
+    speed: i32,
+    paused: bool,
+    /// Number of reduced active pairs.
+    reductions: usize,
+    /// Agent picked with the mouse.
+    selected: Option<usize>,
+    /// Agents whose color was changed to highlight the selection.
+    highlighted: Vec<usize>,
+    /// Cursor position when the left button was pressed.
+    press: Option<(f64, f64)>,
+    layout: Box<dyn Layout>,
+    /// Time step of the layout.
+    dt: f32,
+    react: React,
+    /// Number of steps the active pairs of some rules wait for, overriding `react`.
+    delays: HashMap<(Symbol, Symbol), usize>,
+    /// Number of steps each active pair has been waiting for in timer mode.
+    waiting: HashMap<(usize, usize), usize>,
+    /// Whether agents are labeled with their id and symbol.
+    labels: bool,
+    /// Whether agents are colored by the recency of their last rewrite.
+    heatmap: bool,
+    /// Connected components whose agents are tinted, if enabled.
+    components: Option<Components>,
+    /// Number of agents above which the simulation pauses.
+    max_nodes: Option<usize>,
+    /// Number of reduced active pairs per pair of symbols, smallest symbol first.
+    rules: HashMap<(Symbol, Symbol), usize>,
+    /// Where statistics are written every frame.
+    stats: Option<StatsCsv>,
+    /// Where agents created by rewrites appear.
+    spawn: SpawnPolicy,
+    /// Random generator of the layout and spawn policy.
+    rng: StdRng,
+    /// Kinetic energy of the nodes after the last layout update.
+    energy: f32,
+    /// Kinetic energy under which the layout is settled.
+    settle: f32,
+    /// Fixed positions of free ports, which keep the agent they are connected to nearby.
+    anchors: BTreeMap<usize, Vector3<f32>>,
+    /// Number of agents above which far agents are drawn as points.
+    lod: usize,
+    /// Agents drawn as points, whose sphere is hidden.
+    far: HashSet<usize>,
+    /// Number of super-nodes collapsed so far, which names the next one.
+    groups: usize,
+    /// When the viewer started, which the wires of active pairs pulse after.
+    started: Instant,
+    /// Whether rewrites are explained, and whether the simulation pauses after them.
+    explain: Option<Explain>,
+    /// Explanation of the last rewrite.
+    explanation: Option<String>,
+    /// Warning shown when the initial net probably diverges.
+    warning: Option<String>,
+    /// Whether random nets replace each other once reduced.
+    demo: Option<Demo>,
+    /// Number of rewrites between saved images.
+    snapshot_every: Option<usize>,
+    /// Number of rewrites after which the next image is saved.
+    next_snapshot: usize,
+}
+
+impl Viewer {
+    fn new(
+        names: Vec<String>,
+        (net, starts): (Net, Vec<usize>),
+        file: String,
+        layout: Box<dyn Layout>,
+        dt: f32,
+        react: React,
+        theme: Theme,
+    ) -> Viewer {
+        let mut scene = Scene {
             window: Window::new("Lafont"),
-            camera: ArcBall::new(Point3::new(100., 0., 0.), Point3::origin()),
-            nodes: HashMap::new(),
-            next: 0,
+            nodes: Nodes::default(),
+            spawn: Vector3::zeros(),
+            part: 0,
+            frames: 0,
+            fading: Vec::new(),
+            growing: HashMap::new(),
+            created: Vec::new(),
+            theme,
+        };
+        let [r, g, b] = scene.theme.background;
+        scene.window.set_background_color(r, g, b);
+        scene.window.set_light(Light::StickToCamera);
+        scene.window.set_point_size(2. * POINT_RADIUS);
+        let parts: Vec<_> = names.into_iter().map(Part::new).collect();
+        let mut agents: Vec<_> = net.agents().collect();
+        agents.sort_unstable();
+        for a in agents {
+            // Nets are spread along the vertical axis, which is visible in 2D and 3D.
+            scene.part = starts.partition_point(|&start| start <= a) - 1;
+            let offset = scene.part as f32 - (parts.len() - 1) as f32 / 2.;
+            scene.spawn = Vector3::new(0., 50. * offset, 0.);
+            scene.on_create(a, net.symbol(a).unwrap());
+        }
+        scene.part = 0;
+        scene.spawn = Vector3::zeros();
+        let eye = if layout.planar() {
+            Point3::new(0., 0., 100.)
+        } else {
+            Point3::new(100., 0., 0.)
         };
-        net.window.set_light(Light::StickToCamera);
-        net
+        Viewer {
+            scene,
+            view: View {
+                camera: ArcBall::new(eye, Point3::origin()),
+                spheres: Spheres::new(),
+            },
+            layout,
+            dt,
+            react,
+            delays: HashMap::new(),
+            waiting: HashMap::new(),
+            labels: false,
+            heatmap: false,
+            components: None,
+            max_nodes: None,
+            rules: HashMap::new(),
+            stats: None,
+            spawn: SpawnPolicy::Midpoint,
+            rng: StdRng::from_entropy(),
+            energy: f32::INFINITY,
+            settle: 1e-3,
+            anchors: BTreeMap::new(),
+            lod: 10_000,
+            far: HashSet::new(),
+            groups: 0,
+            started: Instant::now(),
+            explain: None,
+            explanation: None,
+            warning: None,
+            demo: None,
+            snapshot_every: None,
+            next_snapshot: 0,
+            net,
+            parts,
+            file,
+            speed: 1,
+            paused: false,
+            reductions: 0,
+            selected: None,
+            highlighted: Vec::new(),
+            press: None,
+        }
     }
 
-    fn node(&self, a: usize) -> &Node {
-        self.nodes.get(&a).unwrap()
+    fn key(&mut self, key: Key, modifiers: Modifiers) {
+        let control = modifiers.contains(Modifiers::Control);
+        match key {
+            Key::S | Key::L if control => self.snapshot(key == Key::S),
+            Key::Space => self.paused = !self.paused,
+            Key::L => self.labels = !self.labels,
+            Key::H => self.heatmap = !self.heatmap,
+            Key::K => self.toggle_components(),
+            Key::G => self.collapse(),
+            Key::S if self.paused => self.reduce_one(),
+            Key::Equals | Key::Add => self.speed += 1,
+            Key::Minus | Key::Subtract => self.speed = (self.speed - 1).max(1),
+            Key::A | Key::C | Key::D | Key::J => self.tune(key, modifiers),
+            _ => {
+                if let Some((name, sub, port)) = sub_net(key) {
+                    self.inject(&name, &sub, port);
+                }
+            }
+        }
     }
 
-    fn node_mut(&mut self, a: usize) -> &mut Node {
-        self.nodes.get_mut(&a).unwrap()
+    /// Glues the free port `port` of `sub` to the free port of the selected agent, or to the
+    /// first free port, and creates the nodes of its agents next to the glued agent.
+    fn inject(&mut self, name: &str, sub: &Net, port: usize) {
+        let mut free: Vec<_> = self.net.free().collect();
+        free.sort_unstable();
+        let neighbor = |f| self.net.get_port(Port::new(f, 0)).ok().map(|y| y.agent);
+        let target = free
+            .iter()
+            .find(|&&f| neighbor(f).is_some_and(|a| Some(a) == self.selected));
+        let f = match target.or(free.first()) {
+            Some(&f) => f,
+            None => {
+                println!("no free port to inject {} into", name);
+                return;
+            }
+        };
+        let near = neighbor(f).and_then(|a| self.scene.nodes.get(&a));
+        let (spawn, part) = near.map_or((Vector3::zeros(), 0), |n| (n.position, n.part));
+        let shift = self.net.next_id();
+        self.net = match self.net.compose(sub, &[(f, port)]) {
+            Ok(net) => net,
+            Err(e) => {
+                println!("error: {}", e);
+                return;
+            }
+        };
+        println!("injected {} into free port {}", name, f);
+        let scene = &mut self.scene;
+        scene.spawn = spawn;
+        scene.part = part;
+        let mut created: Vec<_> = self.net.agents().filter(|&a| a >= shift).collect();
+        created.sort_unstable();
+        for a in created {
+            scene.on_create(a, self.net.symbol(a).unwrap());
+        }
+        // The free ports of the sub-net take the place of the glued one.
+        if let Some(anchor) = self.anchors.remove(&f) {
+            let free = self.net.free().filter(|&g| g >= shift);
+            self.anchors.extend(free.map(|g| (g, anchor)));
+        }
+        self.energy = f32::INFINITY;
+        if self.components.is_some() {
+            self.components = Some(Components::new(&self.net));
+        }
     }
 
-    fn get_port(&self, x: Port) -> Port {
-        self.node(x.agent).get_port(x.port)
+    /// Replaces the workspace with the net `net` named `name`, whose agents start at the origin.
+    fn replace(&mut self, name: String, net: Net) {
+        let scene = &mut self.scene;
+        let agents: Vec<_> = scene.nodes.keys().copied().collect();
+        for a in agents {
+            scene.on_delete(a);
+        }
+        self.net = net;
+        self.parts = vec![Part::new(name)];
+        scene.spawn = Vector3::zeros();
+        scene.part = 0;
+        for a in self.net.agents() {
+            scene.on_create(a, self.net.symbol(a).unwrap());
+        }
+        // Agent ids of the new net may be the ones of old agents.
+        self.selected = None;
+        self.highlighted.clear();
+        self.waiting.clear();
+        self.far.clear();
+        self.energy = f32::INFINITY;
+        if self.components.is_some() {
+            self.components = Some(Components::new(&self.net));
+        }
     }
 
-    fn set_port(&mut self, x: Port, y: Port) {
-        self.node_mut(x.agent).set_port(x.port, y);
+    /// Fades the net out once it reached its normal form for a while, then replaces it with a
+    /// new random net.
+    fn demo(&mut self) {
+        let demo = match &mut self.demo {
+            Some(demo) if !self.paused => demo,
+            _ => return,
+        };
+        if !self.net.active_pairs().is_empty() {
+            demo.done = 0;
+            return;
+        }
+        demo.done += 1;
+        let (agents, done) = (demo.agents, demo.done);
+        if done == DEMO_HOLD {
+            self.replace(String::new(), Net::with_rules(self.net.rules().clone()));
+        } else if done > DEMO_HOLD + self.scene.frames {
+            self.demo.as_mut().unwrap().done = 0;
+            match demo_net(agents, &mut self.rng) {
+                Ok((name, net)) => self.replace(name, net),
+                Err(e) => {
+                    println!("error: {}", e);
+                    self.demo = None;
+                }
+            }
+        }
     }
 
-    fn create(&mut self, agent: Agent) -> usize {
-        let a = self.next;
-        let scene = self.window.add_sphere(1.);
-        let velocity = Vector3::zeros();
-        let n = Node {
-            agent,
-            scene,
-            velocity,
+    /// Enables or disables the tint of connected components.
+    fn toggle_components(&mut self) {
+        self.components = match self.components {
+            Some(_) => None,
+            None => Some(Components::new(&self.net)),
         };
-        assert!(self.nodes.insert(a, n).is_none());
-        self.next += 1;
-        a
     }
 
-    fn create_construct(&mut self) -> usize {
-        let a = self.create(Agent::Construct([None; 3]));
-        self.node_mut(a).scene.set_color(0., 0., 1.);
-        a
+    /// Decreases a physics constant, or increases it with Shift, and prints it.
+    fn tune(&mut self, key: Key, modifiers: Modifiers) {
+        let name = match key {
+            Key::A => "attraction",
+            Key::C => "collision",
+            Key::D => "damping",
+            Key::J => "jitter",
+            _ => unreachable!(),
+        };
+        let physics = match self.layout.physics() {
+            Some(x) => x,
+            None => return,
+        };
+        let value = physics.get_mut(name).unwrap();
+        if modifiers.contains(Modifiers::Shift) {
+            *value *= 1.25;
+        } else {
+            *value /= 1.25;
+        }
+        println!("{} = {}", name, value);
+        self.energy = f32::INFINITY;
     }
 
-    fn create_duplicate(&mut self) -> usize {
-        let a = self.create(Agent::Duplicate([None; 3]));
-        self.node_mut(a).scene.set_color(0., 1., 0.);
-        a
+    /// Selects the agent under the cursor and prints it.
+    fn pick(&mut self, (x, y): (f64, f64)) {
+        let size = self.scene.window.size();
+        let size = Vector2::new(size.x as f32, size.y as f32);
+        let cursor = Point2::new(x as f32, y as f32);
+        let (origin, direction) = self.view.camera.unproject(&cursor, &size);
+        // Super-nodes are picked through any of their agents.
+        let groups = self.scene.groups();
+        let mut spheres = Vec::new();
+        for (&a, n) in self.scene.nodes.iter() {
+            if n.group.is_none() {
+                spheres.push((a, n.position, n.radius));
+            }
+        }
+        for (center, members) in groups.values() {
+            spheres.push((members[0], *center, group_radius(members.len())));
+        }
+        let mut best = None;
+        for (a, position, radius) in spheres {
+            let center = position - origin.coords;
+            let t = center.dot(&direction);
+            let hit = t > 0. && (center - t * direction).norm() < radius;
+            if hit && best.is_none_or(|(_, u)| t < u) {
+                best = Some((a, t));
+            }
+        }
+        self.selected = best.map(|(a, _)| a);
+        let a = match self.selected {
+            None => return,
+            Some(a) => a,
+        };
+        if let Some(g) = self.scene.node(a).group {
+            println!("group {}: {} agents", g, groups[&g].1.len());
+            return;
+        }
+        let symbol = self.net.symbol(a).unwrap();
+        print!("agent {} {}:", a, self.net.rules().name(symbol));
+        for p in 0..=self.net.rules().arity(symbol) {
+            match self.net.get_port(Port::new(a, p)) {
+                Ok(y) => print!(" {}.{}", y.agent, y.port),
+                Err(_) => print!(" -"),
+            }
+        }
+        println!();
     }
 
-    fn create_erase(&mut self) -> usize {
-        let a = self.create(Agent::Erase([None; 1]));
-        self.node_mut(a).scene.set_color(1., 0., 0.);
-        a
+    /// Colors the selected agent in white and its neighbors in yellow.
+    fn highlight(&mut self) {
+        for a in self.highlighted.drain(..) {
+            if let (Some(n), Some(symbol)) = (self.scene.nodes.get_mut(&a), self.net.symbol(a)) {
+                n.color = self.scene.theme.style(symbol).color;
+            }
+        }
+        let a = match self.selected {
+            Some(a) if self.scene.nodes.contains_key(&a) => a,
+            _ => return,
+        };
+        let symbol = self.net.symbol(a).unwrap();
+        for p in 0..=self.net.rules().arity(symbol) {
+            if let Ok(y) = self.net.get_port(Port::new(a, p)) {
+                if let Some(n) = self.scene.nodes.get_mut(&y.agent) {
+                    n.color = self.scene.theme.neighbor;
+                    self.highlighted.push(y.agent);
+                }
+            }
+        }
+        let n = self.scene.nodes.get_mut(&a).unwrap();
+        n.color = self.scene.theme.selected;
+        self.highlighted.push(a);
     }
 
-    fn delete(&mut self, a: usize) {
-        self.node_mut(a).scene.unlink();
-        assert!(self.nodes.remove(&a).is_some());
+    /// Collapses the selected agent and its neighbors into a super-node, or expands the selected
+    /// super-node.
+    ///
+    /// Super-nodes containing the collapsed agents are merged. Agents created by rewrites between
+    /// two agents of a super-node join it. The wires at the boundary are drawn to the center of
+    /// the super-node.
+    fn collapse(&mut self) {
+        let a = match self.selected {
+            Some(a) if self.scene.nodes.contains_key(&a) => a,
+            _ => return,
+        };
+        if let Some(g) = self.scene.node(a).group {
+            for n in self.scene.nodes.values_mut() {
+                if n.group == Some(g) {
+                    n.group = None;
+                }
+            }
+            println!("expanded group {}", g);
+            return;
+        }
+        let mut merged: HashSet<_> = self.highlighted.iter().copied().collect();
+        merged.insert(a);
+        let groups: HashSet<_> = merged
+            .iter()
+            .filter_map(|a| self.scene.nodes.get(a)?.group)
+            .collect();
+        let g = self.groups;
+        self.groups += 1;
+        let mut count = 0;
+        for (a, n) in self.scene.nodes.iter_mut() {
+            if merged.contains(a) || n.group.is_some_and(|g| groups.contains(&g)) {
+                n.group = Some(g);
+                count += 1;
+            }
+        }
+        println!("collapsed {} agents into group {}", count, g);
     }
 
-    fn connect(&mut self, x: Port, y: Port) {
-        self.set_port(x, y);
-        self.set_port(y, x);
+    /// Fixes free port `port` at `position`.
+    ///
+    /// Whichever agent the port is connected to, after any number of reductions, is pulled
+    /// toward `position` such that the interface of the net does not drift away.
+    fn anchor(&mut self, port: usize, position: Vector3<f32>) {
+        self.anchors.insert(port, position);
     }
 
-    fn eval_cc(&mut self, a: usize, b: usize) {
-        self.connect(
-            self.get_port(Port::new(a, 1)),
-            self.get_port(Port::new(b, 2)),
-        );
-        self.connect(
-            self.get_port(Port::new(a, 2)),
-            self.get_port(Port::new(b, 1)),
-        );
-        self.delete(a);
-        self.delete(b);
-    }
-
-    fn eval_cd(&mut self, a: usize, b: usize, t: Translation3<f32>) {
-        let c = self.create_construct();
-        let d = self.create_duplicate();
-        self.node_mut(c).scene.append_translation(&t);
-        self.node_mut(d).scene.append_translation(&t);
-        self.connect(self.get_port(Port::new(a, 1)), Port::new(d, 0));
-        self.connect(self.get_port(Port::new(a, 2)), Port::new(b, 0));
-        self.connect(self.get_port(Port::new(b, 1)), Port::new(a, 0));
-        self.connect(self.get_port(Port::new(b, 2)), Port::new(c, 0));
-        self.connect(Port::new(b, 1), Port::new(a, 2));
-        self.connect(Port::new(b, 2), Port::new(c, 2));
-        self.connect(Port::new(d, 1), Port::new(a, 1));
-        self.connect(Port::new(d, 2), Port::new(c, 1));
-    }
-
-    fn eval_ce(&mut self, a: usize, b: usize, t: Translation3<f32>) {
-        let c = self.create_erase();
-        self.node_mut(c).scene.append_translation(&t);
-        self.connect(Port::new(b, 0), self.get_port(Port::new(a, 1)));
-        self.connect(Port::new(c, 0), self.get_port(Port::new(a, 2)));
-        self.delete(a);
-    }
-
-    fn eval_dd(&mut self, a: usize, b: usize) {
-        self.connect(
-            self.get_port(Port::new(a, 1)),
-            self.get_port(Port::new(b, 1)),
+    /// Anchors the free ports evenly on a ring of `radius` around the origin.
+    ///
+    /// The ring faces the initial camera.
+    fn anchor_ring(&mut self, radius: f32) {
+        let mut free: Vec<_> = self.net.free().collect();
+        free.sort_unstable();
+        let count = free.len() as f32;
+        for (i, f) in free.into_iter().enumerate() {
+            let angle = std::f32::consts::TAU * i as f32 / count;
+            let (sin, cos) = angle.sin_cos();
+            let position = if self.layout.planar() {
+                Vector3::new(radius * sin, radius * cos, 0.)
+            } else {
+                Vector3::new(0., radius * cos, radius * sin)
+            };
+            self.anchor(f, position);
+        }
+    }
+
+    /// Moves the agents connected to anchored free ports toward their anchor.
+    fn pull_anchors(&mut self) {
+        let rate = (5. * self.dt).min(1.);
+        for (&f, &anchor) in &self.anchors {
+            let a = match self.net.get_port(Port::new(f, 0)) {
+                Ok(y) => y.agent,
+                Err(_) => continue,
+            };
+            let n = match self.scene.nodes.get_mut(&a) {
+                Some(n) => n,
+                None => continue,
+            };
+            // The agent stays at the length of an interface wire from its anchor.
+            let mut direction = anchor - n.position;
+            let distance = direction.normalize_mut();
+            let slack = distance - (n.radius + 3.);
+            if slack > 0. {
+                let offset = rate * slack * direction;
+                n.position += offset;
+                n.velocity -= n.velocity.dot(&direction).min(0.) * direction;
+            }
+        }
+    }
+
+    /// Reduces an active pair of each net without waiting for its agents to collide.
+    fn reduce_one(&mut self) {
+        let mut done = vec![false; self.parts.len()];
+        for (a, b) in self.net.active_pairs() {
+            let part = self.scene.node(a).part;
+            if !std::mem::replace(&mut done[part], true) {
+                let scene = &self.scene;
+                let t = (scene.node(a).position + scene.node(b).position) / 2.;
+                self.reduce_pair(a, b, t);
+            }
+        }
+    }
+
+    /// Reduces an active pair, creating agents at `t` in the net of its agents.
+    fn reduce_pair(&mut self, a: usize, b: usize, t: Vector3<f32>) {
+        let scene = &mut self.scene;
+        scene.spawn = t;
+        scene.part = scene.node(a).part;
+        scene.created.clear();
+        let (x, y) = (self.net.symbol(a).unwrap(), self.net.symbol(b).unwrap());
+        *self.rules.entry((x.min(y), x.max(y))).or_default() += 1;
+        let group = scene
+            .node(a)
+            .group
+            .filter(|&g| scene.node(b).group == Some(g));
+        // The neighbors of the pair outlive it, in the components it may split.
+        let mut touched = Vec::new();
+        if self.components.is_some() {
+            for x in [a, b] {
+                let ports = self.net.node(x).unwrap().1;
+                touched.extend(
+                    ports
+                        .flatten()
+                        .map(|y| y.agent)
+                        .filter(|&y| y != a && y != b),
+                );
+            }
+        }
+        self.net.reduce_pair_observed(a, b, scene).unwrap();
+        if let Some(components) = &mut self.components {
+            components.touched.extend(touched);
+            components.touched.extend(&scene.created);
+        }
+        for c in &scene.created {
+            scene.nodes.get_mut(c).unwrap().group = group;
+        }
+        if let Some(explain) = self.explain {
+            let explanation = self.net.rules().explain((x, y), a, b, &scene.created);
+            println!("{}", explanation);
+            self.explanation = Some(explanation);
+            self.paused |= explain == Explain::Pause;
+        }
+        self.parts[scene.part].reductions += 1;
+        self.reductions += 1;
+        self.energy = f32::INFINITY;
+        self.heat();
+        self.place(t);
+    }
+
+    /// Heats the agents created by the last rewrite and the agents they connect to.
+    fn heat(&mut self) {
+        let scene = &mut self.scene;
+        for &c in &scene.created {
+            let symbol = self.net.symbol(c).unwrap();
+            for p in 0..=self.net.rules().arity(symbol) {
+                if let Ok(y) = self.net.get_port(Port::new(c, p)) {
+                    if let Some(n) = scene.nodes.get_mut(&y.agent) {
+                        n.heat = 1.;
+                    }
+                }
+            }
+            scene.nodes.get_mut(&c).unwrap().heat = 1.;
+        }
+    }
+
+    /// Moves the agents created by the last rewrite, at `t`, according to the spawn policy.
+    fn place(&mut self, t: Vector3<f32>) {
+        let scene = &mut self.scene;
+        let created = std::mem::take(&mut scene.created);
+        for &c in &created {
+            let position = match self.spawn {
+                SpawnPolicy::Midpoint => continue,
+                SpawnPolicy::Jittered => {
+                    let mut offset = Vector3::from_fn(|_, _| self.rng.gen::<f32>() - 0.5);
+                    if self.layout.planar() {
+                        offset.z = 0.;
+                    }
+                    t + offset
+                }
+                SpawnPolicy::AlongWire => {
+                    let symbol = self.net.symbol(c).unwrap();
+                    let mut sum = Vector3::zeros();
+                    let mut count = 0;
+                    for p in 0..=self.net.rules().arity(symbol) {
+                        let y = match self.net.get_port(Port::new(c, p)) {
+                            Ok(y) if !created.contains(&y.agent) => y,
+                            _ => continue,
+                        };
+                        if let Some(m) = scene.nodes.get(&y.agent) {
+                            sum += m.position;
+                            count += 1;
+                        }
+                    }
+                    if count == 0 {
+                        continue;
+                    }
+                    t + (sum / count as f32 - t) / 3.
+                }
+            };
+            scene.nodes.get_mut(&c).unwrap().position = position;
+        }
+    }
+
+    /// Saves the simulation to the file if `save`, or restores it from the file otherwise.
+    #[cfg(feature = "serde")]
+    fn snapshot(&mut self, save: bool) {
+        let result = if save {
+            self.save(&self.file)
+        } else {
+            save::read(&self.file).map(|state| self.restore(state))
+        };
+        match result {
+            Ok(()) if save => println!("saved to {}", self.file),
+            Ok(()) => println!("loaded from {}", self.file),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+
+    #[cfg(not(feature = "serde"))]
+    fn snapshot(&mut self, _save: bool) {
+        println!("cannot use {} without the serde feature", self.file);
+    }
+
+    /// Reduces the active pairs allowed by the layout, then updates the layout.
+    fn step(&mut self) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("step").entered();
+        let scene = &self.scene;
+        let mut ready = Vec::new();
+        let mut waiting = HashMap::new();
+        let settled = self.energy < self.settle;
+        for (a, b) in self.net.active_pairs() {
+            let react = match self.react(a, b) {
+                React::Proximity => self.layout.ready(scene.node(a), scene.node(b)),
+                React::Timer(n) => {
+                    let age = self.waiting.get(&(a, b)).map_or(0, |&age| age + 1);
+                    waiting.insert((a, b), age);
+                    age >= n
+                }
+                React::Instant => true,
+                React::Settled => settled && ready.is_empty(),
+            };
+            if react {
+                ready.push((a, b));
+            }
+        }
+        self.waiting = waiting;
+        for (a, b) in ready {
+            if self.paused {
+                break;
+            }
+            if let Some(max) = self.max_nodes.filter(|&max| self.net.len() > max) {
+                self.paused = true;
+                println!("paused with {} agents, more than {}", self.net.len(), max);
+                break;
+            }
+            let scene = &self.scene;
+            let t = (scene.node(a).position + scene.node(b).position) / 2.;
+            self.reduce_pair(a, b, t);
+        }
+        let scene = &mut self.scene;
+        // A settled layout stays still until a rewrite changes the net.
+        if self.energy >= self.settle {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("layout", nodes = scene.nodes.len()).entered();
+            let (nodes, rng) = (&mut scene.nodes, &mut self.rng);
+            self.energy = self.layout.update(&self.net, nodes, self.dt, rng);
+        }
+        // Pull waiting pairs toward their middle such that they meet when reduced.
+        let pulled: Vec<_> = (self.waiting.iter())
+            .filter(|(&(a, b), _)| self.net.symbol(a).is_some() && self.net.symbol(b).is_some())
+            .filter_map(|(&(a, b), &age)| match self.react(a, b) {
+                React::Timer(n) if age < n => Some((a, b, n - age)),
+                _ => None,
+            })
+            .collect();
+        let scene = &mut self.scene;
+        for (a, b, left) in pulled {
+            let middle = (scene.node(a).position + scene.node(b).position) / 2.;
+            for x in [a, b] {
+                let node = scene.nodes.get_mut(&x).unwrap();
+                let offset = (middle - node.position) / left as f32;
+                node.position += offset;
+            }
+        }
+        self.pull_anchors();
+    }
+
+    /// Returns when the active pair `a`-`b` is reduced, after the delay of its rule if any.
+    fn react(&self, a: usize, b: usize) -> React {
+        let (x, y) = (self.net.symbol(a).unwrap(), self.net.symbol(b).unwrap());
+        match self.delays.get(&(x.min(y), x.max(y))) {
+            Some(&n) => React::Timer(n),
+            None => self.react,
+        }
+    }
+
+    /// Returns the last rendered frame in binary PPM format.
+    fn frame(&self) -> Vec<u8> {
+        let size = self.scene.window.size();
+        let (width, height) = (size.x as usize, size.y as usize);
+        let mut pixels = Vec::new();
+        self.scene.window.snap(&mut pixels);
+        let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        // OpenGL rows go from bottom to top.
+        for row in pixels.chunks(3 * width).rev() {
+            ppm.extend_from_slice(row);
+        }
+        ppm
+    }
+
+    /// Saves an image of the net when the number of rewrites reaches a multiple of
+    /// `snapshot_every`.
+    ///
+    /// The image is a PNG of the window, or an SVG of the net in a plane, named after the number
+    /// of steps like the DOT dumps of `-d`.
+    fn milestone(&mut self, steps: usize) {
+        let every = match self.snapshot_every {
+            Some(every) if self.reductions >= self.next_snapshot => every,
+            _ => return,
+        };
+        self.next_snapshot = (self.reductions / every + 1) * every;
+        let result = if self.layout.planar() {
+            let path = format!("lafont-{}.svg", steps);
+            std::fs::write(&path, self.svg(steps)).map_err(|e| format!("{}: {}", path, e))
+        } else {
+            let path = format!("lafont-{}.png", steps);
+            let image = self.scene.window.snap_image();
+            image.save(&path).map_err(|e| format!("{}: {}", path, e))
+        };
+        if let Err(e) = result {
+            println!("cannot save image: {}", e);
+        }
+    }
+
+    /// Returns the net in SVG as laid out in the plane, labeled with the number of steps.
+    ///
+    /// Agents are circles of their color and size, and wires are lines whose halves are colored
+    /// after the port index of their end, like in the window.
+    fn svg(&self, steps: usize) -> String {
+        const MARGIN: f32 = 10.;
+        let hex = |[r, g, b]: Color| {
+            let byte = |x: f32| (x.clamp(0., 1.) * 255.).round() as u8;
+            format!("#{:02x}{:02x}{:02x}", byte(r), byte(g), byte(b))
+        };
+        let nodes = &self.scene.nodes;
+        let (mut min, mut max) = (
+            Vector2::repeat(f32::INFINITY),
+            Vector2::repeat(-f32::INFINITY),
         );
-        self.connect(
-            self.get_port(Port::new(a, 2)),
-            self.get_port(Port::new(b, 2)),
+        for n in nodes.values() {
+            let (p, r) = (n.position.xy(), Vector2::repeat(n.radius));
+            min = min.inf(&(p - r));
+            max = max.sup(&(p + r));
+        }
+        if nodes.is_empty() {
+            (min, max) = (Vector2::zeros(), Vector2::zeros());
+        }
+        min -= Vector2::repeat(MARGIN);
+        max += Vector2::repeat(MARGIN + 20.);
+        // The y axis of SVG goes down.
+        let point = |x: Vector3<f32>| (x.x - min.x, max.y - x.y);
+        let (width, height) = (max.x - min.x, max.y - min.y);
+        let theme = &self.scene.theme;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\">\n",
+            width, height
         );
-        self.delete(a);
-        self.delete(b);
+        svg.push_str(&format!(
+            "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+            hex(theme.background)
+        ));
+        let mut agents: Vec<_> = nodes.keys().copied().collect();
+        agents.sort_unstable();
+        for &a in &agents {
+            let symbol = self.net.symbol(a).unwrap();
+            for p in 0..=self.net.rules().arity(symbol) {
+                let y = match self.net.get_port(Port::new(a, p)) {
+                    Ok(y) if Port::new(a, p) < y && nodes.contains_key(&y.agent) => y,
+                    _ => continue,
+                };
+                let (start, end) = (nodes[&a].position, nodes[&y.agent].position);
+                let middle = point((start + end) / 2.);
+                for (tip, port) in [(start, p), (end, y.port)] {
+                    let tip = point(tip);
+                    svg.push_str(&format!(
+                        "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\"/>\n",
+                        tip.0,
+                        tip.1,
+                        middle.0,
+                        middle.1,
+                        hex(theme.wire(port))
+                    ));
+                }
+            }
+        }
+        for &a in &agents {
+            let n = &nodes[&a];
+            let (x, y) = point(n.position);
+            svg.push_str(&format!(
+                "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" fill=\"{}\"/>\n",
+                x,
+                y,
+                n.radius,
+                hex(n.color)
+            ));
+        }
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"16\" fill=\"{}\">\
+             step {}</text>\n</svg>\n",
+            MARGIN,
+            MARGIN + 16.,
+            hex(theme.text),
+            steps
+        ));
+        svg
     }
 
-    fn eval_de(&mut self, a: usize, b: usize, t: Translation3<f32>) {
-        let c = self.create_erase();
-        self.node_mut(c).scene.append_translation(&t);
-        self.connect(Port::new(b, 0), self.get_port(Port::new(a, 1)));
-        self.connect(Port::new(c, 0), self.get_port(Port::new(a, 2)));
-        self.delete(a);
+    /// Draws the statistics of the simulation in the top left corner.
+    fn hud(&mut self, font: &Rc<Font>, steps: usize, rate: f32) {
+        let mut counts = BTreeMap::new();
+        for &a in self.scene.nodes.keys() {
+            *counts.entry(self.net.symbol(a).unwrap()).or_insert(0) += 1;
+        }
+        let mut lines = Vec::new();
+        for (symbol, count) in counts {
+            lines.push(format!("{}: {}", self.net.rules().name(symbol), count));
+        }
+        let pairs = self.net.active_pairs();
+        lines.push(format!("active pairs: {}", pairs.len()));
+        if let Some(components) = &self.components {
+            lines.push(format!("components: {}", components.len()));
+        }
+        lines.push(format!("reductions: {} ({:.1}/s)", self.reductions, rate));
+        if self.parts.len() > 1 {
+            let mut stats = vec![(0, 0); self.parts.len()];
+            for n in self.scene.nodes.values() {
+                stats[n.part].0 += 1;
+            }
+            for (a, _) in pairs {
+                stats[self.scene.node(a).part].1 += 1;
+            }
+            for (part, (agents, pairs)) in self.parts.iter().zip(stats) {
+                lines.push(format!(
+                    "{}: {} agents, {} active pairs, {} reductions",
+                    part.name, agents, pairs, part.reductions
+                ));
+            }
+        }
+        lines.push(format!("steps: {}", steps));
+        match self.max_nodes {
+            Some(max) if self.paused && self.net.len() > max => {
+                lines.push(format!("paused: more than {} agents", max));
+            }
+            _ if self.paused => lines.push("paused".to_string()),
+            _ => (),
+        }
+        if self.energy < self.settle {
+            lines.push("settled".to_string());
+        }
+        if let Some(explanation) = &self.explanation {
+            lines.push(explanation.clone());
+        }
+        if let Some(warning) = &self.warning {
+            lines.push(format!("warning: {}", warning));
+        }
+        let color = Point3::from(self.scene.theme.text);
+        for (i, line) in lines.iter().enumerate() {
+            let position = Point2::new(10., 10. + 40. * i as f32);
+            self.scene
+                .window
+                .draw_text(line, &position, 40., font, &color);
+        }
     }
 
-    fn eval_ee(&mut self, a: usize, b: usize) {
-        self.delete(a);
-        self.delete(b);
+    /// Draws the id and the first letter of the symbol of each agent next to its sphere.
+    ///
+    /// Agents behind the camera are skipped.
+    fn draw_labels(&mut self, font: &Rc<Font>) {
+        let eye = self.view.camera.eye().coords;
+        let direction = (self.view.camera.at().coords - eye).normalize();
+        let size = self.scene.window.size();
+        let size = Vector2::new(size.x as f32, size.y as f32);
+        let color = Point3::from(self.scene.theme.text);
+        for (&a, n) in self.scene.nodes.iter() {
+            if n.group.is_some() || (n.position - eye).dot(&direction) <= 0. {
+                continue;
+            }
+            let name = self.net.rules().name(self.net.symbol(a).unwrap());
+            let label = format!("{} {}", a, name.chars().next().unwrap_or('?'));
+            let x = self.view.camera.project(&Point3::from(n.position), &size);
+            // Text coordinates are twice the window coordinates and start at the top.
+            let position = Point2::new(2. * x.x + 20., 2. * (size.y - x.y));
+            self.scene
+                .window
+                .draw_text(&label, &position, 30., font, &color);
+        }
     }
 
-    fn step(&mut self) {
-        let mut collisions = Vec::new();
-        for (&a, n) in self.nodes.iter() {
-            let Port { agent: b, port: p } = n.get_port(0);
-            if p == 0 && b > a && (n.position() - self.node(b).position()).norm() < 0.1 {
-                collisions.push((a, b));
-            }
-        }
-        for (a, b) in collisions {
-            let t = Translation3::from((self.node(a).position() + self.node(a).position()) / 2.);
-            match (self.node(a).agent, self.node(b).agent) {
-                (Agent::Construct(_), Agent::Construct(_)) => self.eval_cc(a, b),
-                (Agent::Construct(_), Agent::Duplicate(_)) => self.eval_cd(a, b, t),
-                (Agent::Construct(_), Agent::Erase(_)) => self.eval_ce(a, b, t),
-                (Agent::Duplicate(_), Agent::Construct(_)) => self.eval_cd(b, a, t),
-                (Agent::Duplicate(_), Agent::Duplicate(_)) => self.eval_dd(a, b),
-                (Agent::Duplicate(_), Agent::Erase(_)) => self.eval_de(a, b, t),
-                (Agent::Erase(_), Agent::Construct(_)) => self.eval_ce(b, a, t),
-                (Agent::Erase(_), Agent::Duplicate(_)) => self.eval_de(b, a, t),
-                (Agent::Erase(_), Agent::Erase(_)) => self.eval_ee(a, b),
-            }
-        }
-        let mut accelerations = HashMap::new();
-        for (&a, n) in self.nodes.iter() {
-            let mut acceleration = -0.1 * n.velocity;
-            for (&b, m) in self.nodes.iter() {
-                if a == b {
+    /// Draws each free port as a point next to the agent it is connected to, labeled with its
+    /// name or `f` followed by its id.
+    ///
+    /// Points are placed away from the center of the net, so that the interface stands out.
+    fn draw_interface(&mut self, font: &Rc<Font>) {
+        let nodes = &self.scene.nodes;
+        if nodes.is_empty() {
+            return;
+        }
+        let sum = nodes.values().fold(Vector3::zeros(), |s, n| s + n.position);
+        let center = sum / nodes.len() as f32;
+        let size = self.scene.window.size();
+        let size = Vector2::new(size.x as f32, size.y as f32);
+        let color = Point3::from(self.scene.theme.interface);
+        let mut free: Vec<_> = self.net.free().collect();
+        free.sort_unstable();
+        for f in free {
+            let n = match self.net.get_port(Port::new(f, 0)) {
+                Ok(y) => match nodes.get(&y.agent) {
+                    Some(n) => n,
+                    None => continue,
+                },
+                Err(_) => continue,
+            };
+            let tip = match self.anchors.get(&f) {
+                Some(&anchor) => Point3::from(anchor),
+                None => {
+                    let outward = (n.position - center).try_normalize(1e-3);
+                    let outward = outward.unwrap_or_else(Vector3::y);
+                    Point3::from(n.position + outward * (n.radius + 3.))
+                }
+            };
+            let label = match self.net.name(f) {
+                Some(name) => name.to_string(),
+                None => format!("f{}", f),
+            };
+            let window = &mut self.scene.window;
+            window.draw_line(&Point3::from(n.position), &tip, &color);
+            window.draw_point(&tip, &color);
+            let x = self.view.camera.project(&tip, &size);
+            // Text coordinates are twice the window coordinates and start at the top.
+            let position = Point2::new(2. * x.x + 10., 2. * (size.y - x.y));
+            window.draw_text(&label, &position, 30., font, &color);
+        }
+    }
+
+    /// Draws far agents as points instead of spheres when the net has more than `lod` agents.
+    ///
+    /// Agents are far when their sphere would be smaller than a point on screen. Selected and
+    /// highlighted agents are never far. Large nets are only readable from afar, where spheres
+    /// cost the most for the least detail.
+    fn level_of_detail(&mut self) {
+        let mut far = HashSet::new();
+        if self.net.len() > self.lod {
+            let size = self.scene.window.size();
+            let size = Vector2::new(size.x as f32, size.y as f32);
+            let direction = self.view.camera.at() - self.view.camera.eye();
+            let side = direction.cross(&Vector3::y()).try_normalize(1e-3);
+            let side = side.unwrap_or_else(Vector3::x);
+            for (&a, n) in &self.scene.nodes {
+                if self.highlighted.contains(&a) || n.group.is_some() {
                     continue;
                 }
-                let mut force = n.position() - m.position();
-                let distance = force.normalize_mut();
-                if (n.get_port(0) == Port { agent: b, port: 0 }) {
-                    if distance < 0.1 {
-                        force = Vector3::zeros();
-                    } else {
-                        force *= -0.1;
-                        match (n.agent, m.agent) {
-                            (Agent::Construct(_), Agent::Duplicate(_))
-                            | (Agent::Duplicate(_), Agent::Construct(_)) => force *= 0.67,
-                            (Agent::Erase(_), Agent::Erase(_)) => force *= 1.5,
-                            (_, _) => (),
-                        }
-                    }
-                } else if distance < 1. {
-                    let rand = || 0.5 - random::<f32>();
-                    force = Vector3::new(rand(), rand(), rand()).normalize();
-                    force *= 10.;
-                } else if distance < 10. {
-                    force /= distance * distance;
-                } else {
-                    force = Vector3::zeros();
+                let center = Point3::from(n.position);
+                let x = self.view.camera.project(&center, &size);
+                let y = self.view.camera.project(&(center + n.radius * side), &size);
+                if (y - x).norm() < POINT_RADIUS {
+                    far.insert(a);
+                }
+            }
+        }
+        for a in self.far.difference(&far) {
+            if let Some(n) = self.scene.nodes.get_mut(a) {
+                n.visible = true;
+            }
+        }
+        for &a in &far {
+            let n = self.scene.nodes.get_mut(&a).unwrap();
+            n.visible = false;
+            let symbol = self.net.symbol(a).unwrap();
+            let mut color = self.scene.theme.style(symbol).color;
+            if let Some(components) = &self.components {
+                color = mix(color, components.color(a), TINT);
+            }
+            if self.heatmap {
+                color = mix(color, self.scene.theme.hot, n.heat);
+            }
+            let color = Point3::from(color);
+            let window = &mut self.scene.window;
+            window.draw_point(&Point3::from(n.position), &color);
+        }
+        self.far = far;
+    }
+
+    /// Queues the spheres of the visible and fading agents for the next frame.
+    fn draw_spheres(&mut self) {
+        let scene = &self.scene;
+        let spheres = &mut self.view.spheres;
+        for (a, n) in scene.nodes.iter() {
+            if !n.visible || n.group.is_some() {
+                continue;
+            }
+            let mut color = n.color;
+            if let Some(components) = &self.components {
+                if !self.highlighted.contains(a) {
+                    color = mix(color, components.color(*a), TINT);
                 }
-                acceleration += force;
             }
-            assert!(accelerations.insert(a, acceleration).is_none());
+            if self.heatmap && !self.highlighted.contains(a) {
+                color = mix(color, scene.theme.hot, n.heat);
+            }
+            spheres.draw(n.symbol, n.position, n.scale, color);
+        }
+        for (n, _) in &scene.fading {
+            spheres.draw(n.symbol, n.position, n.scale, n.color);
+        }
+    }
+
+    /// Draws each super-node as a sphere labeled with its number of agents.
+    fn draw_groups(&mut self, font: &Rc<Font>) {
+        let size = self.scene.window.size();
+        let size = Vector2::new(size.x as f32, size.y as f32);
+        let color = self.scene.theme.group;
+        for (g, (center, members)) in self.scene.groups() {
+            let radius = group_radius(members.len());
+            // The symbol only batches the instances.
+            let symbol = self.scene.node(members[0]).symbol;
+            self.view.spheres.draw(symbol, center, radius, color);
+            let label = format!("group {}: {} agents", g, members.len());
+            let x = self.view.camera.project(&Point3::from(center), &size);
+            // Text coordinates are twice the window coordinates and start at the top.
+            let position = Point2::new(2. * x.x + 20., 2. * (size.y - x.y));
+            let text = Point3::from(self.scene.theme.text);
+            self.scene
+                .window
+                .draw_text(&label, &position, 30., font, &text);
+        }
+    }
+
+    /// Returns the color of the wires of active pairs, which pulses once per second between the
+    /// principal and active colors.
+    fn active_color(&self) -> Color {
+        let t = self.started.elapsed().as_secs_f32();
+        let pulse = 0.5 - 0.5 * (std::f32::consts::TAU * t).cos();
+        mix(self.scene.theme.principal, self.scene.theme.active, pulse)
+    }
+
+    /// Returns whether there is a rule for the agents `a` and `b`, such that they form an active
+    /// pair when connected by their principal ports.
+    fn has_rule(&self, a: usize, b: usize) -> bool {
+        match (self.net.symbol(a), self.net.symbol(b)) {
+            (Some(x), Some(y)) => self.net.rules().rule(x, y).is_some(),
+            _ => false,
         }
-        for (&a, n) in self.nodes.iter_mut() {
-            n.velocity += accelerations.get(&a).unwrap();
-            n.scene
-                .append_translation(&Translation3::from(0.1 * n.velocity));
+    }
+
+    /// Draws all the wires, each half colored after the port index of its end.
+    ///
+    /// Wires of active pairs pulse instead. Wires behind the camera or between far agents are
+    /// skipped.
+    fn draw_wires(&mut self) {
+        let active = Point3::from(self.active_color());
+        let eye = self.view.camera.eye().coords;
+        let direction = (self.view.camera.at().coords - eye).normalize();
+        let visible = |x: Vector3<f32>| (x - eye).dot(&direction) > 0.;
+        let groups = self.scene.groups();
+        for (&a, n) in self.scene.nodes.iter() {
+            let symbol = self.net.symbol(a).unwrap();
+            for p in 0..=self.net.rules().arity(symbol) {
+                let y = match self.net.get_port(Port::new(a, p)) {
+                    Ok(y) if Port::new(a, p) < y => y,
+                    _ => continue,
+                };
+                let m = match self.scene.nodes.get(&y.agent) {
+                    Some(m) => m,
+                    None => continue,
+                };
+                if self.far.contains(&a) && self.far.contains(&y.agent) {
+                    continue;
+                }
+                // Wires inside a super-node are hidden.
+                if n.group.is_some() && n.group == m.group {
+                    continue;
+                }
+                let scene = &self.scene;
+                let (start, end) = (scene.tip(a, &groups), scene.tip(y.agent, &groups));
+                if !visible(start) && !visible(end) {
+                    continue;
+                }
+                let middle = Point3::from((start + end) / 2.);
+                for (tip, port) in [(start, p), (end, y.port)] {
+                    let mut color = Point3::from(self.scene.theme.wire(port));
+                    if p == 0 && y.port == 0 && self.has_rule(a, y.agent) {
+                        color = active;
+                    }
+                    let tip = Point3::from(tip);
+                    self.scene.window.draw_line(&tip, &middle, &color);
+                }
+            }
         }
     }
 
-    fn execute(&mut self, n: i32, v: bool) {
+    /// Draws a frame and reduces until the window is closed.
+    ///
+    /// When `dot` is set, the net is dumped every so many steps. When `record` is set, each frame
+    /// is saved to that directory. Both stop at the first error.
+    fn execute(
+        &mut self,
+        n: i32,
+        v: bool,
+        w: bool,
+        mut dot: Option<usize>,
+        mut record: Option<&str>,
+    ) {
         assert!(n > 0);
+        self.speed = n;
         let mut next = Instant::now() + Duration::from_secs(10);
         let mut count = 0;
-        while self.window.render_with_camera(&mut self.camera) {
+        let mut steps = 0;
+        let font = Font::default();
+        let mut rate = 0.;
+        let mut rate_start = (Instant::now(), 0);
+        let mut frame = 0;
+        let mut last_frame = Instant::now();
+        if let Some(dir) = record {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                println!("error: {}: {}", dir, e);
+                record = None;
+            }
+        }
+        while self.scene.window.render_with_state(&mut self.view) {
+            if let Some(dir) = record {
+                let path = format!("{}/frame-{:06}.ppm", dir, frame);
+                if let Err(e) = std::fs::write(&path, self.frame()) {
+                    println!("error: {}: {}", path, e);
+                    record = None;
+                }
+                frame += 1;
+            }
+            self.milestone(steps);
+            let mut keys = Vec::new();
+            let mut clicks = Vec::new();
+            let cursor = self.scene.window.cursor_pos();
+            for event in self.scene.window.events().iter() {
+                match event.value {
+                    WindowEvent::Key(key, Action::Press, modifiers) => keys.push((key, modifiers)),
+                    WindowEvent::MouseButton(MouseButton::Button1, Action::Press, _) => {
+                        self.press = cursor;
+                    }
+                    // Releasing where the button was pressed is a click, otherwise it rotates.
+                    WindowEvent::MouseButton(MouseButton::Button1, Action::Release, _) => {
+                        clicks.extend(cursor.filter(|&x| self.press == Some(x)));
+                    }
+                    _ => (),
+                }
+            }
+            for (key, modifiers) in keys {
+                self.key(key, modifiers);
+            }
+            for click in clicks {
+                self.pick(click);
+            }
             if Instant::now() < next {
                 count += 1;
             } else {
@@ -284,21 +1646,82 @@ impl Net {
                 next += Duration::from_secs(10);
                 count = 0;
             }
-            for _ in 0..n {
+            let speed = if self.paused { 0 } else { self.speed };
+            for _ in 0..speed {
+                if dot.is_some_and(|dot| steps % dot == 0) {
+                    let path = format!("lafont-{}.dot", steps);
+                    if let Err(e) = std::fs::write(&path, self.net.to_dot()) {
+                        println!("error: {}: {}", path, e);
+                        dot = None;
+                    }
+                }
                 self.step();
+                steps += 1;
+            }
+            let fps = 1.
+                / std::mem::replace(&mut last_frame, Instant::now())
+                    .elapsed()
+                    .as_secs_f32();
+            if let Some(stats) = &mut self.stats {
+                if let Err(e) = stats.row(&self.net, steps, &self.rules, Some(fps)) {
+                    println!("error: {}", e);
+                    self.stats = None;
+                }
+            }
+            let elapsed = rate_start.0.elapsed();
+            if elapsed >= Duration::from_secs(1) {
+                let count = self.reductions - rate_start.1;
+                rate = count as f32 / elapsed.as_secs_f32();
+                rate_start = (Instant::now(), self.reductions);
+            }
+            self.demo();
+            if self.scene.frames > 0 {
+                self.scene.animate(&self.net);
             }
-            if v {
-                for (&a, n) in self.nodes.iter() {
-                    let Port { agent: b, port: p } = n.get_port(0);
-                    let mut color = Vector3::new(1., 1., 1.);
+            for n in self.scene.nodes.values_mut() {
+                n.heat *= COOLING;
+            }
+            if let Some(components) = &mut self.components {
+                components.update(&self.net);
+            }
+            self.highlight();
+            self.level_of_detail();
+            self.draw_spheres();
+            self.hud(&font, steps, rate);
+            if self.labels {
+                self.draw_labels(&font);
+            }
+            self.draw_interface(&font);
+            self.draw_groups(&font);
+            if w {
+                self.draw_wires();
+            } else if v {
+                let active = Vector3::from(self.active_color());
+                let groups = self.scene.groups();
+                for (&a, n) in self.scene.nodes.iter() {
+                    let Ok(Port { agent: b, port: p }) = self.net.get_port(Port::new(a, 0)) else {
+                        continue;
+                    };
+                    let m = match self.scene.nodes.get(&b) {
+                        Some(m) if !self.far.contains(&a) || !self.far.contains(&b) => m,
+                        _ => continue,
+                    };
+                    if n.group.is_some() && n.group == m.group {
+                        continue;
+                    }
+                    let theme = &self.scene.theme;
+                    let mut color = Vector3::from(theme.principal);
                     if p != 0 {
-                        color *= 0.3;
+                        color = 0.3 * color + 0.7 * Vector3::from(theme.background);
                     } else if b > a {
                         continue;
+                    } else if self.has_rule(a, b) {
+                        color = active;
                     }
-                    self.window.draw_line(
-                        &Point3::from(n.position()),
-                        &Point3::from(self.node(b).position()),
+                    let (start, end) = (self.scene.tip(a, &groups), self.scene.tip(b, &groups));
+                    self.scene.window.draw_line(
+                        &Point3::from(start),
+                        &Point3::from(end),
                         &Point3::from(color),
                     );
                 }
@@ -307,30 +1730,402 @@ impl Net {
     }
 }
 
+/// Reduces `net`, prints statistics to `log`, and returns the reduced net with its statistics.
+///
+/// With [`Mode::Sweeps`], all the active pairs are reduced in passes, and the number of active
+/// pairs of the passes is printed too. With [`Mode::Explain`], each rewrite is explained. The
+/// `checkpoint` is called every so often with the statistics so far, and returns how many steps
+/// to reduce at most before calling it again.
+fn headless(
+    mut net: Net,
+    limit: Option<usize>,
+    max_nodes: Option<usize>,
+    mode: Mode,
+    checkpoint: &mut dyn FnMut(&Net, &ReductionStats) -> usize,
+    log: &mut dyn Write,
+) -> (Net, ReductionStats) {
+    const CHUNK: usize = 100_000;
+    let start = Instant::now();
+    let mut stats = ReductionStats::default();
+    let mut budget = checkpoint(&net, &stats);
+    loop {
+        let chunk = limit.map_or(CHUNK, |limit| CHUNK.min(limit - stats.steps));
+        let chunk = chunk.min(budget);
+        let max = max_nodes.unwrap_or(usize::MAX);
+        let (done, overflow) = match mode {
+            Mode::Plain => match net.reduce_bounded(Strategy::PreferErase, Some(chunk), max) {
+                Ok(done) => (done, None),
+                Err(e) => (e.stats.clone(), Some(e.max_agents)),
+            },
+            Mode::Sweeps => match net.reduce_sweeps_bounded(Some(chunk), max) {
+                Ok(done) => (done, None),
+                Err(e) => (e.stats.clone(), Some(e.max_agents)),
+            },
+            Mode::Explain => {
+                let mut explainer = Explainer::new(net.rules().clone(), &mut *log);
+                let strategy = Strategy::PreferErase;
+                match net.reduce_bounded_observed(strategy, Some(chunk), max, &mut explainer) {
+                    Ok(done) => (done, None),
+                    Err(e) => (e.stats.clone(), Some(e.max_agents)),
+                }
+            }
+        };
+        stats.merge(&done);
+        writeln!(log, "steps={} time={:?}", stats.steps, start.elapsed()).unwrap();
+        budget = checkpoint(&net, &stats);
+        if let Some(max) = overflow {
+            let len = net.len();
+            writeln!(log, "stopped with {} agents, more than {}", len, max).unwrap();
+            break;
+        }
+        // The last pass of sweeps may exceed the limit.
+        if done.steps < chunk || limit.is_some_and(|limit| stats.steps >= limit) {
+            break;
+        }
+    }
+    writeln!(log, "status: {}", net.status()).unwrap();
+    writeln!(log, "peak agents: {}", stats.peak_agents).unwrap();
+    if mode == Mode::Sweeps {
+        profile(&stats, log);
+    }
+    let mut rules: Vec<_> = stats.rules.iter().collect();
+    rules.sort();
+    for (&(x, y), count) in rules {
+        let name = |s| net.rules().name(s);
+        writeln!(log, "{}-{}: {}", name(x), name(y), count).unwrap();
+    }
+    (net, stats)
+}
+
+/// How [`headless`] reduces nets.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Rewrites active pairs one at a time, erasures first.
+    Plain,
+    /// Rewrites all the active pairs in passes.
+    Sweeps,
+    /// Rewrites active pairs one at a time, erasures first, and explains them.
+    Explain,
+}
+
+/// Prints an explanation of each rewrite once its agents are deleted.
+struct Explainer<'a> {
+    rules: RuleSet,
+    log: &'a mut dyn Write,
+    /// Rule and active pair of the current rewrite.
+    rewrite: Option<((Symbol, Symbol), usize, usize)>,
+    /// Agents created by the current rewrite.
+    created: Vec<usize>,
+}
+
+impl<'a> Explainer<'a> {
+    fn new(rules: RuleSet, log: &'a mut dyn Write) -> Explainer<'a> {
+        Explainer {
+            rules,
+            log,
+            rewrite: None,
+            created: Vec::new(),
+        }
+    }
+}
+
+impl NetObserver for Explainer<'_> {
+    fn on_rewrite(&mut self, rule: (Symbol, Symbol), a: usize, b: usize) {
+        self.rewrite = Some((rule, a, b));
+        self.created.clear();
+    }
+
+    fn on_create(&mut self, a: usize, _: Symbol) {
+        self.created.push(a);
+    }
+
+    fn on_delete(&mut self, a: usize) {
+        // Both agents of the active pair are deleted last, in order.
+        if let Some((rule, x, y)) = self.rewrite {
+            if a == y {
+                let explanation = self.rules.explain(rule, x, y, &self.created);
+                writeln!(self.log, "{}", explanation).unwrap();
+                self.rewrite = None;
+            }
+        }
+    }
+}
+
+/// Prints the number of passes of [`Net::reduce_sweeps`], their average and maximum number of
+/// active pairs, and how many passes had 1, 2-3, 4-7, etc. active pairs.
+fn profile(stats: &ReductionStats, log: &mut dyn Write) {
+    let max = stats.sweeps.iter().max().copied().unwrap_or(0);
+    writeln!(log, "sweeps: {}", stats.sweeps.len()).unwrap();
+    writeln!(
+        log,
+        "parallelism: {:.1} average, {} max",
+        stats.parallelism(),
+        max
+    )
+    .unwrap();
+    let mut histogram = Vec::new();
+    for &count in &stats.sweeps {
+        let bucket = count.ilog2() as usize;
+        if histogram.len() <= bucket {
+            histogram.resize(bucket + 1, 0);
+        }
+        histogram[bucket] += 1;
+    }
+    for (bucket, count) in histogram.into_iter().enumerate() {
+        let (low, high) = (1 << bucket, (2 << bucket) - 1);
+        match count {
+            0 => (),
+            _ if low == high => writeln!(log, "  {} pair: {} sweeps", low, count).unwrap(),
+            _ => writeln!(log, "  {}-{} pairs: {} sweeps", low, high, count).unwrap(),
+        }
+    }
+}
+
+/// Reduces nets one after the other without opening a window.
+///
+/// A net read from the standard input is written to the standard output once reduced, and the
+/// statistics then go to the standard error.
+fn reduce(args: ReduceArgs) -> Result<(), String> {
+    let mut out = args.out.clone().map(|path| (path, args.to));
+    let mut nets = Vec::new();
+    if args.inputs.files.iter().any(|path| path == cli::STDIO) {
+        if args.inputs.files.len() + args.inputs.example.len() > 1 {
+            return Err("the standard input takes a single net".to_string());
+        }
+        let (net, format) = cli::read_format(cli::STDIO, args.inputs.from)?;
+        let format = match format {
+            cli::Format::Hvm => cli::Format::Sic,
+            format => format,
+        };
+        out.get_or_insert((cli::STDIO.to_string(), Some(args.to.unwrap_or(format))));
+        nets.push((cli::STDIO.to_string(), net));
+    } else {
+        nets = args.inputs.nets()?;
+    }
+    #[cfg(not(feature = "serde"))]
+    if args.autosave.is_some() || args.resume.is_some() {
+        return Err("--autosave and --resume require the serde feature".to_string());
+    }
+    #[cfg(feature = "serde")]
+    if let Some(path) = &args.resume {
+        nets.push((path.clone(), cli::read(path, Some(cli::Format::Json))?));
+    }
+    if nets.is_empty() {
+        nets.push(("loop".to_string(), lafont::gen::example("loop")?));
+    }
+    for (option, present) in [
+        ("--out", out.is_some()),
+        ("--stats", args.stats.is_some()),
+        (
+            "--autosave",
+            args.autosave.is_some() || args.resume.is_some(),
+        ),
+    ] {
+        if present && nets.len() > 1 {
+            return Err(format!("{} takes a single net", option));
+        }
+    }
+    #[cfg(feature = "serde")]
+    let mut autosave = match args.autosave.as_ref().or(args.resume.as_ref()) {
+        None => None,
+        Some(path) => Some(autosave::Autosave::new(path.clone(), &args.autosave_every)?),
+    };
+    let mut stats = args.stats.as_deref().map(StatsCsv::create).transpose()?;
+    let mut log: Box<dyn Write> = match &out {
+        Some((path, _)) if path == cli::STDIO => Box::new(std::io::stderr()),
+        _ => Box::new(std::io::stdout()),
+    };
+    for (name, net) in nets {
+        writeln!(log, "net: {}", name).unwrap();
+        let mut checkpoint = |net: &Net, reduction: &ReductionStats| {
+            let mut budget = usize::MAX;
+            if let Some(csv) = &mut stats {
+                let steps = reduction.steps;
+                match csv.row(net, steps, &reduction.rules, None) {
+                    Ok(()) => budget = 1000,
+                    // The reduction goes on without statistics, like in the viewer.
+                    Err(e) => {
+                        eprintln!("error: {}", e);
+                        stats = None;
+                    }
+                }
+            }
+            #[cfg(feature = "serde")]
+            if let Some(autosave) = &mut autosave {
+                budget = budget.min(autosave.tick(net, reduction.steps));
+            }
+            budget
+        };
+        let (steps, max_nodes) = (args.steps, args.max_nodes);
+        let mode = match (args.sweeps, args.explain) {
+            (true, _) => Mode::Sweeps,
+            (_, true) => Mode::Explain,
+            _ => Mode::Plain,
+        };
+        let (net, reduction) = headless(net, steps, max_nodes, mode, &mut checkpoint, &mut log);
+        if args.coverage {
+            let rules = net.rules();
+            write!(log, "{}", rules.coverage(&reduction).report(rules)).unwrap();
+        }
+        #[cfg(feature = "serde")]
+        if let Some(autosave) = &mut autosave {
+            autosave.finish(&net);
+        }
+        if let Some((path, format)) = &out {
+            cli::write(path, *format, &net)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reduces a single net in the debugger.
+fn debug(inputs: Inputs) -> Result<(), String> {
+    let mut nets = inputs.nets_or_loop()?;
+    if nets.len() > 1 {
+        return Err("debug takes a single net".to_string());
+    }
+    debug::run(nets.pop().unwrap().1);
+    Ok(())
+}
+
+#[cfg(feature = "server")]
+fn serve(args: ServeArgs) -> Result<(), String> {
+    let (net, _) = workspace(args.inputs.nets_or_loop()?)?;
+    server::run(args.port, net);
+    Ok(())
+}
+
+#[cfg(not(feature = "server"))]
+fn serve(args: ServeArgs) -> Result<(), String> {
+    Err(format!(
+        "serving on port {} requires the server feature",
+        args.port
+    ))
+}
+
+/// Animates the reduction of nets in a window.
+fn view(args: ViewArgs) -> Result<(), String> {
+    #[cfg(not(feature = "serde"))]
+    if args.load.is_some() {
+        return Err("--load requires the serde feature".to_string());
+    }
+    #[cfg(feature = "serde")]
+    let state = args.load.as_deref().map(save::read).transpose()?;
+    let nets = args.inputs.nets_or_loop()?;
+    #[cfg(feature = "serde")]
+    let nets = match &state {
+        Some(state) => vec![(args.save.clone(), state.net().clone())],
+        None => nets,
+    };
+    let mut rng = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let nets = match args.demo {
+        Some(agents) => vec![demo_net(agents, &mut rng)?],
+        None => nets,
+    };
+    if args.snapshot_every == Some(0) {
+        return Err("--snapshot-every takes a positive number".to_string());
+    }
+    let stats = args.stats.as_deref().map(StatsCsv::create).transpose()?;
+    let delays = match &args.delays {
+        Some(path) => read_delays(path, nets[0].1.rules())?,
+        None => HashMap::new(),
+    };
+    let layout: Box<dyn Layout> = match args.layout.as_str() {
+        "force" => {
+            let mut physics = PhysicsConfig::default();
+            if let Some(settings) = &args.physics {
+                physics.parse(settings)?;
+            }
+            Box::new(ForceDirected {
+                planar: args.planar,
+                theta: args.theta,
+                physics,
+            })
+        }
+        "frozen" => Box::new(Frozen),
+        "hierarchical" => Box::new(Hierarchical),
+        name => return Err(format!("unknown layout {:?}", name)),
+    };
+    let react = React::parse(&args.react_mode)?;
+    let theme = Theme::load(&args.theme)?;
+    let names = nets.iter().map(|(name, _)| name.clone()).collect();
+    let workspace = workspace(nets)?;
+    let mut viewer = Viewer::new(names, workspace, args.save, layout, args.dt, react, theme);
+    viewer.scene.frames = args.animate;
+    viewer.labels = args.labels;
+    viewer.heatmap = args.heatmap;
+    if args.components {
+        viewer.toggle_components();
+    }
+    viewer.max_nodes = args.max_nodes;
+    viewer.stats = stats;
+    viewer.explain = args.explain;
+    viewer.snapshot_every = args.snapshot_every;
+    viewer.delays = delays;
+    if let estimate @ Estimate::ProbablyDiverges(_) = viewer.net.estimate(ESTIMATE_BUDGET) {
+        viewer.warning = Some(estimate.to_string());
+    }
+    if let Some(settle) = args.settle {
+        viewer.settle = settle;
+    }
+    viewer.rng = rng;
+    viewer.demo = args.demo.map(|agents| Demo { agents, done: 0 });
+    if let Some(spawn) = &args.spawn {
+        viewer.spawn = SpawnPolicy::parse(spawn)?;
+    }
+    if let Some(lod) = args.lod {
+        viewer.lod = lod;
+    }
+    if let Some(radius) = args.anchor_ring {
+        viewer.anchor_ring(radius);
+    }
+    #[cfg(feature = "serde")]
+    if let Some(state) = state {
+        viewer.restore(state);
+    }
+    viewer.execute(args.n, args.v, args.w, args.d, args.record.as_deref());
+    Ok(())
+}
+
+/// Records spans as folded stacks in the file named by `LAFONT_FLAME`, if set.
+///
+/// The file is complete once the returned guard is dropped, and is turned into a flame graph
+/// by `inferno-flamegraph`.
+#[cfg(feature = "flame")]
+fn flame() -> Result<Option<impl Drop>, String> {
+    use tracing_subscriber::layer::SubscriberExt;
+    let Some(path) = std::env::var_os("LAFONT_FLAME") else {
+        return Ok(None);
+    };
+    let (layer, guard) = tracing_flame::FlameLayer::with_file(&path)
+        .map_err(|e| format!("{}: {}", path.to_string_lossy(), e))?;
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::set_global_default(subscriber).map_err(|e| e.to_string())?;
+    Ok(Some(guard))
+}
+
 fn main() {
-    let args = std::env::args().skip(1).collect::<Vec<_>>();
-    let mut opts = Options::new();
-    opts.optopt("n", "", "speed factor", "N");
-    opts.optflag("v", "", "show principal edges");
-    let matches = match opts.parse(&args) {
-        Ok(m) => m,
-        Err(f) => panic!("{}", f),
+    let cli = Cli::parse();
+    #[cfg(feature = "flame")]
+    let _flame = flame().unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    });
+    let result = match cli.command {
+        None => view(cli.view),
+        Some(Command::View(args)) => view(args),
+        Some(Command::Reduce(args)) => reduce(args),
+        Some(Command::Bench(args)) => cli::bench(&args),
+        Some(Command::Compare(args)) => cli::compare(&args),
+        Some(Command::Convert(args)) => cli::convert(&args),
+        Some(Command::Debug(inputs)) => debug(inputs),
+        Some(Command::Serve(args)) => serve(args),
     };
-    let v = matches.opt_present("v");
-    let n = matches
-        .opt_str("n")
-        .map(|x| x.parse().unwrap())
-        .unwrap_or(1);
-    assert!(matches.free.is_empty());
-
-    let mut net = Net::new();
-    let a = net.create_erase();
-    let b = net.create_erase();
-    let c = net.create_construct();
-    let d = net.create_duplicate();
-    net.connect(Port::new(a, 0), Port::new(c, 1));
-    net.connect(Port::new(c, 2), Port::new(d, 1));
-    net.connect(Port::new(d, 2), Port::new(b, 0));
-    net.connect(Port::new(c, 0), Port::new(d, 0));
-    net.execute(n, v);
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,107 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::strategy;
+use crate::{Net, Port, Ports};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+impl Net {
+    /// Returns the distance in wires from the interface of each agent connected to a free port.
+    ///
+    /// Agents connected to a free port have depth 1.
+    pub fn depths(&self) -> HashMap<usize, usize> {
+        let ports = |a| match self.agents.get(&a) {
+            None => Ports::new(std::slice::from_ref(&self.free[&a])),
+            Some(agent) => agent.ports(),
+        };
+        let mut depths = strategy::depths(self.free.keys().copied(), ports);
+        depths.retain(|a, _| self.agents.contains_key(a));
+        depths
+    }
+
+    /// Returns the connected components of the agents.
+    ///
+    /// Each component is sorted, and components are sorted by their smallest agent. Free ports
+    /// don't connect agents, since only one agent is connected to each.
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let mut agents: Vec<_> = self.agents().collect();
+        agents.sort_unstable();
+        let mut seen = HashSet::new();
+        let mut components = Vec::new();
+        for a in agents {
+            if seen.contains(&a) {
+                continue;
+            }
+            let component = self.component(a);
+            seen.extend(component.iter().copied());
+            components.push(component);
+        }
+        components
+    }
+
+    /// Returns the agents of the connected component of agent `a`, sorted.
+    ///
+    /// This only searches the component, so it is faster than [`Net::components`] to follow a
+    /// part of the net.
+    pub fn component(&self, a: usize) -> Vec<usize> {
+        let mut component: Vec<_> = self.distances(a).into_keys().collect();
+        component.sort_unstable();
+        component
+    }
+
+    /// Returns the longest distance in wires between two connected agents.
+    ///
+    /// This searches the net from each agent, so it takes quadratic time.
+    pub fn diameter(&self) -> usize {
+        let distances = self.agents().map(|a| self.distances(a));
+        distances
+            .filter_map(|d| d.into_values().max())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the average distance between the ids of the agents of an active pair.
+    ///
+    /// Ids are allocated in order, so this measures how far apart in memory the agents of a rewrite
+    /// are. Returns `None` without active pairs.
+    pub fn locality(&self) -> Option<f64> {
+        let pairs = self.active_pairs();
+        if pairs.is_empty() {
+            return None;
+        }
+        let sum: usize = pairs.iter().map(|&(a, b)| b - a).sum();
+        Some(sum as f64 / pairs.len() as f64)
+    }
+
+    /// Returns the distance in wires from agent `a` of each agent connected to it.
+    fn distances(&self, a: usize) -> HashMap<usize, usize> {
+        let mut distances = HashMap::new();
+        distances.insert(a, 0);
+        let mut todo = VecDeque::from([a]);
+        while let Some(a) = todo.pop_front() {
+            let distance = distances[&a] + 1;
+            for Port { agent: b, .. } in self.agents[&a].ports().flatten() {
+                if !self.agents.contains_key(&b) {
+                    continue;
+                }
+                if let Entry::Vacant(e) = distances.entry(b) {
+                    e.insert(distance);
+                    todo.push_back(b);
+                }
+            }
+        }
+        distances
+    }
+}
@@ -0,0 +1,39 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Port, Symbol};
+
+/// Callbacks invoked while a net is reduced.
+///
+/// See [`Net::reduce_observed`](crate::Net::reduce_observed). For each reduced active pair,
+/// [`NetObserver::on_rewrite`] is called first, then [`NetObserver::on_create`] for each created
+/// agent, [`NetObserver::on_connect`] for each new wire, and finally
+/// [`NetObserver::on_delete`] for both agents of the active pair. All callbacks do nothing by
+/// default.
+pub trait NetObserver {
+    /// Agent `a` with `symbol` was created.
+    fn on_create(&mut self, _a: usize, _symbol: Symbol) {}
+
+    /// Agent `a` was deleted.
+    fn on_delete(&mut self, _a: usize) {}
+
+    /// Ports `x` and `y` were connected.
+    fn on_connect(&mut self, _x: Port, _y: Port) {}
+
+    /// The active pair `a`-`b` is about to be rewritten with the rule for `rule`.
+    fn on_rewrite(&mut self, _rule: (Symbol, Symbol), _a: usize, _b: usize) {}
+}
+
+/// Ignores all events.
+impl NetObserver for () {}
@@ -0,0 +1,136 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Barnes–Hut approximation of repulsive forces.
+
+use kiss3d::nalgebra::core::Vector3;
+
+/// Maximum number of bodies in a leaf.
+const LEAF: usize = 8;
+
+/// A cube of space with the bodies it contains.
+pub struct Octree {
+    center: Vector3<f32>,
+    /// Half the side of the cube.
+    half: f32,
+    count: usize,
+    /// Center of mass of the bodies.
+    mass: Vector3<f32>,
+    children: Vec<Octree>,
+    /// The bodies if the cube is a leaf.
+    bodies: Vec<(usize, Vector3<f32>)>,
+}
+
+impl Octree {
+    pub fn new(bodies: Vec<(usize, Vector3<f32>)>) -> Octree {
+        let mut min = Vector3::repeat(f32::INFINITY);
+        let mut max = Vector3::repeat(f32::NEG_INFINITY);
+        for (_, x) in &bodies {
+            min = min.inf(x);
+            max = max.sup(x);
+        }
+        if bodies.is_empty() {
+            min = Vector3::zeros();
+            max = Vector3::zeros();
+        }
+        let half = (max - min).max() / 2. + 1.;
+        Octree::build((min + max) / 2., half, bodies)
+    }
+
+    fn build(center: Vector3<f32>, half: f32, bodies: Vec<(usize, Vector3<f32>)>) -> Octree {
+        let count = bodies.len();
+        let sum = bodies.iter().fold(Vector3::zeros(), |s, (_, x)| s + x);
+        let mass = if count == 0 {
+            center
+        } else {
+            sum / count as f32
+        };
+        // Bodies too close to each other stay in the same leaf.
+        if count <= LEAF || half < 0.5 {
+            return Octree {
+                center,
+                half,
+                count,
+                mass,
+                children: Vec::new(),
+                bodies,
+            };
+        }
+        let mut octants = vec![Vec::new(); 8];
+        for (a, x) in bodies {
+            let d = x - center;
+            let i =
+                usize::from(d.x >= 0.) | usize::from(d.y >= 0.) << 1 | usize::from(d.z >= 0.) << 2;
+            octants[i].push((a, x));
+        }
+        let children = octants.into_iter().enumerate().map(|(i, bodies)| {
+            let sign = |bit: usize| if i & bit == 0 { -1. } else { 1. };
+            let offset = Vector3::new(sign(1), sign(2), sign(4)) * half / 2.;
+            Octree::build(center + offset, half / 2., bodies)
+        });
+        Octree {
+            center,
+            half,
+            count,
+            mass,
+            children: children.collect(),
+            bodies: Vec::new(),
+        }
+    }
+
+    /// Returns the sum of `force` from each body on the body `a` at `x`.
+    ///
+    /// The force from a body is given by `force(direction, distance, exact)` where `direction`
+    /// goes from the body to `x`. A cube seen under an angle smaller than `theta` acts as a
+    /// single body at its center of mass, with `exact` false. The force must be zero beyond
+    /// `cutoff`. Bodies for which `skip` holds are ignored when computed exactly.
+    pub fn force(
+        &self,
+        a: usize,
+        x: Vector3<f32>,
+        theta: f32,
+        cutoff: f32,
+        skip: &dyn Fn(usize) -> bool,
+        force: &mut dyn FnMut(Vector3<f32>, f32, bool) -> Vector3<f32>,
+    ) -> Vector3<f32> {
+        if self.count == 0 {
+            return Vector3::zeros();
+        }
+        let outside = ((x - self.center).abs() - Vector3::repeat(self.half)).sup(&Vector3::zeros());
+        if outside.norm() > cutoff {
+            return Vector3::zeros();
+        }
+        if self.children.is_empty() {
+            let mut total = Vector3::zeros();
+            for &(b, y) in &self.bodies {
+                if a == b || skip(b) {
+                    continue;
+                }
+                let mut direction = x - y;
+                let distance = direction.normalize_mut();
+                total += force(direction, distance, true);
+            }
+            return total;
+        }
+        let mut direction = x - self.mass;
+        let distance = direction.normalize_mut();
+        if 2. * self.half < theta * distance {
+            return self.count as f32 * force(direction, distance, false);
+        }
+        let children = self.children.iter();
+        children.fold(Vector3::zeros(), |total, child| {
+            total + child.force(a, x, theta, cutoff, skip, force)
+        })
+    }
+}
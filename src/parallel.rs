@@ -0,0 +1,65 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::journal::Change;
+use crate::{Net, Port};
+use rayon::prelude::*;
+use std::collections::HashSet;
+
+impl Net {
+    /// Same as [`Net::step_all`] but computes the rewrites on the rayon thread pool.
+    ///
+    /// Active pairs connected to each other through their auxiliary ports conflict. Only one of
+    /// them is rewritten in parallel, the others are rewritten sequentially afterwards.
+    pub fn step_parallel(&mut self) -> usize {
+        span!(DEBUG, "step_parallel");
+        self.begin_step();
+        if self.journaling() {
+            self.log(Change::Drain(self.worklist.save()));
+        }
+        let pairs = self.worklist.drain();
+        let mut taken = HashSet::new();
+        let mut batch = Vec::new();
+        let mut rest = Vec::new();
+        for (a, b) in pairs {
+            if self.partner(a) != Some(b) {
+                continue;
+            }
+            let neighbors = [a, b]
+                .into_iter()
+                .flat_map(|x| self.agents[&x].ports().skip(1));
+            if neighbors
+                .flatten()
+                .any(|Port { agent, .. }| taken.contains(&agent))
+            {
+                rest.push((a, b));
+            } else {
+                taken.insert(a);
+                taken.insert(b);
+                batch.push((a, b));
+            }
+        }
+        let count = batch.len() + rest.len();
+        let net = &*self;
+        let plans: Vec<_> = batch.into_par_iter().map(|(a, b)| net.plan(a, b)).collect();
+        for plan in plans {
+            self.apply(plan, &mut ());
+        }
+        for (a, b) in rest {
+            self.interact(a, b, &mut ());
+        }
+        self.end_step(count > 0);
+        count
+    }
+}
@@ -0,0 +1,270 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::strategy::{self, Worklist};
+use crate::{
+    Agent, CostModel, End, Engine, Net, NetError, NetObserver, Plan, Port, Ports, ReductionStats,
+    RuleSet, Status, Strategy, Symbol, Wire,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// A net whose clones share their agents.
+///
+/// Cloning a [`Net`] copies all its agents, while cloning a `PersistentNet` only copies its
+/// pending active pairs: the agents are shared until a clone modifies them. This makes it cheap
+/// to fork a reduction, for example to explore several strategies from the same point. Reducing
+/// is slower than with a [`Net`] though, so nets are built as a [`Net`] then converted.
+///
+/// Reductions select the same active pairs as the [`Net`] it was converted from would.
+#[derive(Clone, Debug)]
+pub struct PersistentNet {
+    rules: Arc<RuleSet>,
+    agents: im::HashMap<usize, Agent>,
+    free: im::HashMap<usize, Wire>,
+    /// Names of free ports, which reduction does not change.
+    names: Arc<HashMap<usize, String>>,
+    next: usize,
+    worklist: Worklist,
+    cost: CostModel,
+}
+
+impl From<&Net> for PersistentNet {
+    fn from(net: &Net) -> PersistentNet {
+        PersistentNet {
+            rules: net.rules.clone(),
+            agents: net.agents.iter().map(|(&a, x)| (a, x.clone())).collect(),
+            free: net.free.iter().map(|(&a, &x)| (a, x)).collect(),
+            names: Arc::new(net.names.clone()),
+            next: net.next,
+            worklist: net.worklist.clone(),
+            cost: net.cost.clone(),
+        }
+    }
+}
+
+impl PersistentNet {
+    /// Converts back to a [`Net`], copying all the agents.
+    pub fn to_net(&self) -> Net {
+        Net {
+            rules: self.rules.clone(),
+            agents: self.agents.iter().map(|(&a, x)| (a, x.clone())).collect(),
+            free: self.free.iter().map(|(&a, &x)| (a, x)).collect(),
+            names: HashMap::clone(&self.names),
+            next: self.next,
+            worklist: self.worklist.clone(),
+            journal: None,
+            cost: self.cost.clone(),
+            pool: Vec::new(),
+            tracer: None,
+            engine: Engine::default(),
+        }
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    /// Seeds the random generator of [`Strategy::Random`], see [`Net::seed`].
+    pub fn seed(&mut self, seed: u64) {
+        self.worklist.seed(seed);
+    }
+
+    /// Returns the ids of the agents of the net, in no particular order.
+    pub fn agents(&self) -> impl Iterator<Item = usize> + '_ {
+        self.agents.keys().copied()
+    }
+
+    /// Returns the number of agents of the net.
+    pub fn len(&self) -> usize {
+        self.agents.len()
+    }
+
+    /// Returns whether the net has no agents.
+    pub fn is_empty(&self) -> bool {
+        self.agents.is_empty()
+    }
+
+    /// Returns the symbol of agent `a`, or `None` if `a` is not an agent.
+    pub fn symbol(&self, a: usize) -> Option<Symbol> {
+        self.agents.get(&a).map(|agent| agent.symbol)
+    }
+
+    /// Returns the port connected to `x`.
+    pub fn get_port(&self, x: Port) -> Result<Port, NetError> {
+        let y = match self.agents.get(&x.agent) {
+            Some(agent) => agent
+                .wires
+                .get(x.port)
+                .ok_or(NetError::InvalidPort(x))?
+                .get(),
+            None => match self.free.get(&x.agent) {
+                Some(y) if x.port == 0 => y.get(),
+                Some(_) => return Err(NetError::InvalidPort(x)),
+                None => return Err(NetError::MissingAgent(x.agent)),
+            },
+        };
+        y.ok_or(NetError::Unconnected(x))
+    }
+
+    /// Returns the active pairs of the net, sorted, with the smallest agent first.
+    pub fn active_pairs(&self) -> Vec<(usize, usize)> {
+        let pairs = self.agents.keys();
+        let mut pairs: Vec<_> = pairs.filter_map(|&a| Some((a, self.partner(a)?))).collect();
+        pairs.sort_unstable();
+        pairs
+    }
+
+    /// Returns whether the net is reducible, in normal form, or deadlocked.
+    ///
+    /// This converts the net to a [`Net`] when it has no active pairs.
+    pub fn status(&self) -> Status {
+        match self.active_pairs().is_empty() {
+            false => Status::Reducible,
+            true => self.to_net().status(),
+        }
+    }
+
+    /// Reduces one active pair selected by `strategy`, see [`Net::step_with`].
+    pub fn step_with(&mut self, strategy: Strategy) -> bool {
+        self.reduce_with(strategy, Some(1)).steps > 0
+    }
+
+    /// Reduces active pairs, see [`Net::reduce_with`].
+    pub fn reduce_with(&mut self, strategy: Strategy, limit: Option<usize>) -> ReductionStats {
+        self.reduce_observed(strategy, limit, &mut ())
+    }
+
+    /// Same as [`PersistentNet::reduce_with`] but notifies `observer` of each rewrite.
+    pub fn reduce_observed(
+        &mut self,
+        strategy: Strategy,
+        limit: Option<usize>,
+        observer: &mut dyn NetObserver,
+    ) -> ReductionStats {
+        let mut stats = ReductionStats::new(self.agents.len());
+        while limit.is_none_or(|limit| stats.steps < limit) {
+            let (a, b) = match self.select(strategy) {
+                None => break,
+                Some(pair) => pair,
+            };
+            let (x, y) = (self.agents[&a].symbol, self.agents[&b].symbol);
+            self.interact(a, b, observer);
+            stats.record(x, y, self.agents.len(), &self.cost);
+        }
+        stats
+    }
+
+    /// Returns the agent forming an active pair with `a` if it has a greater id.
+    fn partner(&self, a: usize) -> Option<usize> {
+        let agent = self.agents.get(&a)?;
+        let Port { agent: b, port: p } = agent.port(0)?;
+        if p != 0 || b < a {
+            return None;
+        }
+        let other = self.agents.get(&b)?;
+        self.rules.rule(agent.symbol, other.symbol)?;
+        Some(b)
+    }
+
+    fn select(&mut self, strategy: Strategy) -> Option<(usize, usize)> {
+        if matches!(strategy, Strategy::Outermost | Strategy::Fair) {
+            // Like Net::select, since these strategies never pop the worklist.
+            let stale: HashSet<_> = (self.worklist.pairs())
+                .filter(|&(a, b)| self.partner(a) != Some(b))
+                .collect();
+            self.worklist.remove(|a, b| stale.contains(&(a, b)));
+        }
+        if strategy == Strategy::Outermost {
+            let ports = |a| match self.agents.get(&a) {
+                None => Ports::new(std::slice::from_ref(&self.free[&a])),
+                Some(agent) => agent.ports(),
+            };
+            let depths = strategy::depths(self.free.keys().copied(), ports);
+            let depth = |a: usize| depths.get(&a).copied().unwrap_or(usize::MAX);
+            let pairs = self.active_pairs().into_iter();
+            return pairs.min_by_key(|&(a, b)| depth(a).min(depth(b)));
+        }
+        if strategy == Strategy::Fair {
+            let pairs = self.active_pairs();
+            let nodes = self.agents.keys().chain(self.free.keys()).copied();
+            let ports = |a| match self.agents.get(&a) {
+                None => Ports::new(std::slice::from_ref(&self.free[&a])),
+                Some(agent) => agent.ports(),
+            };
+            return self.worklist.fair(pairs, nodes, ports);
+        }
+        loop {
+            let (a, b) = self.worklist.pop(strategy)?.pair();
+            if self.partner(a) == Some(b) {
+                return Some((a, b));
+            }
+        }
+    }
+
+    fn get_port_unchecked(&self, x: Port) -> Port {
+        match self.agents.get(&x.agent) {
+            Some(agent) => agent.get_port(x.port),
+            None => self.free[&x.agent].get().unwrap(),
+        }
+    }
+
+    fn set_port(&mut self, x: Port, y: Port) {
+        match self.agents.get_mut(&x.agent) {
+            Some(agent) => {
+                agent.set_port(x.port, Some(y));
+            }
+            None => self.free[&x.agent] = Wire::new(Some(y)),
+        }
+    }
+
+    fn connect(&mut self, x: Port, y: Port, observer: &mut dyn NetObserver) {
+        observer.on_connect(x, y);
+        self.set_port(x, y);
+        self.set_port(y, x);
+        let (a, b) = (x.agent.min(y.agent), x.agent.max(y.agent));
+        if x.port == 0 && y.port == 0 && self.partner(a) == Some(b) {
+            let queue =
+                strategy::queue(&self.rules, self.agents[&a].symbol, self.agents[&b].symbol);
+            self.worklist.push(a, b, queue);
+        }
+    }
+
+    /// Applies the rule of the active pair `a`-`b`, like [`Net`] does.
+    fn interact(&mut self, a: usize, b: usize, observer: &mut dyn NetObserver) {
+        let symbol = |x| self.agents[&x].symbol;
+        let plan = Plan::new(&self.rules, a, b, symbol, |x| self.get_port_unchecked(x));
+        observer.on_rewrite((symbol(a), symbol(b)), a, b);
+        let mut agents = Vec::new();
+        for &symbol in &plan.agents {
+            let c = self.next;
+            let wires = vec![Wire::NONE; self.rules.arity(symbol) + 1];
+            self.agents.insert(c, Agent { symbol, wires });
+            self.next += 1;
+            observer.on_create(c, symbol);
+            agents.push(c);
+        }
+        let port = |x| match x {
+            End::Old(p) => p,
+            End::New(i, p) => Port::new(agents[i], p),
+        };
+        for (x, y) in plan.wires {
+            self.connect(port(x), port(y), observer);
+        }
+        for x in [a, b] {
+            self.agents.remove(&x);
+            observer.on_delete(x);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Random closed nets, for fuzzing, benchmarks, and demos.
+//!
+//! Unlike the generators of the `testing` feature, which explore all the nets of a given size,
+//! these nets are drawn from a seed with control over their symbols and how many agents start in
+//! an active pair.
+
+use crate::{Net, NetError, Port, RuleSet, Symbol};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// Number of swaps tried per wire to disconnect an agent from itself.
+const ATTEMPTS: usize = 100;
+
+impl Net {
+    /// Returns a random closed net of interaction combinators.
+    ///
+    /// See [`Net::random_with`].
+    pub fn random(
+        agents: usize,
+        weights: &[(Symbol, f64)],
+        active: f64,
+        seed: u64,
+    ) -> Result<Net, String> {
+        Net::random_with(RuleSet::combinators(), agents, weights, active, seed)
+    }
+
+    /// Returns a random closed net for the interaction system `rules`, the same for the same
+    /// seed.
+    ///
+    /// The symbol of each of the `agents` agents is drawn with the probability of its weight in
+    /// `weights`. An agent is added if needed for the ports to connect in pairs. About `active`
+    /// of the agents, between 0 and 1, start in an active pair. Other principal ports are
+    /// connected to auxiliary ports when there are enough, to not create more active pairs, and
+    /// the remaining ports are connected at random, never an agent to itself unless it cannot be
+    /// avoided.
+    pub fn random_with(
+        rules: RuleSet,
+        agents: usize,
+        weights: &[(Symbol, f64)],
+        active: f64,
+        seed: u64,
+    ) -> Result<Net, String> {
+        if let Some(&(symbol, _)) = weights.iter().find(|x| !rules.symbols().any(|s| s == x.0)) {
+            return Err(NetError::UnknownSymbol(symbol).to_string());
+        }
+        let index = WeightedIndex::new(weights.iter().map(|x| x.1))
+            .map_err(|e| format!("invalid weights: {}", e))?;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut net = Net::with_rules(rules.clone());
+        let mut symbols: Vec<_> = (0..agents)
+            .map(|_| weights[index.sample(&mut rng)].0)
+            .collect();
+        let ports = |s: Symbol| rules.arity(s) + 1;
+        if symbols.iter().map(|&s| ports(s)).sum::<usize>() % 2 == 1 {
+            // Some symbol has an odd number of ports, otherwise the sum would be even.
+            let odd: Vec<_> = weights.iter().filter(|x| ports(x.0) % 2 == 1).collect();
+            let odd = WeightedIndex::new(odd.iter().map(|x| x.1))
+                .map_or(odd[0].0, |index| odd[index.sample(&mut rng)].0);
+            symbols.push(odd);
+        }
+        let ids: Vec<_> = symbols
+            .iter()
+            .map(|&s| net.add_agent_unchecked(s))
+            .collect();
+        let mut order = ids.clone();
+        order.shuffle(&mut rng);
+        let target = (active.clamp(0., 1.) * ids.len() as f64 / 2.).round() as usize;
+        let mut principals = Vec::new();
+        let mut pairs = 0;
+        for pair in order.chunks(2) {
+            match *pair {
+                [a, b] if pairs < target && rules.rule(symbols[a], symbols[b]).is_some() => {
+                    // Agents of a new net are numbered from 0.
+                    net.connect_unchecked(Port::new(a, 0), Port::new(b, 0));
+                    pairs += 1;
+                }
+                _ => principals.extend(pair.iter().map(|&a| Port::new(a, 0))),
+            }
+        }
+        let mut auxiliaries: Vec<_> = ids
+            .iter()
+            .flat_map(|&a| (1..ports(net.symbol(a).unwrap())).map(move |p| Port::new(a, p)))
+            .collect();
+        auxiliaries.shuffle(&mut rng);
+        let split = principals.len().min(auxiliaries.len());
+        let mut mixed: Vec<_> = principals
+            .drain(..split)
+            .zip(auxiliaries.drain(..split))
+            .collect();
+        let mut rest: Vec<_> = principals.into_iter().chain(auxiliaries).collect();
+        rest.shuffle(&mut rng);
+        let mut rest: Vec<_> = rest.chunks(2).map(|x| (x[0], x[1])).collect();
+        for wires in [&mut mixed, &mut rest] {
+            untie(wires, &mut rng);
+            for &(x, y) in wires.iter() {
+                net.connect_unchecked(x, y);
+            }
+        }
+        Ok(net)
+    }
+}
+
+/// Swaps the second ports of wires to disconnect agents from themselves.
+fn untie(wires: &mut [(Port, Port)], rng: &mut StdRng) {
+    let tied = |(x, y): (Port, Port)| x.agent == y.agent;
+    for i in 0..wires.len() {
+        for _ in 0..ATTEMPTS {
+            if !tied(wires[i]) {
+                break;
+            }
+            let j = rng.gen_range(0..wires.len());
+            let ((a, b), (c, d)) = (wires[i], wires[j]);
+            if !tied((a, d)) && !tied((c, b)) {
+                wires[i] = (a, d);
+                wires[j] = (c, b);
+            }
+        }
+    }
+}
@@ -0,0 +1,364 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parallel reduction of regions of the net owned by threads.
+
+use crate::{Agent, CostModel, End, Net, Plan, Port, ReductionStats, RuleSet, Symbol, Wire};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+/// Agent or free port of a [`Region`].
+struct Node {
+    /// Symbol of the agent, `None` for free ports.
+    symbol: Option<Symbol>,
+    ports: Vec<Option<Port>>,
+    /// Id in the net, `None` for created agents.
+    id: Option<usize>,
+}
+
+/// Agents of the net with consecutive ids, or its free ports, owned by one thread at a time.
+///
+/// Node ids interleave the regions: node `x` is at index `x / count` of region `x % count`,
+/// where `count` is the number of regions.
+#[derive(Default)]
+struct Region {
+    nodes: Vec<Option<Node>>,
+    /// Indices of deleted nodes, reused by created agents.
+    vacant: Vec<usize>,
+    /// Number of nodes.
+    len: usize,
+    /// Active pairs whose first agent is in the region, possibly stale.
+    pairs: Vec<(usize, usize)>,
+    /// Active pairs whose regions were held by other threads, reduced between rounds.
+    deferred: Vec<(usize, usize)>,
+}
+
+impl Region {
+    fn node(&self, x: usize, count: usize) -> Option<&Node> {
+        self.nodes.get(x / count)?.as_ref()
+    }
+
+    /// Returns the regions of the nodes connected to node `x` of the region.
+    fn around(&self, x: usize, count: usize) -> Vec<usize> {
+        let ports = self.node(x, count).unwrap().ports.iter().flatten();
+        ports.map(|p| p.agent % count).collect()
+    }
+}
+
+/// Regions held by a thread, which may rewrite the active pairs whose agents and neighbors all
+/// belong to them.
+struct View<'a> {
+    count: usize,
+    held: Vec<(usize, &'a mut Region)>,
+}
+
+impl View<'_> {
+    fn region(&self, x: usize) -> &Region {
+        let r = x % self.count;
+        self.held.iter().find(|(i, _)| *i == r).unwrap().1
+    }
+
+    fn region_mut(&mut self, x: usize) -> &mut Region {
+        let r = x % self.count;
+        self.held.iter_mut().find(|(i, _)| *i == r).unwrap().1
+    }
+
+    fn node(&self, x: usize) -> &Node {
+        self.region(x).node(x, self.count).unwrap()
+    }
+
+    fn symbol(&self, x: usize) -> Symbol {
+        self.node(x).symbol.unwrap()
+    }
+
+    fn port(&self, x: Port) -> Port {
+        self.node(x.agent).ports[x.port].unwrap()
+    }
+
+    /// Returns whether `a`-`b` is an active pair with a rule.
+    fn active(&self, rules: &RuleSet, a: usize, b: usize) -> bool {
+        let node = match self.region(a).node(a, self.count) {
+            Some(node) if node.ports[0] == Some(Port::new(b, 0)) => node,
+            _ => return false,
+        };
+        let other = self.node(b);
+        matches!((node.symbol, other.symbol), (Some(x), Some(y)) if rules.rule(x, y).is_some())
+    }
+
+    /// Creates an agent in region `r`.
+    fn create(&mut self, r: usize, symbol: Symbol, arity: usize) -> usize {
+        let count = self.count;
+        let region = self.region_mut(r);
+        let node = Some(Node {
+            symbol: Some(symbol),
+            ports: vec![None; arity + 1],
+            id: None,
+        });
+        region.len += 1;
+        let i = match region.vacant.pop() {
+            Some(i) => {
+                region.nodes[i] = node;
+                i
+            }
+            None => {
+                region.nodes.push(node);
+                region.nodes.len() - 1
+            }
+        };
+        i * count + r
+    }
+
+    fn delete(&mut self, x: usize) {
+        let count = self.count;
+        let region = self.region_mut(x);
+        region.nodes[x / count] = None;
+        region.vacant.push(x / count);
+        region.len -= 1;
+    }
+
+    fn connect(&mut self, rules: &RuleSet, x: Port, y: Port) {
+        let count = self.count;
+        let mut set = |x: Port, y| {
+            let region = self.region_mut(x.agent);
+            let node = region.nodes[x.agent / count].as_mut().unwrap();
+            node.ports[x.port] = Some(y);
+        };
+        set(x, y);
+        set(y, x);
+        let (a, b) = (x.agent.min(y.agent), x.agent.max(y.agent));
+        if x.port == 0 && y.port == 0 && self.active(rules, a, b) {
+            self.region_mut(a).pairs.push((a, b));
+        }
+    }
+
+    /// Applies the rule of the active pair `a`-`b`, creating agents in the region of `a`.
+    fn rewrite(&mut self, rules: &RuleSet, a: usize, b: usize) -> (Symbol, Symbol) {
+        let plan = Plan::new(rules, a, b, |x| self.symbol(x), |x| self.port(x));
+        let r = a % self.count;
+        let mut agents = Vec::new();
+        for &symbol in &plan.agents {
+            agents.push(self.create(r, symbol, rules.arity(symbol)));
+        }
+        let port = |x| match x {
+            End::Old(p) => p,
+            End::New(i, p) => Port::new(agents[i], p),
+        };
+        for &(x, y) in &plan.wires {
+            self.connect(rules, port(x), port(y));
+        }
+        let rule = (self.symbol(a), self.symbol(b));
+        self.delete(a);
+        self.delete(b);
+        rule
+    }
+}
+
+/// Locks region `i` into `others` unless it is already there, returning false if another thread
+/// holds it.
+fn lock<'a>(
+    regions: &'a [Mutex<Region>],
+    i: usize,
+    others: &mut Vec<(usize, MutexGuard<'a, Region>)>,
+) -> bool {
+    if others.iter().any(|(j, _)| *j == i) {
+        return true;
+    }
+    match regions[i].try_lock() {
+        Ok(guard) => others.push((i, guard)),
+        Err(_) => return false,
+    }
+    true
+}
+
+/// Reduces the active pairs of region `r` until there are none left.
+///
+/// Active pairs whose neighbors are in other regions lock them, or are deferred if another thread
+/// holds them. Locks are only tried, so threads never wait for each other.
+fn round(regions: &[Mutex<Region>], r: usize, rules: &RuleSet, cost: &CostModel) -> ReductionStats {
+    let count = regions.len();
+    let mut stats = ReductionStats::default();
+    let mut home = regions[r].lock().unwrap();
+    while let Some((a, b)) = home.pairs.pop() {
+        match home.node(a, count) {
+            Some(node) if node.ports[0] == Some(Port::new(b, 0)) => (),
+            _ => continue,
+        }
+        let mut others = Vec::new();
+        let hold = |i, others: &mut _| i == r || lock(regions, i, others);
+        // The regions around `a` include the one of `b`, which gives the regions around `b`.
+        let mut locked = home
+            .around(a, count)
+            .into_iter()
+            .all(|i| hold(i, &mut others));
+        if locked {
+            let around = match b % count {
+                i if i == r => home.around(b, count),
+                i => others
+                    .iter()
+                    .find(|(j, _)| *j == i)
+                    .unwrap()
+                    .1
+                    .around(b, count),
+            };
+            locked = around.into_iter().all(|i| hold(i, &mut others));
+        }
+        if !locked {
+            home.deferred.push((a, b));
+            continue;
+        }
+        let others = others.iter_mut().map(|(i, guard)| (*i, &mut **guard));
+        let mut view = View {
+            count,
+            held: std::iter::once((r, &mut *home)).chain(others).collect(),
+        };
+        if view.active(rules, a, b) {
+            let (x, y) = view.rewrite(rules, a, b);
+            stats.record(x, y, 0, cost);
+        }
+    }
+    stats
+}
+
+impl Net {
+    /// Same as [`Net::reduce`] without limit but reduces on the rayon thread pool, splitting the
+    /// agents in `regions` regions of consecutive ids.
+    ///
+    /// Each region is owned by one thread at a time, which reduces the active pairs whose agents
+    /// and neighbors all belong to the region without synchronization, creating agents in the
+    /// same region. Active pairs across regions lock the other regions when they are free, and
+    /// are reduced sequentially between rounds otherwise. Threads steal regions from each other,
+    /// so a few regions per thread balance the load best.
+    ///
+    /// The normal form is the same as with [`Net::reduce`], but the ids of the created agents
+    /// depend on the scheduling. The peak number of agents is only measured between rounds. The
+    /// reduction is neither journaled nor traced.
+    pub fn reduce_regions(&mut self, regions: usize) -> ReductionStats {
+        assert!(regions > 0);
+        self.forget();
+        // The last region holds the free ports.
+        let count = regions + 1;
+        let mut agents: Vec<_> = self.agents.keys().copied().collect();
+        agents.sort_unstable();
+        let chunk = agents.len().div_ceil(regions).max(1);
+        let mut free: Vec<_> = self.free.keys().copied().collect();
+        free.sort_unstable();
+        let mut ids = HashMap::new();
+        let mut pairs = Vec::new();
+        let nodes = agents.iter().enumerate().map(|(i, &a)| (i / chunk, a));
+        let nodes: Vec<_> = nodes.chain(free.iter().map(|&f| (regions, f))).collect();
+        let mut parts: Vec<_> = (0..count).map(|_| Region::default()).collect();
+        for &(r, x) in &nodes {
+            ids.insert(x, parts[r].nodes.len() * count + r);
+            parts[r].nodes.push(None);
+        }
+        for (r, x) in nodes {
+            let (symbol, ports) = self.node(x).unwrap();
+            let ports = ports.map(|p| p.map(|p| Port::new(ids[&p.agent], p.port)));
+            let node = Node {
+                symbol,
+                ports: ports.collect(),
+                id: Some(x),
+            };
+            let region = &mut parts[r];
+            region.nodes[ids[&x] / count] = Some(node);
+            region.len += 1;
+            pairs.extend(self.partner(x).map(|y| (ids[&x], ids[&y])));
+        }
+        // Pairs go to the region of their first agent.
+        for (a, b) in pairs {
+            parts[a.min(b) % count].pairs.push((a.min(b), a.max(b)));
+        }
+        let mut regions: Vec<_> = parts.into_iter().map(Mutex::new).collect();
+        let mut stats = ReductionStats::new(self.agents.len());
+        let (rules, cost) = (&*self.rules, &self.cost);
+        loop {
+            let mut view = View {
+                count,
+                held: regions
+                    .iter_mut()
+                    .map(|x| x.get_mut().unwrap())
+                    .enumerate()
+                    .collect(),
+            };
+            let deferred: Vec<_> = view
+                .held
+                .iter_mut()
+                .flat_map(|(_, x)| std::mem::take(&mut x.deferred))
+                .collect();
+            for (a, b) in deferred {
+                if view.active(rules, a, b) {
+                    let (x, y) = view.rewrite(rules, a, b);
+                    stats.record(x, y, 0, cost);
+                }
+            }
+            let len = view.held[..count - 1].iter().map(|(_, x)| x.len).sum();
+            stats.peak_agents = stats.peak_agents.max(len);
+            if view.held.iter().all(|(_, x)| x.pairs.is_empty()) {
+                break;
+            }
+            let shared = &regions;
+            let round = (0..count)
+                .into_par_iter()
+                .map(|r| round(shared, r, rules, cost))
+                .reduce(ReductionStats::default, |mut x, y| {
+                    x.merge(&y);
+                    x
+                });
+            stats.merge(&round);
+        }
+        let mut next = self.next;
+        let mut back = HashMap::new();
+        let regions: Vec<_> = regions
+            .into_iter()
+            .map(|x| x.into_inner().unwrap())
+            .collect();
+        for (r, region) in regions.iter().enumerate() {
+            for (i, node) in region.nodes.iter().enumerate() {
+                if let Some(node) = node {
+                    let id = node.id.unwrap_or_else(|| {
+                        next += 1;
+                        next - 1
+                    });
+                    back.insert(i * count + r, id);
+                }
+            }
+        }
+        self.agents.clear();
+        self.next = next;
+        for (r, region) in regions.into_iter().enumerate() {
+            for (i, node) in region.nodes.into_iter().enumerate() {
+                let node = match node {
+                    Some(node) => node,
+                    None => continue,
+                };
+                let id = back[&(i * count + r)];
+                let ports = node
+                    .ports
+                    .iter()
+                    .map(|p| p.map(|p| Port::new(back[&p.agent], p.port)));
+                let ports: Vec<_> = ports.collect();
+                match node.symbol {
+                    Some(symbol) => {
+                        assert!(self.agents.insert(id, Agent::new(symbol, ports)).is_none())
+                    }
+                    None => *self.free.get_mut(&id).unwrap() = Wire::new(ports[0]),
+                }
+            }
+        }
+        // No active pairs are left.
+        self.worklist.drain();
+        stats
+    }
+}
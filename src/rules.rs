@@ -0,0 +1,221 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interaction systems.
+//!
+//! A [`RuleSet`] declares symbols with their arity and, for pairs of symbols, the net replacing
+//! an active pair of agents with those symbols.
+
+use std::collections::HashMap;
+
+/// Index of a symbol in a rule set.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Symbol(pub usize);
+
+impl Symbol {
+    pub const CONSTRUCT: Symbol = Symbol(0);
+    pub const DUPLICATE: Symbol = Symbol(1);
+    pub const ERASE: Symbol = Symbol(2);
+}
+
+/// A port in the right-hand side of a rule.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Slot {
+    /// Auxiliary port of the left agent of the active pair.
+    Left(usize),
+    /// Auxiliary port of the right agent of the active pair.
+    Right(usize),
+    /// Port of the created agent at the given index.
+    Agent(usize, usize),
+}
+
+impl Slot {
+    fn swap(self) -> Slot {
+        match self {
+            Slot::Left(p) => Slot::Right(p),
+            Slot::Right(p) => Slot::Left(p),
+            x => x,
+        }
+    }
+}
+
+/// Right-hand side of an interaction rule.
+///
+/// Every auxiliary port of the active pair and every port of the created agents must appear in
+/// exactly one wire.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rule {
+    pub agents: Vec<Symbol>,
+    pub wires: Vec<(Slot, Slot)>,
+}
+
+impl Rule {
+    fn swap(&self) -> Rule {
+        let wires = self.wires.iter().map(|&(x, y)| (x.swap(), y.swap()));
+        Rule {
+            agents: self.agents.clone(),
+            wires: wires.collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RuleSet {
+    symbols: Vec<(String, usize)>,
+    rules: HashMap<(Symbol, Symbol), Rule>,
+}
+
+impl RuleSet {
+    /// Creates a rule set without symbols.
+    pub fn new() -> RuleSet {
+        RuleSet::default()
+    }
+
+    /// Lafont's interaction combinators.
+    ///
+    /// The symbols are [`Symbol::CONSTRUCT`], [`Symbol::DUPLICATE`], and [`Symbol::ERASE`].
+    pub fn combinators() -> RuleSet {
+        use Slot::*;
+        let (c, d, e) = (Symbol::CONSTRUCT, Symbol::DUPLICATE, Symbol::ERASE);
+        let mut rules = RuleSet::new();
+        assert_eq!(rules.add_symbol("construct", 2), c);
+        assert_eq!(rules.add_symbol("duplicate", 2), d);
+        assert_eq!(rules.add_symbol("erase", 0), e);
+        let cc = Rule {
+            agents: vec![],
+            wires: vec![(Left(1), Right(2)), (Left(2), Right(1))],
+        };
+        let cd = Rule {
+            agents: vec![d, d, c, c],
+            wires: vec![
+                (Left(1), Agent(0, 0)),
+                (Left(2), Agent(1, 0)),
+                (Right(1), Agent(2, 0)),
+                (Right(2), Agent(3, 0)),
+                (Agent(0, 1), Agent(2, 1)),
+                (Agent(0, 2), Agent(3, 1)),
+                (Agent(1, 1), Agent(2, 2)),
+                (Agent(1, 2), Agent(3, 2)),
+            ],
+        };
+        let xe = Rule {
+            agents: vec![e, e],
+            wires: vec![(Left(1), Agent(0, 0)), (Left(2), Agent(1, 0))],
+        };
+        let dd = Rule {
+            agents: vec![],
+            wires: vec![(Left(1), Right(1)), (Left(2), Right(2))],
+        };
+        for (l, r, rule) in [
+            (c, c, cc),
+            (c, d, cd),
+            (c, e, xe.clone()),
+            (d, d, dd),
+            (d, e, xe),
+        ] {
+            rules.add_rule(l, r, rule).unwrap();
+        }
+        rules.add_rule(e, e, Rule::default()).unwrap();
+        rules
+    }
+
+    /// Symmetric interaction combinators, as used by HVM and other tools.
+    ///
+    /// Same as [`RuleSet::combinators`] except that constructors annihilate like duplicators,
+    /// connecting their auxiliary ports in order instead of crosswise.
+    pub fn symmetric() -> RuleSet {
+        let (c, d) = (Symbol::CONSTRUCT, Symbol::DUPLICATE);
+        let mut rules = RuleSet::combinators();
+        let dd = rules.rule(d, d).unwrap().clone();
+        rules.add_rule(c, c, dd).unwrap();
+        rules
+    }
+
+    /// Adds a symbol with `arity` auxiliary ports.
+    ///
+    /// Panics if `arity` is 65535 or more, since ports are packed in 16 bits.
+    pub fn add_symbol(&mut self, name: &str, arity: usize) -> Symbol {
+        assert!(arity < (1 << crate::wire::PORT_BITS) - 1, "arity too large");
+        self.symbols.push((name.to_string(), arity));
+        Symbol(self.symbols.len() - 1)
+    }
+
+    /// Adds the rule for the active pair `left`-`right`.
+    ///
+    /// The rule for `right`-`left` is derived by swapping `Slot::Left` and `Slot::Right`.
+    pub fn add_rule(&mut self, left: Symbol, right: Symbol, rule: Rule) -> Result<(), String> {
+        self.check(left, right, &rule)?;
+        if left != right {
+            self.rules.insert((right, left), rule.swap());
+        }
+        self.rules.insert((left, right), rule);
+        Ok(())
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = Symbol> {
+        (0..self.symbols.len()).map(Symbol)
+    }
+
+    pub fn name(&self, symbol: Symbol) -> &str {
+        &self.symbols[symbol.0].0
+    }
+
+    /// Returns the number of auxiliary ports of `symbol`.
+    pub fn arity(&self, symbol: Symbol) -> usize {
+        self.symbols[symbol.0].1
+    }
+
+    /// Returns the rules of the rule set, in both orientations.
+    pub fn rules(&self) -> impl Iterator<Item = (Symbol, Symbol, &Rule)> {
+        self.rules.iter().map(|(&(l, r), rule)| (l, r, rule))
+    }
+
+    /// Returns the rule for the active pair `left`-`right`, if any.
+    pub fn rule(&self, left: Symbol, right: Symbol) -> Option<&Rule> {
+        self.rules.get(&(left, right))
+    }
+
+    fn check(&self, left: Symbol, right: Symbol, rule: &Rule) -> Result<(), String> {
+        let known = |s: Symbol| s.0 < self.symbols.len();
+        if let Some(&s) = [left, right]
+            .iter()
+            .chain(&rule.agents)
+            .find(|&&s| !known(s))
+        {
+            return Err(format!("unknown symbol {}", s.0));
+        }
+        let mut slots = Vec::new();
+        slots.extend((1..=self.arity(left)).map(Slot::Left));
+        slots.extend((1..=self.arity(right)).map(Slot::Right));
+        for (i, &s) in rule.agents.iter().enumerate() {
+            slots.extend((0..=self.arity(s)).map(|p| Slot::Agent(i, p)));
+        }
+        let mut count: HashMap<Slot, usize> = slots.into_iter().map(|x| (x, 0)).collect();
+        for &(x, y) in &rule.wires {
+            for z in [x, y] {
+                match count.get_mut(&z) {
+                    None => return Err(format!("invalid slot {:?}", z)),
+                    Some(n) => *n += 1,
+                }
+            }
+        }
+        match count.into_iter().find(|&(_, n)| n != 1) {
+            None => Ok(()),
+            Some((x, n)) => Err(format!("slot {:?} used {} times", x, n)),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Saving and restoring the viewer.
+
+use crate::Viewer;
+use kiss3d::nalgebra::core::Vector3;
+use lafont::Net;
+use serde::{Deserialize, Serialize};
+
+/// The net with the position and velocity of its agents.
+#[derive(Serialize, Deserialize)]
+pub struct State {
+    net: Net,
+    nodes: Vec<(usize, [f32; 3], [f32; 3])>,
+}
+
+pub fn read(path: &str) -> Result<State, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    let reader = std::io::BufReader::new(file);
+    serde_json::from_reader(reader).map_err(|e| format!("{}: {}", path, e))
+}
+
+impl State {
+    pub fn net(&self) -> &Net {
+        &self.net
+    }
+}
+
+impl Viewer {
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut nodes: Vec<_> = self.scene.nodes.iter().collect();
+        nodes.sort_by_key(|&(&a, _)| a);
+        let nodes = nodes.into_iter().map(|(&a, n)| {
+            let (x, v) = (n.position, n.velocity);
+            (a, [x.x, x.y, x.z], [v.x, v.y, v.z])
+        });
+        let state = State {
+            net: self.net.clone(),
+            nodes: nodes.collect(),
+        };
+        let file = std::fs::File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+        let writer = std::io::BufWriter::new(file);
+        serde_json::to_writer(writer, &state).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// Replaces the net and its nodes with `state`.
+    ///
+    /// Agents without saved position start at the origin. The workspace becomes a single net,
+    /// named after the file.
+    pub fn restore(&mut self, state: State) {
+        self.replace(self.file.clone(), state.net);
+        for (a, [x, y, z], [vx, vy, vz]) in state.nodes {
+            if let Some(n) = self.scene.nodes.get_mut(&a) {
+                n.position = Vector3::new(x, y, z);
+                n.velocity = Vector3::new(vx, vy, vz);
+            }
+        }
+    }
+}
@@ -0,0 +1,274 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! HTTP server exposing the reduction of a net.
+
+use lafont::{Net, NetObserver, Port, RuleSet, Strategy, Symbol};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+
+const HELP: &str = "endpoints:
+  GET  /net[?format=sic]   the net in JSON (or in the textual format)
+  POST /net[?format=sic]   replace the net with the body
+  POST /step[?n=N]         reduce N active pairs (default 1)
+  GET  /state              number of agents, active pairs, reductions, and status
+  GET  /events             rewrite events as server-sent events";
+
+/// Maximum size of a request body, in bytes.
+const MAX_BODY: usize = 64 << 20;
+
+const BAD_REQUEST: &str = "400 Bad Request";
+
+struct Server {
+    net: Net,
+    reductions: usize,
+    /// Connections listening to events.
+    subscribers: Vec<Sender<String>>,
+}
+
+impl Server {
+    /// Sends `events` to all subscribers, forgetting those which are gone.
+    fn broadcast(&mut self, events: &[Value]) {
+        self.subscribers
+            .retain(|s| events.iter().all(|e| s.send(e.to_string()).is_ok()));
+    }
+
+    fn state(&self) -> Value {
+        json!({
+            "agents": self.net.len(),
+            "active_pairs": self.net.active_pairs().len(),
+            "reductions": self.reductions,
+            "status": self.net.status().to_string(),
+        })
+    }
+}
+
+/// Events of a reduction, in JSON.
+struct Events<'a> {
+    rules: &'a RuleSet,
+    events: Vec<Value>,
+}
+
+impl<'a> Events<'a> {
+    fn name(&self, symbol: Symbol) -> &'a str {
+        self.rules.name(symbol)
+    }
+}
+
+impl NetObserver for Events<'_> {
+    fn on_create(&mut self, a: usize, symbol: Symbol) {
+        let symbol = self.name(symbol);
+        let event = json!({"type": "create", "agent": a, "symbol": symbol});
+        self.events.push(event);
+    }
+
+    fn on_delete(&mut self, a: usize) {
+        self.events.push(json!({"type": "delete", "agent": a}));
+    }
+
+    fn on_connect(&mut self, x: Port, y: Port) {
+        let ports = [[x.agent, x.port], [y.agent, y.port]];
+        self.events.push(json!({"type": "connect", "ports": ports}));
+    }
+
+    fn on_rewrite(&mut self, (x, y): (Symbol, Symbol), a: usize, b: usize) {
+        let rule = [self.name(x), self.name(y)];
+        let event = json!({"type": "rewrite", "rule": rule, "agents": [a, b]});
+        self.events.push(event);
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    body: String,
+}
+
+/// Reads a request, or returns the status and the message of the error response.
+fn read_request(stream: &mut BufReader<TcpStream>) -> Result<Request, (&'static str, String)> {
+    let io = |e: std::io::Error| (BAD_REQUEST, e.to_string());
+    let mut line = String::new();
+    stream.read_line(&mut line).map_err(io)?;
+    let mut words = line.split_whitespace();
+    let (method, target) = match (words.next(), words.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target),
+        _ => return Err((BAD_REQUEST, format!("invalid request line {:?}", line))),
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter_map(|x| x.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let path = path.to_string();
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        stream.read_line(&mut header).map_err(io)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                let invalid = |_| (BAD_REQUEST, "invalid content length".to_string());
+                length = value.trim().parse().map_err(invalid)?;
+            }
+        }
+    }
+    if length > MAX_BODY {
+        let message = format!("body of {} bytes exceeds {} bytes", length, MAX_BODY);
+        return Err(("413 Payload Too Large", message));
+    }
+    let mut body = vec![0; length];
+    stream.read_exact(&mut body).map_err(io)?;
+    let body = String::from_utf8(body).map_err(|e| (BAD_REQUEST, e.to_string()))?;
+    Ok(Request {
+        method,
+        path,
+        query,
+        body,
+    })
+}
+
+fn respond(stream: &mut TcpStream, status: &str, kind: &str, body: &str) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{}",
+        status,
+        kind,
+        body.len(),
+        body
+    )
+}
+
+/// Handles a request, returning the content type and the body of the response.
+///
+/// Bodies that are JSON strings are sent as text.
+fn handle(server: &Mutex<Server>, request: &Request) -> Result<(String, Value), String> {
+    let mut server = server.lock().unwrap();
+    let sic = request.query.get("format").map(|x| x.as_str()) == Some("sic");
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/net") if sic => Ok(("text/plain".to_string(), server.net.to_sic().into())),
+        ("GET", "/net") => {
+            let net = serde_json::to_value(&server.net).map_err(|e| e.to_string())?;
+            Ok(("application/json".to_string(), net))
+        }
+        ("POST", "/net") => {
+            let net: Net = match sic {
+                true => Net::from_sic(&request.body)?,
+                false => serde_json::from_str(&request.body).map_err(|e| e.to_string())?,
+            };
+            // Wires of JSON nets are not checked when parsed, and reducing malformed nets panics.
+            if let Err(errors) = net.validate() {
+                let errors: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+                return Err(format!("malformed net:\n{}", errors.join("\n")));
+            }
+            server.net = net;
+            server.reductions = 0;
+            server.broadcast(&[json!({"type": "load"})]);
+            Ok(("application/json".to_string(), server.state()))
+        }
+        ("POST", "/step") => {
+            let n = match request.query.get("n") {
+                None => 1,
+                Some(n) => n.parse().map_err(|_| format!("invalid count {:?}", n))?,
+            };
+            let rules = server.net.rules().clone();
+            let mut events = Events {
+                rules: &rules,
+                events: Vec::new(),
+            };
+            let stats = server
+                .net
+                .reduce_observed(Strategy::PreferErase, Some(n), &mut events);
+            let events = events.events;
+            server.reductions += stats.steps;
+            server.broadcast(&events);
+            let mut state = server.state();
+            state["steps"] = stats.steps.into();
+            Ok(("application/json".to_string(), state))
+        }
+        ("GET", "/state") => Ok(("application/json".to_string(), server.state())),
+        _ => Err(format!(
+            "unknown endpoint {} {}",
+            request.method, request.path
+        )),
+    }
+}
+
+/// Streams events to the connection until it is closed.
+fn subscribe(server: &Mutex<Server>, stream: &mut TcpStream) -> std::io::Result<()> {
+    let (sender, receiver) = channel();
+    server.lock().unwrap().subscribers.push(sender);
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\
+         Access-Control-Allow-Origin: *\r\n\r\n"
+    )?;
+    for event in receiver {
+        write!(stream, "data: {}\n\n", event)?;
+    }
+    Ok(())
+}
+
+fn connection(server: &Mutex<Server>, stream: TcpStream) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut stream = stream;
+    let request = match read_request(&mut reader) {
+        Ok(x) => x,
+        Err((status, e)) => return respond(&mut stream, status, "text/plain", &e),
+    };
+    if request.method == "GET" && request.path == "/events" {
+        return subscribe(server, &mut stream);
+    }
+    match handle(server, &request) {
+        Ok((kind, Value::String(body))) => respond(&mut stream, "200 OK", &kind, &body),
+        Ok((kind, body)) => respond(&mut stream, "200 OK", &kind, &body.to_string()),
+        Err(e) => respond(&mut stream, BAD_REQUEST, "text/plain", &e),
+    }
+}
+
+/// Serves `net` on `port` forever, a thread per connection.
+pub fn run(port: u16, net: Net) {
+    let listener = TcpListener::bind(("127.0.0.1", port)).unwrap_or_else(|e| panic!("{}", e));
+    println!("listening on http://127.0.0.1:{}", port);
+    println!("{}", HELP);
+    let server = Arc::new(Mutex::new(Server {
+        net,
+        reductions: 0,
+        subscribers: Vec::new(),
+    }));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(x) => x,
+            Err(e) => {
+                println!("error: {}", e);
+                continue;
+            }
+        };
+        let server = server.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = connection(&server, stream) {
+                println!("error: {}", e);
+            }
+        });
+    }
+}
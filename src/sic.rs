@@ -0,0 +1,442 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Textual format of symmetric interaction combinators.
+//!
+//! A net is a list of equations `tree = tree`, each connecting the principal ports (or wires) of
+//! its two sides. A tree is one of:
+//!
+//! - `*` for an eraser,
+//! - `(a b)` for a constructor and `{a b}` for a duplicator, with the trees of their auxiliary
+//!   ports,
+//! - `name[a b ...]` for an agent of any symbol of the rule set,
+//! - a variable, naming a wire: a variable occurs twice, or once for a free port,
+//! - `_` for a port that is not connected.
+//!
+//! For example, `(a b) = {c d}` is a constructor-duplicator active pair with 4 free ports, and
+//! `(x x) = *` connects the auxiliary ports of a constructor erased by an eraser. Comments start
+//! with `//` and end with the line. Free ports are named after their variable, like `result` in
+//! `result = (x x)`.
+
+use crate::{Net, Port, RuleSet, Symbol};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// Builds a net from equations written inline, as parsed by [`Net::from_sic`].
+///
+/// For example, `net! { r = (a a) }` is the identity and `net! { (a b) = {c d} }` is a
+/// constructor-duplicator active pair. Variables must be Rust identifiers, and comments are not
+/// kept. A rule set in a variable is given first with `net!(rules; ...)`, like
+/// [`Net::from_sic_with`].
+///
+/// Panics if the equations do not describe a net, which is meant for test fixtures and examples.
+#[macro_export]
+macro_rules! net {
+    ($rules:ident; $($sic:tt)*) => {
+        match $crate::Net::from_sic_with($rules, stringify!($($sic)*)) {
+            Ok(net) => net,
+            Err(error) => panic!("invalid net: {}", error),
+        }
+    };
+    ($($sic:tt)*) => {{
+        let rules = $crate::RuleSet::combinators();
+        $crate::net!(rules; $($sic)*)
+    }};
+}
+
+impl Net {
+    /// Returns the net as equations, one per line.
+    ///
+    /// Active pairs come first, then free ports connected to principal ports, then trees whose
+    /// principal port is not connected, and finally trees in cycles through auxiliary ports.
+    /// Free ports are written with their [name](Net::set_name), or `f` followed by their id, and
+    /// other wires `x` followed by a number. Names are suffixed with `'` if needed to make them
+    /// unique. Free ports that are not connected are lost.
+    pub fn to_sic(&self) -> String {
+        let mut writer = Writer {
+            net: self,
+            names: HashMap::new(),
+            free: HashMap::new(),
+            taken: HashSet::new(),
+            written: HashSet::new(),
+            output: String::new(),
+        };
+        let mut free: Vec<_> = self.free.keys().copied().collect();
+        // Named free ports come first to keep their name.
+        free.sort_by_key(|f| (!self.names.contains_key(f), *f));
+        for f in free {
+            let name = match self.names.get(&f) {
+                Some(name) => name.clone(),
+                None => format!("f{}", f),
+            };
+            let name = writer.fresh(name);
+            writer.free.insert(f, name);
+        }
+        for (a, b) in self.active_pairs() {
+            let left = writer.tree(a);
+            let right = writer.tree(b);
+            writeln!(writer.output, "{} = {}", left, right).unwrap();
+        }
+        let mut free: Vec<_> = self.free.iter().map(|(&f, y)| (f, y.get())).collect();
+        free.sort();
+        for (f, y) in free {
+            match y {
+                Some(y) if y.port == 0 && self.agents.contains_key(&y.agent) => {
+                    let tree = writer.tree(y.agent);
+                    writeln!(writer.output, "{} = {}", writer.free[&f], tree).unwrap();
+                }
+                Some(y) if f < y.agent && self.free.contains_key(&y.agent) => {
+                    let (x, y) = (&writer.free[&f], &writer.free[&y.agent]);
+                    writeln!(writer.output, "{} = {}", x, y).unwrap();
+                }
+                _ => (),
+            }
+        }
+        let mut agents: Vec<_> = self.agents.keys().copied().collect();
+        agents.sort_unstable();
+        for &a in &agents {
+            if self.agents[&a].port(0).is_none() {
+                let tree = writer.tree(a);
+                writeln!(writer.output, "_ = {}", tree).unwrap();
+            }
+        }
+        for &a in &agents {
+            if !writer.written.contains(&a) {
+                let tree = writer.tree(a);
+                let name = writer.wire(Port::new(a, 0));
+                writeln!(writer.output, "{} = {}", name, tree).unwrap();
+            }
+        }
+        writer.output
+    }
+
+    /// Parses interaction combinators written by [`Net::to_sic`] or by other tools.
+    ///
+    /// The net follows Lafont's rules, where constructors annihilate crosswise. Most other tools
+    /// use [`RuleSet::symmetric`] instead, see [`Net::from_sic_with`].
+    pub fn from_sic(input: &str) -> Result<Net, String> {
+        Net::from_sic_with(RuleSet::combinators(), input)
+    }
+
+    /// Parses a net for the interaction system `rules`.
+    ///
+    /// The `*`, `(a b)`, and `{a b}` notations stand for the symbols named `erase`,
+    /// `construct`, and `duplicate`.
+    pub fn from_sic_with(rules: RuleSet, input: &str) -> Result<Net, String> {
+        let mut parser = Parser {
+            input,
+            pos: 0,
+            builder: Builder::new(rules),
+        };
+        loop {
+            parser.skip();
+            if parser.peek().is_none() {
+                break;
+            }
+            let left = parser.tree()?;
+            parser.skip();
+            if parser.peek() != Some('=') {
+                return Err(parser.error("expected '='"));
+            }
+            parser.pos += 1;
+            let right = parser.tree()?;
+            parser.link(left, right)?;
+        }
+        Ok(parser.builder.finish())
+    }
+}
+
+struct Writer<'a> {
+    net: &'a Net,
+    /// Names of the wires written once, by their smallest port.
+    names: HashMap<Port, String>,
+    /// Names of the free ports.
+    free: HashMap<usize, String>,
+    /// Names already used.
+    taken: HashSet<String>,
+    /// Agents already written.
+    written: HashSet<usize>,
+    output: String,
+}
+
+impl<'a> Writer<'a> {
+    /// Writes agent `a` and the agents whose principal port is connected to its auxiliary ports.
+    fn tree(&mut self, a: usize) -> String {
+        self.written.insert(a);
+        let agent = &self.net.agents[&a];
+        let mut children = Vec::new();
+        for p in 1..agent.wires.len() {
+            let x = Port::new(a, p);
+            let child = match agent.port(p) {
+                None => "_".to_string(),
+                Some(y) if self.net.free.contains_key(&y.agent) => self.free[&y.agent].clone(),
+                Some(y) if y.port == 0 && !self.written.contains(&y.agent) => self.tree(y.agent),
+                Some(_) => self.wire(x),
+            };
+            children.push(child);
+        }
+        let rules = &self.net.rules;
+        let children = children.join(" ");
+        match rules.name(agent.symbol) {
+            "erase" if children.is_empty() => "*".to_string(),
+            "construct" => format!("({})", children),
+            "duplicate" => format!("{{{}}}", children),
+            name => format!("{}[{}]", name, children),
+        }
+    }
+
+    /// Returns the name of the wire at `x`, which must be connected to an agent.
+    fn wire(&mut self, x: Port) -> String {
+        let y = self.net.agents[&x.agent].get_port(x.port);
+        if let Some(name) = self.names.get(&x.min(y)) {
+            return name.clone();
+        }
+        let name = self.fresh(format!("x{}", self.names.len()));
+        self.names.insert(x.min(y), name.clone());
+        name
+    }
+
+    /// Returns `name`, suffixed with `'` until it is not taken, and takes it.
+    fn fresh(&mut self, mut name: String) -> String {
+        while self.taken.contains(&name) {
+            name.push('\'');
+        }
+        self.taken.insert(name.clone());
+        name
+    }
+}
+
+/// Returns whether `name` can name a free port.
+pub(crate) fn is_name(name: &str) -> bool {
+    let valid = |c: char| c.is_alphanumeric() || "_.'-".contains(c);
+    !name.is_empty() && name != "_" && name.chars().all(valid)
+}
+
+/// One side of an equation, or the tree at an auxiliary port.
+pub(crate) enum End {
+    Port(Port),
+    Var(usize),
+    Unconnected,
+}
+
+/// What a variable occurrence is linked to.
+#[derive(Clone, Copy, PartialEq)]
+enum Link {
+    Port(Port),
+    Var(usize),
+}
+
+/// Builds a net whose wires are named by variables.
+pub(crate) struct Builder {
+    pub(crate) net: Net,
+    names: HashMap<String, usize>,
+    /// Name and links of each variable, by order of first occurrence.
+    vars: Vec<(String, Vec<Link>)>,
+}
+
+impl Builder {
+    pub(crate) fn new(rules: RuleSet) -> Builder {
+        Builder {
+            net: Net::with_rules(rules),
+            names: HashMap::new(),
+            vars: Vec::new(),
+        }
+    }
+
+    pub(crate) fn var(&mut self, name: &str) -> End {
+        if let Some(&v) = self.names.get(name) {
+            return End::Var(v);
+        }
+        self.names.insert(name.to_string(), self.vars.len());
+        self.vars.push((name.to_string(), Vec::new()));
+        End::Var(self.vars.len() - 1)
+    }
+
+    fn occur(&mut self, v: usize, link: Link) -> Result<(), String> {
+        let (name, links) = &mut self.vars[v];
+        if links.len() == 2 {
+            return Err(format!("variable {} occurs more than twice", name));
+        }
+        links.push(link);
+        Ok(())
+    }
+
+    /// Adds a free port named after variable `v`.
+    fn add_free(&mut self, v: usize) -> Port {
+        let f = self.net.add_free();
+        self.net.names.insert(f, self.vars[v].0.clone());
+        Port::new(f, 0)
+    }
+
+    pub(crate) fn link(&mut self, x: End, y: End) -> Result<(), String> {
+        match (x, y) {
+            (End::Port(x), End::Port(y)) => self.net.connect_unchecked(x, y),
+            (End::Port(x), End::Var(v)) | (End::Var(v), End::Port(x)) => {
+                self.occur(v, Link::Port(x))?
+            }
+            (End::Var(v), End::Var(w)) => {
+                self.occur(v, Link::Var(w))?;
+                self.occur(w, Link::Var(v))?;
+            }
+            (_, End::Unconnected) | (End::Unconnected, _) => (),
+        }
+        Ok(())
+    }
+
+    /// Connects the ends of the chains of variables, adding free ports for unmatched ends.
+    ///
+    /// Free ports are named after the variable at their end. Cycles of variables without ports
+    /// are dropped.
+    pub(crate) fn finish(mut self) -> Net {
+        let mut done = vec![false; self.vars.len()];
+        for v in 0..self.vars.len() {
+            let links = &self.vars[v].1;
+            if done[v] || links.iter().filter(|x| matches!(x, Link::Var(_))).count() == 2 {
+                continue;
+            }
+            done[v] = true;
+            let mut links = links.clone();
+            let mut ends = Vec::new();
+            // Start at v towards its port, then follow the variables to the other end.
+            let (mut prev, mut next) = (v, None);
+            if let Some(i) = links.iter().position(|x| matches!(x, Link::Port(_))) {
+                if let Link::Port(x) = links.remove(i) {
+                    ends.push(x);
+                }
+            } else {
+                ends.push(self.add_free(v));
+            }
+            match links.pop() {
+                None => ends.push(self.add_free(v)),
+                Some(Link::Port(y)) => ends.push(y),
+                Some(Link::Var(w)) => next = Some(w),
+            }
+            while let Some(w) = next.take() {
+                done[w] = true;
+                let mut links = self.vars[w].1.clone();
+                let i = links.iter().position(|&x| x == Link::Var(prev)).unwrap();
+                links.remove(i);
+                match links.pop() {
+                    None => ends.push(self.add_free(w)),
+                    Some(Link::Port(y)) => ends.push(y),
+                    Some(Link::Var(u)) => (prev, next) = (w, Some(u)),
+                }
+            }
+            self.net.connect_unchecked(ends[0], ends[1]);
+        }
+        self.net
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    builder: Builder,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn skip(&mut self) {
+        loop {
+            let rest = &self.input[self.pos..];
+            if rest.starts_with("//") {
+                self.pos += rest.find('\n').unwrap_or(rest.len());
+            } else {
+                match self.peek() {
+                    Some(c) if c.is_whitespace() => self.pos += c.len_utf8(),
+                    _ => break,
+                }
+            }
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at offset {}", message, self.pos)
+    }
+
+    fn ident(&mut self) -> Option<&'a str> {
+        self.skip();
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !c.is_alphanumeric() && !"_.'-".contains(c) {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+        (self.pos > start).then(|| &self.input[start..self.pos])
+    }
+
+    fn symbol(&self, name: &str) -> Result<Symbol, String> {
+        let rules = self.builder.net.rules();
+        let symbol = rules.symbols().find(|&s| rules.name(s) == name);
+        symbol.ok_or_else(|| self.error(&format!("unknown symbol {}", name)))
+    }
+
+    fn link(&mut self, x: End, y: End) -> Result<(), String> {
+        self.builder.link(x, y).map_err(|e| self.error(&e))
+    }
+
+    fn tree(&mut self) -> Result<End, String> {
+        self.skip();
+        let (symbol, close) = match self.peek() {
+            Some('*') => {
+                self.pos += 1;
+                (self.symbol("erase")?, None)
+            }
+            Some('(') => {
+                self.pos += 1;
+                (self.symbol("construct")?, Some(')'))
+            }
+            Some('{') => {
+                self.pos += 1;
+                (self.symbol("duplicate")?, Some('}'))
+            }
+            _ => {
+                let name = match self.ident() {
+                    Some(name) => name,
+                    None => return Err(self.error("expected a tree")),
+                };
+                // Agents may be written `name [a b]`, for example by the net macro.
+                self.skip();
+                if self.peek() != Some('[') {
+                    return Ok(match name {
+                        "_" => End::Unconnected,
+                        _ => self.builder.var(name),
+                    });
+                }
+                self.pos += 1;
+                (self.symbol(name)?, Some(']'))
+            }
+        };
+        let a = self.builder.net.add_agent_unchecked(symbol);
+        let arity = self.builder.net.rules().arity(symbol);
+        if let Some(close) = close {
+            for p in 1..=arity {
+                let child = self.tree()?;
+                self.link(End::Port(Port::new(a, p)), child)?;
+            }
+            self.skip();
+            if self.peek() != Some(close) {
+                return Err(self.error(&format!("expected '{}'", close)));
+            }
+            self.pos += 1;
+        } else if arity > 0 {
+            return Err(self.error("'*' has auxiliary ports"));
+        }
+        Ok(End::Port(Port::new(a, 0)))
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{Net, Port, RuleSet, Symbol};
+use std::collections::HashMap;
+
+impl Net {
+    /// Removes patterns of interaction combinators that cancel out, before reduction.
+    ///
+    /// Two erasers connected by their principal ports are removed. An agent whose auxiliary
+    /// ports are all connected to the principal port of an eraser is replaced by one of those
+    /// erasers, since whatever interacts with it ends up erased. This repeats until no pattern
+    /// is left. Reduction then takes fewer steps, and its normal form differs only by such
+    /// patterns.
+    ///
+    /// Nets of other interaction systems are left unchanged. Returns the number of removed
+    /// agents.
+    pub fn simplify(&mut self) -> usize {
+        if *self.rules != RuleSet::combinators() && *self.rules != RuleSet::symmetric() {
+            return 0;
+        }
+        let before = self.agents.len();
+        let mut todo: Vec<usize> = self.agents().collect();
+        todo.sort_unstable_by(|a, b| b.cmp(a));
+        while let Some(e) = todo.pop() {
+            let x = match self.agents.get(&e) {
+                Some(agent) if agent.symbol == Symbol::ERASE => agent.port(0),
+                _ => continue,
+            };
+            let x = match x {
+                Some(x) if self.agents.contains_key(&x.agent) => x,
+                _ => continue,
+            };
+            if x.port == 0 {
+                if self.agents[&x.agent].symbol == Symbol::ERASE {
+                    self.forget();
+                    self.delete(e);
+                    self.delete(x.agent);
+                }
+                continue;
+            }
+            let agent = &self.agents[&x.agent];
+            let erasers: Option<Vec<usize>> = agent
+                .ports()
+                .skip(1)
+                .map(|y| match y {
+                    Some(y) if y.port == 0 && self.symbol(y.agent) == Some(Symbol::ERASE) => {
+                        Some(y.agent)
+                    }
+                    _ => None,
+                })
+                .collect();
+            let (erasers, y) = match (erasers, agent.port(0)) {
+                (Some(erasers), Some(y)) => (erasers, y),
+                _ => continue,
+            };
+            self.forget();
+            for &c in &erasers {
+                if c != e {
+                    self.delete(c);
+                }
+            }
+            self.delete(x.agent);
+            self.connect_observed(Port::new(e, 0), y, &mut ());
+            todo.push(e);
+        }
+        before - self.agents.len()
+    }
+
+    /// Shares the isomorphic closed trees hanging off the auxiliary ports of a duplicator.
+    ///
+    /// A closed tree is a constructor or an eraser whose auxiliary ports are all connected to the
+    /// principal port of a closed tree. A duplicator whose auxiliary ports hold two copies of the
+    /// same closed tree is replaced by one of them, since duplicating the tree gives back both
+    /// copies. Trees are hash-consed bottom-up, so that comparing them takes constant time, and
+    /// sharing repeats until no duplicator holds two copies. Reduction then takes fewer steps,
+    /// and its normal form differs only by such sharing.
+    ///
+    /// Nets of other interaction systems are left unchanged. Returns the number of removed
+    /// agents.
+    pub fn share(&mut self) -> usize {
+        if *self.rules != RuleSet::combinators() {
+            return 0;
+        }
+        let before = self.agents.len();
+        let mut trees = Trees::default();
+        let mut todo: Vec<usize> = self.agents().collect();
+        todo.sort_unstable_by(|a, b| b.cmp(a));
+        while let Some(d) = todo.pop() {
+            let (x, y, p) = match self.agents.get(&d) {
+                Some(agent) if agent.symbol == Symbol::DUPLICATE => {
+                    (agent.port(1), agent.port(2), agent.port(0))
+                }
+                _ => continue,
+            };
+            let (x, y, p) = match (x, y, p) {
+                (Some(x), Some(y), Some(p)) if x.port == 0 && y.port == 0 => (x, y, p),
+                _ => continue,
+            };
+            let (s, t) = (trees.id(self, x.agent), trees.id(self, y.agent));
+            if s.is_none() || s != t {
+                continue;
+            }
+            self.forget();
+            let mut copy = vec![y.agent];
+            while let Some(a) = copy.pop() {
+                copy.extend(self.agents[&a].ports().skip(1).flatten().map(|z| z.agent));
+                self.delete(a);
+            }
+            self.delete(d);
+            self.connect_observed(x, p, &mut ());
+            // The agents above may now be closed trees, up to a duplicator holding two copies.
+            let mut p = Some(p);
+            while let Some(Port {
+                agent: a,
+                port: 1..,
+            }) = p
+            {
+                trees.ids.remove(&a);
+                match self.agents.get(&a) {
+                    Some(agent) if agent.symbol == Symbol::CONSTRUCT => p = agent.port(0),
+                    Some(agent) if agent.symbol == Symbol::DUPLICATE => {
+                        todo.push(a);
+                        break;
+                    }
+                    _ => break,
+                }
+            }
+        }
+        before - self.agents.len()
+    }
+}
+
+/// Hash-consed closed trees of constructors and erasers, see [`Net::share`].
+#[derive(Default)]
+struct Trees {
+    /// Id of the tree of each visited agent, `None` if it is not a closed tree.
+    ids: HashMap<usize, Option<usize>>,
+    /// Id of each tree, by the symbol of its root and the ids of its subtrees.
+    interned: HashMap<(Symbol, Vec<usize>), usize>,
+}
+
+impl Trees {
+    /// Returns the id of the closed tree rooted at agent `a`, if any.
+    ///
+    /// Isomorphic trees have the same id. Trees don't change while sharing, except the ones
+    /// deleted, so ids are computed once.
+    fn id(&mut self, net: &Net, a: usize) -> Option<usize> {
+        if let Some(&id) = self.ids.get(&a) {
+            return id;
+        }
+        // Cycles are not trees: the root is visited again before having an id.
+        self.ids.insert(a, None);
+        let agent = net.agents.get(&a)?;
+        if agent.symbol != Symbol::CONSTRUCT && agent.symbol != Symbol::ERASE {
+            return None;
+        }
+        let mut children = Vec::new();
+        for x in agent.ports().skip(1) {
+            match x {
+                Some(x) if x.port == 0 => children.push(self.id(net, x.agent)?),
+                _ => return None,
+            }
+        }
+        let len = self.interned.len();
+        let id = *self.interned.entry((agent.symbol, children)).or_insert(len);
+        self.ids.insert(a, Some(id));
+        Some(id)
+    }
+}
@@ -0,0 +1,172 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serialization of nets.
+//!
+//! A net is serialized as a [`Snapshot`] whose variant is the format version. New versions are
+//! added as new variants and older variants are converted when deserialized, so that old
+//! snapshots stay loadable.
+
+use crate::{Agent, Net, Port, Rule, RuleSet, Symbol, Wire};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Serialize, Deserialize)]
+enum Snapshot {
+    V1(V1),
+    V2(V2),
+}
+
+#[derive(Serialize, Deserialize)]
+struct V1 {
+    symbols: Vec<(String, usize)>,
+    /// Rules for pairs of symbols, smallest symbol first.
+    rules: Vec<(Symbol, Symbol, Rule)>,
+    agents: Vec<(usize, Symbol, Vec<Option<Port>>)>,
+    free: Vec<(usize, Option<Port>)>,
+    next: usize,
+}
+
+/// Same as [`V1`] with the names of the free ports.
+#[derive(Serialize, Deserialize)]
+struct V2 {
+    symbols: Vec<(String, usize)>,
+    rules: Vec<(Symbol, Symbol, Rule)>,
+    agents: Vec<(usize, Symbol, Vec<Option<Port>>)>,
+    free: Vec<(usize, Option<Port>)>,
+    /// Names of free ports, sorted by id.
+    names: Vec<(usize, String)>,
+    next: usize,
+}
+
+impl From<V1> for V2 {
+    fn from(v1: V1) -> V2 {
+        V2 {
+            symbols: v1.symbols,
+            rules: v1.rules,
+            agents: v1.agents,
+            free: v1.free,
+            names: Vec::new(),
+            next: v1.next,
+        }
+    }
+}
+
+impl Net {
+    /// Returns the net in JSON.
+    ///
+    /// This is the [`Serialize`] implementation with [`serde_json`]. The net is an object with a
+    /// single field naming the format version, currently `V2`, whose value has the fields:
+    ///
+    /// - `symbols`: the name and arity of each symbol, in order. Symbols are referred to by
+    ///   their index in this list.
+    /// - `rules`: for each pair of symbols with a rule, smallest first, the left symbol, the
+    ///   right symbol, and the [`Rule`] as an object with the symbols of the created `agents`
+    ///   and the `wires` between slots, like `{"Left":1}`, `{"Right":2}`, or `{"Agent":[0,1]}`.
+    /// - `agents`: for each agent, sorted by id, its id, its symbol, and its ports. A port is
+    ///   `null` if not connected, otherwise the port it is connected to as an object with the
+    ///   fields `agent` (an agent or free port id) and `port`.
+    /// - `free`: for each free port, sorted by id, its id and the port it is connected to.
+    /// - `names`: for each named free port, sorted by id, its id and its name.
+    /// - `next`: the id of the next added agent or free port, greater than all ids.
+    ///
+    /// For example, a constructor whose principal port is connected to an eraser and whose
+    /// first auxiliary port is connected to a free port (rules elided):
+    ///
+    /// ```json
+    /// {"V2": {
+    ///   "symbols": [["construct", 2], ["duplicate", 2], ["erase", 0]],
+    ///   "rules": [...],
+    ///   "agents": [[0, 0, [{"agent": 2, "port": 0}, {"agent": 1, "port": 0}, null]],
+    ///              [2, 2, [{"agent": 0, "port": 0}]]],
+    ///   "free": [[1, {"agent": 0, "port": 1}]],
+    ///   "names": [[1, "result"]],
+    ///   "next": 3
+    /// }}
+    /// ```
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// Parses a net returned by [`Net::to_json`], or by previous versions.
+    ///
+    /// Symbols, arities, and ids are checked, but not wires: see [`Net::validate`].
+    pub fn from_json(json: &str) -> Result<Net, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+impl Serialize for Net {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let rules = &self.rules;
+        let symbols = rules
+            .symbols()
+            .map(|s| (rules.name(s).to_string(), rules.arity(s)));
+        let pairs = rules.rules().filter(|&(l, r, _)| l <= r);
+        let agents = self.agents.iter();
+        let mut v2 = V2 {
+            symbols: symbols.collect(),
+            rules: pairs.map(|(l, r, x)| (l, r, x.clone())).collect(),
+            agents: agents
+                .map(|(&a, x)| (a, x.symbol, x.ports().collect()))
+                .collect(),
+            free: self.free.iter().map(|(&a, x)| (a, x.get())).collect(),
+            names: self.names.iter().map(|(&a, x)| (a, x.clone())).collect(),
+            next: self.next,
+        };
+        v2.rules.sort_by_key(|&(l, r, _)| (l, r));
+        v2.agents.sort_by_key(|&(a, _, _)| a);
+        v2.free.sort_by_key(|&(a, _)| a);
+        v2.names.sort();
+        Snapshot::V2(v2).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Net {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Net, D::Error> {
+        let v2 = match Snapshot::deserialize(deserializer)? {
+            Snapshot::V1(v1) => V2::from(v1),
+            Snapshot::V2(v2) => v2,
+        };
+        let mut rules = RuleSet::new();
+        for (name, arity) in &v2.symbols {
+            rules.add_symbol(name, *arity);
+        }
+        for (l, r, rule) in v2.rules {
+            rules.add_rule(l, r, rule).map_err(D::Error::custom)?;
+        }
+        let mut net = Net::with_rules(rules);
+        for (a, symbol, ports) in v2.agents {
+            if symbol.0 >= v2.symbols.len() || ports.len() != net.rules.arity(symbol) + 1 {
+                return Err(D::Error::custom(format!("invalid agent {}", a)));
+            }
+            net.agents.insert(a, Agent::new(symbol, ports));
+        }
+        net.free
+            .extend(v2.free.into_iter().map(|(a, x)| (a, Wire::new(x))));
+        for (f, name) in v2.names {
+            net.set_name(f, &name).map_err(D::Error::custom)?;
+        }
+        net.next = v2.next;
+        let mut ids = net.agents.keys().chain(net.free.keys());
+        if ids.any(|&a| a >= net.next) {
+            return Err(D::Error::custom("invalid next id"));
+        }
+        for (a, b) in net.active_pairs() {
+            let queue = net.queue(a, b);
+            net.worklist.push(a, b, queue);
+        }
+        Ok(net)
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Instanced rendering of the agents.
+
+use glow::HasContext;
+use kiss3d::camera::Camera;
+use kiss3d::context::Context;
+use kiss3d::nalgebra::core::Vector3;
+use kiss3d::renderer::Renderer;
+use lafont::Symbol;
+use std::collections::BTreeMap;
+use std::f32::consts::{PI, TAU};
+use std::sync::Arc;
+
+type Gl = glow::Context;
+
+/// Number of floats per instance: center, radius, and color.
+const STRIDE: usize = 7;
+
+/// Draws spheres with one instanced draw call per symbol.
+///
+/// A kiss3d scene node per agent costs a draw call per agent, which dominates the frame time of
+/// large nets. Instead, the spheres of a frame are queued with [`Spheres::draw`] and uploaded to
+/// an instance buffer when the frame is rendered.
+pub struct Spheres {
+    gl: Arc<Gl>,
+    program: <Gl as HasContext>::Program,
+    /// Vertices of the triangles of a unit sphere, which are also their normals.
+    mesh: <Gl as HasContext>::Buffer,
+    vertices: i32,
+    instances: <Gl as HasContext>::Buffer,
+    /// Locations of the attributes, in the order: vertex, center, radius, color.
+    attributes: [u32; 4],
+    transform: Option<<Gl as HasContext>::UniformLocation>,
+    eye: Option<<Gl as HasContext>::UniformLocation>,
+    /// Instances to draw at the next frame, per symbol.
+    queue: BTreeMap<Symbol, Vec<f32>>,
+}
+
+impl Spheres {
+    /// Compiles the shaders and uploads the mesh, once the window is created.
+    pub fn new() -> Spheres {
+        let gl = Context::get().ctxt.context;
+        let mesh = sphere(12, 16);
+        let bytes: Vec<u8> = mesh.iter().flat_map(|x| x.to_ne_bytes()).collect();
+        unsafe {
+            let program = gl.create_program().unwrap();
+            let mut shaders = Vec::new();
+            for (kind, source) in [
+                (glow::VERTEX_SHADER, VERTEX_SHADER),
+                (glow::FRAGMENT_SHADER, FRAGMENT_SHADER),
+            ] {
+                let shader = gl.create_shader(kind).unwrap();
+                gl.shader_source(shader, source);
+                gl.compile_shader(shader);
+                assert!(
+                    gl.get_shader_compile_status(shader),
+                    "{}",
+                    gl.get_shader_info_log(shader)
+                );
+                gl.attach_shader(program, shader);
+                shaders.push(shader);
+            }
+            gl.link_program(program);
+            assert!(
+                gl.get_program_link_status(program),
+                "{}",
+                gl.get_program_info_log(program)
+            );
+            for shader in shaders {
+                gl.detach_shader(program, shader);
+                gl.delete_shader(shader);
+            }
+            let attribute = |name| gl.get_attrib_location(program, name).unwrap();
+            let attributes = [
+                attribute("vertex"),
+                attribute("center"),
+                attribute("radius"),
+                attribute("color"),
+            ];
+            let buffer = gl.create_buffer().unwrap();
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(buffer));
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, &bytes, glow::STATIC_DRAW);
+            Spheres {
+                program,
+                mesh: buffer,
+                vertices: (mesh.len() / 3) as i32,
+                instances: gl.create_buffer().unwrap(),
+                attributes,
+                transform: gl.get_uniform_location(program, "transform"),
+                eye: gl.get_uniform_location(program, "eye"),
+                queue: BTreeMap::new(),
+                gl,
+            }
+        }
+    }
+
+    /// Draws a sphere for an agent with `symbol` at the next frame.
+    pub fn draw(&mut self, symbol: Symbol, center: Vector3<f32>, radius: f32, color: [f32; 3]) {
+        let batch = self.queue.entry(symbol).or_default();
+        batch.extend_from_slice(center.as_slice());
+        batch.push(radius);
+        batch.extend_from_slice(&color);
+    }
+}
+
+impl Renderer for Spheres {
+    fn render(&mut self, _pass: usize, camera: &mut dyn Camera) {
+        if self.queue.values().all(|batch| batch.is_empty()) {
+            return;
+        }
+        let gl = &self.gl;
+        let [vertex, center, radius, color] = self.attributes;
+        unsafe {
+            gl.use_program(Some(self.program));
+            let transform = camera.transformation();
+            gl.uniform_matrix_4_f32_slice(self.transform.as_ref(), false, transform.as_slice());
+            gl.uniform_3_f32_slice(self.eye.as_ref(), camera.eye().coords.as_slice());
+            gl.enable(glow::DEPTH_TEST);
+            gl.disable(glow::CULL_FACE);
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.mesh));
+            gl.enable_vertex_attrib_array(vertex);
+            gl.vertex_attrib_pointer_f32(vertex, 3, glow::FLOAT, false, 0, 0);
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.instances));
+            let stride = (STRIDE * 4) as i32;
+            for (index, size, offset) in [(center, 3, 0), (radius, 1, 3), (color, 3, 4)] {
+                gl.enable_vertex_attrib_array(index);
+                gl.vertex_attrib_pointer_f32(index, size, glow::FLOAT, false, stride, 4 * offset);
+                gl.vertex_attrib_divisor(index, 1);
+            }
+            for batch in self.queue.values_mut() {
+                if batch.is_empty() {
+                    continue;
+                }
+                let bytes: Vec<u8> = batch.iter().flat_map(|x| x.to_ne_bytes()).collect();
+                gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, &bytes, glow::STREAM_DRAW);
+                let count = (batch.len() / STRIDE) as i32;
+                gl.draw_arrays_instanced(glow::TRIANGLES, 0, self.vertices, count);
+                batch.clear();
+            }
+            // Kiss3d shares the vertex array, so its attributes must look untouched.
+            for index in [vertex, center, radius, color] {
+                gl.vertex_attrib_divisor(index, 0);
+                gl.disable_vertex_attrib_array(index);
+            }
+        }
+    }
+}
+
+/// Returns the vertices of the triangles of a unit sphere, 3 floats each.
+///
+/// The sphere is cut into `stacks` from pole to pole and `slices` around the axis.
+fn sphere(stacks: usize, slices: usize) -> Vec<f32> {
+    let point = |i: usize, j: usize| {
+        let theta = PI * i as f32 / stacks as f32;
+        let phi = TAU * j as f32 / slices as f32;
+        let (sin, cos) = theta.sin_cos();
+        [sin * phi.cos(), cos, sin * phi.sin()]
+    };
+    let mut vertices = Vec::new();
+    for i in 0..stacks {
+        for j in 0..slices {
+            let quad = [
+                point(i, j),
+                point(i + 1, j),
+                point(i + 1, j + 1),
+                point(i, j + 1),
+            ];
+            for k in [0, 1, 2, 0, 2, 3] {
+                vertices.extend_from_slice(&quad[k]);
+            }
+        }
+    }
+    vertices
+}
+
+const VERTEX_SHADER: &str = "#version 100
+attribute vec3 vertex;
+attribute vec3 center;
+attribute float radius;
+attribute vec3 color;
+uniform mat4 transform;
+varying vec3 Position;
+varying vec3 Normal;
+varying vec3 Color;
+void main() {
+    Position = center + radius * vertex;
+    Normal = vertex;
+    Color = color;
+    gl_Position = transform * vec4(Position, 1.0);
+}";
+
+// The light sticks to the camera, like the scene nodes of kiss3d did.
+const FRAGMENT_SHADER: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+uniform vec3 eye;
+varying vec3 Position;
+varying vec3 Normal;
+varying vec3 Color;
+void main() {
+    vec3 light = normalize(eye - Position);
+    float diffuse = max(dot(normalize(Normal), light), 0.0);
+    gl_FragColor = vec4(Color * (0.3 + 0.7 * diffuse), 1.0);
+}";
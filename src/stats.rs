@@ -0,0 +1,116 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Net, Symbol};
+use std::collections::HashMap;
+
+/// Statistics of [`Net::reduce`](crate::Net::reduce).
+#[derive(Clone, Debug, Default)]
+pub struct ReductionStats {
+    /// Number of reduced active pairs.
+    pub steps: usize,
+    /// Maximum number of agents during the reduction.
+    pub peak_agents: usize,
+    /// Number of reduced active pairs per pair of symbols, smallest symbol first.
+    pub rules: HashMap<(Symbol, Symbol), usize>,
+    /// Sum of the weights of the reduced active pairs, see [`CostModel`].
+    pub cost: f64,
+    /// Number of active pairs reduced by each pass of
+    /// [`Net::reduce_sweeps`](crate::Net::reduce_sweeps), empty for other reductions.
+    pub sweeps: Vec<usize>,
+}
+
+/// Weights of the rules, to measure reductions by more than their number of steps.
+///
+/// Rules without a weight cost 1, so that the cost of a reduction is its number of steps by
+/// default.
+#[derive(Clone, Debug, Default)]
+pub struct CostModel {
+    /// Weights per pair of symbols, smallest symbol first.
+    weights: HashMap<(Symbol, Symbol), f64>,
+}
+
+impl CostModel {
+    pub fn new() -> CostModel {
+        CostModel::default()
+    }
+
+    /// Sets the weight of the rule for `a`-`b` (and `b`-`a`).
+    pub fn set(&mut self, a: Symbol, b: Symbol, weight: f64) {
+        self.weights.insert((a.min(b), a.max(b)), weight);
+    }
+
+    /// Returns the weight of the rule for `a`-`b` (or `b`-`a`).
+    pub fn weight(&self, a: Symbol, b: Symbol) -> f64 {
+        self.weights
+            .get(&(a.min(b), a.max(b)))
+            .copied()
+            .unwrap_or(1.)
+    }
+}
+
+impl ReductionStats {
+    pub(crate) fn new(agents: usize) -> ReductionStats {
+        ReductionStats {
+            peak_agents: agents,
+            ..ReductionStats::default()
+        }
+    }
+
+    pub(crate) fn record(&mut self, a: Symbol, b: Symbol, agents: usize, cost: &CostModel) {
+        self.steps += 1;
+        self.cost += cost.weight(a, b);
+        self.peak_agents = self.peak_agents.max(agents);
+        *self.rules.entry((a.min(b), a.max(b))).or_default() += 1;
+    }
+
+    /// Adds the statistics of a subsequent reduction.
+    pub fn merge(&mut self, other: &ReductionStats) {
+        self.steps += other.steps;
+        self.peak_agents = self.peak_agents.max(other.peak_agents);
+        self.cost += other.cost;
+        self.sweeps.extend(&other.sweeps);
+        for (&rule, &count) in &other.rules {
+            *self.rules.entry(rule).or_default() += count;
+        }
+    }
+
+    /// Returns the average number of active pairs per pass of
+    /// [`Net::reduce_sweeps`](crate::Net::reduce_sweeps).
+    ///
+    /// This is the speedup of an ideal parallel reducer over a sequential one, or 0 without
+    /// passes.
+    pub fn parallelism(&self) -> f64 {
+        match self.sweeps.len() {
+            0 => 0.,
+            n => self.sweeps.iter().sum::<usize>() as f64 / n as f64,
+        }
+    }
+
+    /// Returns how many active pairs `a`-`b` (or `b`-`a`) were reduced.
+    pub fn count(&self, a: Symbol, b: Symbol) -> usize {
+        self.rules.get(&(a.min(b), a.max(b))).copied().unwrap_or(0)
+    }
+}
+
+impl Net {
+    /// Sets the weights of the rules accumulated in [`ReductionStats::cost`].
+    pub fn set_cost_model(&mut self, cost: CostModel) {
+        self.cost = cost;
+    }
+
+    pub fn cost_model(&self) -> &CostModel {
+        &self.cost
+    }
+}
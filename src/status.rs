@@ -0,0 +1,106 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Net, Port};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Whether reduction can continue, see [`Net::status`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Status {
+    /// There are active pairs.
+    Reducible,
+    /// There are no active pairs nor vicious circles.
+    NormalForm,
+    /// There are no active pairs, but the agents of each cycle have their principal port
+    /// connected to an auxiliary port of the next agent, so they never interact.
+    ///
+    /// Each cycle starts with its smallest agent, and cycles are sorted.
+    Deadlocked { cycles: Vec<Vec<usize>> },
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Reducible => write!(f, "reducible"),
+            Status::NormalForm => write!(f, "normal form"),
+            Status::Deadlocked { cycles } => {
+                write!(f, "deadlocked on")?;
+                for (i, cycle) in cycles.iter().enumerate() {
+                    let sep = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{:?}", sep, cycle)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Net {
+    /// Returns whether the net can be reduced, and otherwise why not.
+    ///
+    /// Active pairs without rule are not reducible, so a net with only such pairs is in normal
+    /// form (or deadlocked).
+    pub fn status(&self) -> Status {
+        if !self.active_pairs().is_empty() {
+            return Status::Reducible;
+        }
+        let cycles = self.vicious_circles();
+        if cycles.is_empty() {
+            Status::NormalForm
+        } else {
+            Status::Deadlocked { cycles }
+        }
+    }
+
+    /// Returns the cycles of agents whose principal port is connected to an auxiliary port of
+    /// the next one.
+    fn vicious_circles(&self) -> Vec<Vec<usize>> {
+        // Each agent has at most one successor, so each walk ends in a cycle or stops.
+        let next = |a: usize| match self.agents[&a].port(0) {
+            Some(Port { agent: b, port }) if port > 0 && self.agents.contains_key(&b) => Some(b),
+            _ => None,
+        };
+        let mut agents: Vec<_> = self.agents.keys().copied().collect();
+        agents.sort_unstable();
+        // The walk that visited each agent.
+        let mut walks = HashMap::new();
+        let mut cycles = Vec::new();
+        for (walk, &start) in agents.iter().enumerate() {
+            let mut a = start;
+            while !walks.contains_key(&a) {
+                walks.insert(a, walk);
+                a = match next(a) {
+                    Some(b) => b,
+                    None => break,
+                };
+            }
+            if walks[&a] != walk || next(a).is_none() {
+                continue;
+            }
+            // The walk came back to a, so a is on a new cycle.
+            let mut cycle = vec![a];
+            let mut b = next(a).unwrap();
+            while b != a {
+                cycle.push(b);
+                b = next(b).unwrap();
+            }
+            let first = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap();
+            cycle.rotate_left(first);
+            cycles.push(cycle);
+        }
+        cycles.sort();
+        cycles
+    }
+}
@@ -0,0 +1,422 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::journal::Change;
+use crate::{Net, Port, Ports, RuleSet, Symbol};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Range;
+
+/// Which active pair to reduce next.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Strategy {
+    /// The active pair that became active first, preferring the ones involving an eraser.
+    #[default]
+    PreferErase,
+    /// The active pair that became active first.
+    Fifo,
+    /// The active pair that became active last.
+    Lifo,
+    /// An active pair chosen uniformly at random.
+    ///
+    /// The random generator is seeded from entropy unless [`Net::seed`] is called.
+    Random,
+    /// The active pair closest to a free port.
+    ///
+    /// This strategy scans the whole net at each step, which thus takes a time linear in the
+    /// size of the net.
+    Outermost,
+    /// The active pair that became active first, preferring the ones involving an eraser, then
+    /// the ones between agents with the same symbol.
+    ///
+    /// Active pairs between agents with the same symbol annihilate (or erase each other) with
+    /// the combinator rules, while the others commute and create agents. Delaying commutations
+    /// keeps the net smaller when many agents get duplicated. [`Strategy::PreferErase`] instead
+    /// treats annihilations and commutations alike, reducing the one that became active first.
+    PreferAnnihilate,
+    /// The first active pair of the connected component served least recently.
+    ///
+    /// Components take turns, such that a net with several components shows all of them
+    /// evolving, while the other strategies may reduce a single component until it has no
+    /// active pairs, forever if it diverges. This strategy computes the components of the whole
+    /// net at each step, which thus takes a time linear in the size of the net.
+    Fair,
+}
+
+/// Queues of a [`Worklist`], with entries `(time, a, b)`.
+pub(crate) type Queues = [VecDeque<(usize, usize, usize)>; 3];
+
+/// Turns of [`Strategy::Fair`], as the served nodes and the number of turns of a [`Worklist`].
+pub(crate) type Turns = (HashMap<usize, usize>, usize);
+
+/// Queue of the pairs involving an eraser.
+const ERASE: usize = 0;
+/// Queue of the other pairs between agents with the same symbol.
+const ANNIHILATE: usize = 1;
+/// Queue of the other pairs.
+const COMMUTE: usize = 2;
+
+/// Active pairs in the order they became active.
+///
+/// Pairs are not removed when they stop being active, but skipped when selected. Pairs involving
+/// an eraser, between agents with the same symbol, and the others are kept apart, to select them
+/// in this order with some strategies.
+#[derive(Clone, Debug)]
+pub(crate) struct Worklist {
+    queues: Queues,
+    time: usize,
+    rng: StdRng,
+    /// Turn at which [`Strategy::Fair`] last served the component of some nodes.
+    ///
+    /// The neighbors of the reduced pair are marked, since they outlive the pair and stay in the
+    /// same component.
+    served: HashMap<usize, usize>,
+    /// Number of turns of [`Strategy::Fair`].
+    turns: usize,
+}
+
+impl Default for Worklist {
+    fn default() -> Worklist {
+        Worklist {
+            queues: Default::default(),
+            time: 0,
+            rng: StdRng::from_entropy(),
+            served: HashMap::new(),
+            turns: 0,
+        }
+    }
+}
+
+impl Worklist {
+    /// Adds a pair to `queue`, as returned by [`Net::queue`], and returns it.
+    pub(crate) fn push(&mut self, a: usize, b: usize, queue: usize) -> usize {
+        self.queues[queue].push_back((self.time, a, b));
+        self.time += 1;
+        queue
+    }
+
+    pub(crate) fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// Undoes the last push to `queue`.
+    pub(crate) fn unpush(&mut self, queue: usize) {
+        self.queues[queue].pop_back().unwrap();
+        self.time -= 1;
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+
+    /// Returns the queue among `queues` with the oldest front (if `front`) or youngest back.
+    fn pick(&self, queues: Range<usize>, front: bool) -> Option<usize> {
+        let time = |q: usize| {
+            let q = &self.queues[q];
+            let x = if front { q.front() } else { q.back() };
+            x.map(|x| x.0)
+        };
+        let queues = queues.filter_map(|q| Some((time(q)?, q)));
+        match front {
+            true => queues.min().map(|x| x.1),
+            false => queues.max().map(|x| x.1),
+        }
+    }
+
+    /// Returns the first non-empty queue.
+    fn first(&self) -> usize {
+        let queue = self.queues.iter().position(|q| !q.is_empty());
+        queue.unwrap_or(ERASE)
+    }
+
+    pub(crate) fn pop(&mut self, strategy: Strategy) -> Option<Popped> {
+        let (queue, index, swap) = match strategy {
+            Strategy::PreferErase => match self.queues[ERASE].is_empty() {
+                false => (ERASE, 0, false),
+                true => (self.pick(ANNIHILATE..COMMUTE + 1, true)?, 0, false),
+            },
+            Strategy::PreferAnnihilate => (self.first(), 0, false),
+            Strategy::Fifo => (self.pick(ERASE..COMMUTE + 1, true)?, 0, false),
+            Strategy::Lifo => {
+                let queue = self.pick(ERASE..COMMUTE + 1, false)?;
+                (queue, self.queues[queue].len() - 1, false)
+            }
+            Strategy::Random => {
+                if self.len() == 0 {
+                    return None;
+                }
+                let mut i = self.rng.gen_range(0..self.len());
+                let mut queue = ERASE;
+                while i >= self.queues[queue].len() {
+                    i -= self.queues[queue].len();
+                    queue += 1;
+                }
+                (queue, i, true)
+            }
+            Strategy::Outermost | Strategy::Fair => unreachable!(),
+        };
+        let entry = if swap {
+            self.queues[queue].swap_remove_back(index)
+        } else {
+            self.queues[queue].remove(index)
+        };
+        Some(Popped {
+            queue,
+            index,
+            swap,
+            entry: entry?,
+        })
+    }
+
+    /// Undoes `popped`, which must be the last pop.
+    pub(crate) fn unpop(&mut self, popped: Popped) {
+        let queue = &mut self.queues[popped.queue];
+        if popped.swap && popped.index < queue.len() {
+            let moved = std::mem::replace(&mut queue[popped.index], popped.entry);
+            queue.push_back(moved);
+        } else {
+            queue.insert(popped.index, popped.entry);
+        }
+    }
+
+    /// Returns the pairs of all queues, including the ones that stopped being active.
+    pub(crate) fn pairs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.queues.iter().flatten().map(|&(_, a, b)| (a, b))
+    }
+
+    /// Removes the pairs for which `stale` holds.
+    pub(crate) fn remove(&mut self, stale: impl Fn(usize, usize) -> bool) {
+        for queue in &mut self.queues {
+            queue.retain(|&(_, a, b)| !stale(a, b));
+        }
+    }
+
+    /// Returns the queues, to restore them later with [`Worklist::restore`].
+    pub(crate) fn save(&self) -> Queues {
+        self.queues.clone()
+    }
+
+    pub(crate) fn restore(&mut self, queues: Queues) {
+        self.queues = queues;
+    }
+
+    /// Returns the turns of [`Strategy::Fair`], to restore them later with
+    /// [`Worklist::restore_turns`].
+    pub(crate) fn save_turns(&self) -> Turns {
+        (self.served.clone(), self.turns)
+    }
+
+    pub(crate) fn restore_turns(&mut self, (served, turns): Turns) {
+        self.served = served;
+        self.turns = turns;
+    }
+
+    /// Returns the first of the `pairs` in the component served least recently, and marks its
+    /// component as served.
+    ///
+    /// The `ports` give the ports of each agent and free port.
+    pub(crate) fn fair<'a>(
+        &mut self,
+        pairs: Vec<(usize, usize)>,
+        nodes: impl Iterator<Item = usize>,
+        ports: impl Fn(usize) -> Ports<'a>,
+    ) -> Option<(usize, usize)> {
+        let components = components(nodes, &ports);
+        self.served.retain(|a, _| components.contains_key(a));
+        let mut served = HashMap::new();
+        for (&a, &turn) in &self.served {
+            let last = served.entry(components[&a]).or_insert(turn);
+            *last = turn.max(*last);
+        }
+        // Components never served come first, then by their smallest node.
+        let (a, b) = pairs.into_iter().min_by_key(|&(a, _)| {
+            let component = components[&a];
+            (served.get(&component).copied(), component)
+        })?;
+        for x in [a, b] {
+            for y in ports(x).skip(1).flatten() {
+                self.served.insert(y.agent, self.turns);
+            }
+        }
+        self.turns += 1;
+        Some((a, b))
+    }
+
+    /// Removes all pairs.
+    pub(crate) fn drain(&mut self) -> Vec<(usize, usize)> {
+        let queues = self.queues.iter_mut().flat_map(|q| q.drain(..));
+        queues.map(|(_, a, b)| (a, b)).collect()
+    }
+}
+
+/// A pair removed from a [`Worklist`], to undo the removal.
+#[derive(Clone, Debug)]
+pub(crate) struct Popped {
+    queue: usize,
+    index: usize,
+    /// Whether the back of the queue replaced the pair.
+    swap: bool,
+    entry: (usize, usize, usize),
+}
+
+impl Popped {
+    pub(crate) fn pair(&self) -> (usize, usize) {
+        (self.entry.1, self.entry.2)
+    }
+}
+
+impl Net {
+    /// Seeds the random generator of [`Strategy::Random`].
+    ///
+    /// Reductions are otherwise deterministic: two nets built and reduced with the same calls
+    /// reduce the same active pairs in the same order.
+    pub fn seed(&mut self, seed: u64) {
+        self.worklist.seed(seed);
+    }
+
+    /// Returns the worklist queue of the active pair `a`-`b`.
+    pub(crate) fn queue(&self, a: usize, b: usize) -> usize {
+        queue(&self.rules, self.agents[&a].symbol, self.agents[&b].symbol)
+    }
+
+    /// Returns the number of entries of the worklist, which includes active pairs and pairs that
+    /// stopped being active but were not skipped yet.
+    ///
+    /// This measures the memory used to select active pairs, which stays proportional to the
+    /// number of active pairs.
+    pub fn queued(&self) -> usize {
+        self.worklist.len()
+    }
+
+    /// Removes the pairs that stopped being active from the worklist.
+    ///
+    /// Strategies that scan the net never pop the worklist, which would otherwise grow with each
+    /// rewrite.
+    fn compact(&mut self) {
+        let stale: HashSet<_> = (self.worklist.pairs())
+            .filter(|&(a, b)| self.partner(a) != Some(b))
+            .collect();
+        if stale.is_empty() {
+            return;
+        }
+        if self.journaling() {
+            self.log(Change::Drain(self.worklist.save()));
+        }
+        self.worklist.remove(|a, b| stale.contains(&(a, b)));
+    }
+
+    /// Selects the next active pair to reduce according to `strategy`.
+    pub(crate) fn select(&mut self, strategy: Strategy) -> Option<(usize, usize)> {
+        if matches!(strategy, Strategy::Outermost | Strategy::Fair) {
+            self.compact();
+        }
+        if strategy == Strategy::Outermost {
+            let ports = |a| match self.agents.get(&a) {
+                None => Ports::new(std::slice::from_ref(&self.free[&a])),
+                Some(agent) => agent.ports(),
+            };
+            let depths = depths(self.free.keys().copied(), ports);
+            let depth = |a: usize| depths.get(&a).copied().unwrap_or(usize::MAX);
+            let pairs = self.active_pairs().into_iter();
+            return pairs.min_by_key(|&(a, b)| depth(a).min(depth(b)));
+        }
+        if strategy == Strategy::Fair {
+            if self.journaling() {
+                self.log(Change::Turns(self.worklist.save_turns()));
+            }
+            let pairs = self.active_pairs();
+            let nodes = self.agents.keys().chain(self.free.keys()).copied();
+            let ports = |a| match self.agents.get(&a) {
+                None => Ports::new(std::slice::from_ref(&self.free[&a])),
+                Some(agent) => agent.ports(),
+            };
+            return self.worklist.fair(pairs, nodes, ports);
+        }
+        loop {
+            let popped = self.worklist.pop(strategy)?;
+            let (a, b) = popped.pair();
+            self.log(Change::Pop(popped));
+            if self.partner(a) == Some(b) {
+                return Some((a, b));
+            }
+        }
+    }
+}
+
+/// Returns the smallest node of the connected component of each of the `nodes`.
+///
+/// The net is given by the `ports` of each agent and free port.
+pub(crate) fn components<'a>(
+    nodes: impl Iterator<Item = usize>,
+    ports: impl Fn(usize) -> Ports<'a>,
+) -> HashMap<usize, usize> {
+    let mut nodes: Vec<_> = nodes.collect();
+    nodes.sort_unstable();
+    let mut components = HashMap::new();
+    let mut todo = Vec::new();
+    for root in nodes {
+        if components.contains_key(&root) {
+            continue;
+        }
+        components.insert(root, root);
+        todo.push(root);
+        while let Some(a) = todo.pop() {
+            for Port { agent: b, .. } in ports(a).flatten() {
+                if let Entry::Vacant(e) = components.entry(b) {
+                    e.insert(root);
+                    todo.push(b);
+                }
+            }
+        }
+    }
+    components
+}
+
+/// Returns the worklist queue of an active pair between `x` and `y`.
+pub(crate) fn queue(rules: &RuleSet, x: Symbol, y: Symbol) -> usize {
+    if rules.arity(x) == 0 || rules.arity(y) == 0 {
+        ERASE
+    } else if x == y {
+        ANNIHILATE
+    } else {
+        COMMUTE
+    }
+}
+
+/// Returns the distance of each agent connected to one of the `free` ports.
+///
+/// The net is given by the `ports` of each agent and free port.
+pub(crate) fn depths<'a>(
+    free: impl Iterator<Item = usize>,
+    ports: impl Fn(usize) -> Ports<'a>,
+) -> HashMap<usize, usize> {
+    let mut depths = HashMap::new();
+    let mut todo = VecDeque::new();
+    for a in free {
+        depths.insert(a, 0);
+        todo.push_back(a);
+    }
+    while let Some(a) = todo.pop_front() {
+        let depth = depths[&a] + 1;
+        for Port { agent: b, .. } in ports(a).flatten() {
+            if let Entry::Vacant(e) = depths.entry(b) {
+                e.insert(depth);
+                todo.push_back(b);
+            }
+        }
+    }
+    depths
+}
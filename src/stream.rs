@@ -0,0 +1,130 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Net, Port, ReductionStats, Slot, Strategy, Symbol};
+use std::collections::HashSet;
+
+impl Net {
+    /// Same as [`Net::reduce`] but erases subnets in bulk.
+    ///
+    /// When an eraser meets an agent, the erasers created by the rule are only virtual: they
+    /// follow the erase front, deleting the agents they meet on their principal port, and are
+    /// only created where they stop, on auxiliary and free ports. Erasing a huge subnet thus
+    /// neither creates erasers nor queues active pairs, which keeps memory flat. The result and
+    /// the statistics are the same as with [`Net::reduce`], counting each virtual rewrite as a
+    /// step.
+    ///
+    /// Only rules erasing like those of the combinators run in bulk: the eraser has no auxiliary
+    /// ports, the rule creates an eraser on each auxiliary port of the other agent, and two
+    /// erasers annihilate. A cascade is a single step of the journal and is not traced.
+    pub fn reduce_streaming(&mut self, limit: Option<usize>) -> ReductionStats {
+        let mut stats = ReductionStats::new(self.agents.len());
+        while limit.is_none_or(|limit| stats.steps < limit) {
+            self.begin_step();
+            let pair = self.select(Strategy::PreferErase);
+            if let Some((a, b)) = pair {
+                let (x, y) = (self.agents[&a].symbol, self.agents[&b].symbol);
+                if self.erases(x, y) {
+                    self.cascade(a, b, limit, &mut stats);
+                } else if self.erases(y, x) {
+                    self.cascade(b, a, limit, &mut stats);
+                } else {
+                    self.interact(a, b, &mut ());
+                    stats.record(x, y, self.agents.len(), &self.cost);
+                }
+            }
+            self.end_step(pair.is_some());
+            if pair.is_none() {
+                break;
+            }
+        }
+        stats
+    }
+
+    /// Returns whether an `e` facing an `x` creates an `e` on each auxiliary port of `x`.
+    ///
+    /// Two `e` must also annihilate, since virtual erasers may meet.
+    fn erases(&self, e: Symbol, x: Symbol) -> bool {
+        let rules = &self.rules;
+        if rules.arity(e) != 0 || rules.rule(e, e).is_none_or(|r| !r.agents.is_empty()) {
+            return false;
+        }
+        let rule = match rules.rule(e, x) {
+            Some(rule) => rule,
+            None => return false,
+        };
+        let arity = rules.arity(x);
+        if rule.agents.len() != arity || rule.agents.iter().any(|&s| s != e) {
+            return false;
+        }
+        let mut ports = Vec::new();
+        let mut agents = Vec::new();
+        for &wire in &rule.wires {
+            match wire {
+                (Slot::Right(p), Slot::Agent(i, 0)) | (Slot::Agent(i, 0), Slot::Right(p)) => {
+                    ports.push(p);
+                    agents.push(i + 1);
+                }
+                _ => return false,
+            }
+        }
+        ports.sort_unstable();
+        agents.sort_unstable();
+        ports.iter().copied().eq(1..=arity) && agents.iter().copied().eq(1..=arity)
+    }
+
+    /// Erases agent `x` facing eraser `e`, and the agents the erase front meets.
+    ///
+    /// Stops when `stats` reach `limit`, creating the erasers of the front.
+    fn cascade(&mut self, e: usize, x: usize, limit: Option<usize>, stats: &mut ReductionStats) {
+        let eraser = self.agents[&e].symbol;
+        self.delete(e);
+        // Agents whose principal port faces a virtual eraser.
+        let mut front = vec![x];
+        // Other ports facing a virtual eraser.
+        let mut erasers = HashSet::new();
+        while let Some(y) = front.pop() {
+            if limit.is_some_and(|limit| stats.steps >= limit) {
+                front.push(y);
+                break;
+            }
+            let symbol = self.agents[&y].symbol;
+            for p in 1..=self.rules.arity(symbol) {
+                let q = self.get_port_unchecked(Port::new(y, p));
+                if erasers.remove(&Port::new(y, p)) || (q.agent == y && p < q.port) {
+                    // The erasers on both ends of the wire annihilate.
+                    stats.record(eraser, eraser, self.agents.len(), &self.cost);
+                } else if q.agent == y {
+                    continue;
+                } else if q.port == 0
+                    && self.symbol(q.agent).is_some_and(|z| self.erases(eraser, z))
+                {
+                    front.push(q.agent);
+                } else {
+                    erasers.insert(q);
+                }
+            }
+            self.delete(y);
+            stats.record(eraser, symbol, self.agents.len(), &self.cost);
+        }
+        let front = front.into_iter().map(|y| Port::new(y, 0));
+        let mut ports: Vec<_> = front.chain(erasers).collect();
+        ports.sort_unstable();
+        for q in ports {
+            let c = self.create(eraser);
+            self.connect_observed(Port::new(c, 0), q, &mut ());
+        }
+        stats.peak_agents = stats.peak_agents.max(self.agents.len());
+    }
+}
@@ -0,0 +1,409 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Random nets and properties for property-based testing.
+//!
+//! Nets are generated with [proptest] strategies. Properties return a description of the first
+//! violation they find, so that they can be checked with `prop_assert!` or any other harness.
+//!
+//! This module is only available with the `testing` feature.
+
+use crate::{Engine, Malformed, Net, Port, RuleSet, Strategy, Symbol};
+use proptest::prelude::{prop, Just};
+use proptest::strategy::Strategy as Generator;
+
+/// Returns random well-formed nets of interaction combinators.
+///
+/// See [`arb_net_with`].
+pub fn arb_net(max_agents: usize, max_free: usize) -> impl Generator<Value = Net> {
+    arb_net_with(RuleSet::combinators(), max_agents, max_free)
+}
+
+/// Returns random well-formed nets for the interaction system `rules`.
+///
+/// Nets have up to `max_agents` agents and up to `max_free` free ports, plus one if needed to
+/// connect all ports. Wires are chosen uniformly among the ways to connect all ports, except
+/// that nets with an agent connected to itself are rejected. The rule set must have symbols.
+pub fn arb_net_with(
+    rules: RuleSet,
+    max_agents: usize,
+    max_free: usize,
+) -> impl Generator<Value = Net> {
+    let symbols = prop::sample::select(rules.symbols().collect::<Vec<_>>());
+    let agents = prop::collection::vec(symbols, 0..=max_agents);
+    let ports = {
+        let rules = rules.clone();
+        move |agents: &[Symbol], free: usize| {
+            free + agents.iter().map(|&s| rules.arity(s) + 1).sum::<usize>()
+        }
+    };
+    (agents, 0..=max_free)
+        .prop_flat_map(move |(agents, mut free)| {
+            free += ports(&agents, free) % 2;
+            let order: Vec<usize> = (0..ports(&agents, free)).collect();
+            (Just(agents), Just(free), Just(order).prop_shuffle())
+        })
+        .prop_map(move |(agents, free, order)| {
+            let mut net = Net::with_rules(rules.clone());
+            let mut ports = Vec::new();
+            for symbol in agents {
+                let a = net.add_agent_unchecked(symbol);
+                ports.extend((0..=net.rules.arity(symbol)).map(|p| Port::new(a, p)));
+            }
+            for _ in 0..free {
+                ports.push(Port::new(net.add_free(), 0));
+            }
+            for wire in order.chunks(2) {
+                net.connect_unchecked(ports[wire[0]], ports[wire[1]]);
+            }
+            net
+        })
+        .prop_filter("agent connected to itself", |net| net.validate().is_ok())
+}
+
+/// Returns all the ports of the net, including free ports.
+fn ports(net: &Net) -> Vec<Port> {
+    let mut ports = Vec::new();
+    for (a, _, ports_of_a) in net.nodes() {
+        ports.extend((0..ports_of_a.len()).map(|p| Port::new(a, p)));
+    }
+    ports.sort_unstable();
+    ports
+}
+
+/// Checks that each port is connected back by the port it is connected to.
+pub fn connect_involutive(net: &Net) -> Result<(), String> {
+    for x in ports(net) {
+        let y = net.get_port(x).map_err(|e| e.to_string())?;
+        let z = net.get_port(y).map_err(|e| e.to_string())?;
+        if z != x {
+            return Err(format!(
+                "port {}.{} is connected to {}.{} which is connected to {}.{}",
+                x.agent, x.port, y.agent, y.port, z.agent, z.port
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Reduces the first active pair and checks that the numbers of agents and ports change as the
+/// rule says, and that the net stays well-formed.
+///
+/// Agents connected to themselves are allowed after reduction since rules may create them.
+/// Returns whether there was an active pair.
+pub fn rule_arithmetic(net: &mut Net) -> Result<bool, String> {
+    let (a, b) = match net.active_pairs().first() {
+        None => return Ok(false),
+        Some(&pair) => pair,
+    };
+    let symbols = (net.symbol(a).unwrap(), net.symbol(b).unwrap());
+    let rule = net.rules.rule(symbols.0, symbols.1).unwrap();
+    let created: usize = rule.agents.iter().map(|&s| net.rules.arity(s) + 1).sum();
+    let deleted = net.rules.arity(symbols.0) + net.rules.arity(symbols.1) + 2;
+    let count = rule.agents.len();
+    let agents = net.agents.len() + count - 2;
+    let ports = ports(net).len() + created - deleted;
+    let new = net.reduce_pair(a, b).unwrap();
+    if new.len() != count || net.agents.len() != agents {
+        return Err(format!(
+            "reducing {}-{} created {} agents and left {} instead of {} and {}",
+            a,
+            b,
+            new.len(),
+            net.agents.len(),
+            count,
+            agents
+        ));
+    }
+    if self::ports(net).len() != ports {
+        let actual = self::ports(net).len();
+        return Err(format!(
+            "reducing {}-{} left {} ports instead of {}",
+            a, b, actual, ports
+        ));
+    }
+    let errors = net.validate().err().unwrap_or_default();
+    let errors = errors
+        .into_iter()
+        .filter(|e| !matches!(e, Malformed::SelfConnected(_)));
+    if let Some(error) = errors.into_iter().next() {
+        return Err(format!(
+            "reducing {}-{} made the net malformed: {}",
+            a, b, error
+        ));
+    }
+    connect_involutive(net)?;
+    Ok(true)
+}
+
+/// Checks that reducing with different strategies reaches isomorphic normal forms.
+///
+/// Interaction nets are strongly confluent: if one strategy reaches a normal form in some number
+/// of steps, all strategies reach it in the same number of steps. Nets with no normal form
+/// within `limit` steps pass.
+pub fn confluent(net: &Net, limit: usize) -> Result<(), String> {
+    let strategies = [
+        Strategy::PreferErase,
+        Strategy::Fifo,
+        Strategy::Lifo,
+        Strategy::Random,
+        Strategy::Outermost,
+        Strategy::PreferAnnihilate,
+        Strategy::Fair,
+    ];
+    let mut normal: Option<(Strategy, Net, usize)> = None;
+    for strategy in strategies {
+        let mut net = net.clone();
+        net.seed(0);
+        let steps = net.reduce_with(strategy, Some(limit + 1)).steps;
+        let (first, other, count) = match &normal {
+            None => {
+                normal = Some((strategy, net, steps));
+                continue;
+            }
+            Some(x) => x,
+        };
+        if steps != *count && (steps <= limit || *count <= limit) {
+            return Err(format!(
+                "{:?} took {} steps but {:?} took {}",
+                strategy, steps, first, count
+            ));
+        }
+        if steps <= limit && !net.isomorphic(other) {
+            let diff = other.diff(&net);
+            let diff = diff.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+            return Err(format!(
+                "{:?} and {:?} reached different normal forms: {}",
+                first,
+                strategy,
+                diff.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Node of the [`Oracle`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Kind {
+    Free,
+    Agent(Symbol),
+    /// Two ports connected through, removed after each rewrite.
+    Relay,
+}
+
+/// Reference reducer of interaction combinators, deliberately simple and slow.
+///
+/// Nodes are never reused, active pairs are found by scanning all the nodes, and the rules are
+/// written out by hand instead of read from [`RuleSet::combinators`]. The auxiliary ports of an
+/// active pair are first replaced by relays, such that wires between them need no special case.
+struct Oracle {
+    nodes: Vec<Option<(Kind, Vec<Port>)>>,
+}
+
+impl Oracle {
+    fn add(&mut self, kind: Kind, ports: usize) -> usize {
+        let unconnected = Port::new(usize::MAX, 0);
+        self.nodes.push(Some((kind, vec![unconnected; ports])));
+        self.nodes.len() - 1
+    }
+
+    fn agent(&mut self, symbol: Symbol) -> usize {
+        let ports = if symbol == Symbol::ERASE { 1 } else { 3 };
+        self.add(Kind::Agent(symbol), ports)
+    }
+
+    fn kind(&self, a: usize) -> Option<Kind> {
+        self.nodes[a].as_ref().map(|x| x.0)
+    }
+
+    fn peer(&self, x: Port) -> Port {
+        self.nodes[x.agent].as_ref().unwrap().1[x.port]
+    }
+
+    fn link(&mut self, x: Port, y: Port) {
+        self.nodes[x.agent].as_mut().unwrap().1[x.port] = y;
+        self.nodes[y.agent].as_mut().unwrap().1[y.port] = x;
+    }
+
+    fn active_pair(&self) -> Option<(usize, usize)> {
+        (0..self.nodes.len()).find_map(|a| {
+            let b = match self.kind(a)? {
+                Kind::Agent(_) => self.peer(Port::new(a, 0)),
+                _ => return None,
+            };
+            match self.kind(b.agent)? {
+                Kind::Agent(_) if b.port == 0 => Some((a, b.agent)),
+                _ => None,
+            }
+        })
+    }
+
+    /// Replaces the auxiliary ports of `a` by relays and returns their free end.
+    fn relays(&mut self, a: usize) -> Vec<Port> {
+        let mut ends = Vec::new();
+        for p in 1..self.nodes[a].as_ref().unwrap().1.len() {
+            let w = self.add(Kind::Relay, 2);
+            self.link(Port::new(w, 0), self.peer(Port::new(a, p)));
+            ends.push(Port::new(w, 1));
+        }
+        ends
+    }
+
+    fn rewrite(&mut self, a: usize, b: usize) {
+        let (c, d, e) = (Symbol::CONSTRUCT, Symbol::DUPLICATE, Symbol::ERASE);
+        let symbol = |this: &Oracle, x| match this.kind(x) {
+            Some(Kind::Agent(symbol)) => symbol,
+            _ => unreachable!(),
+        };
+        let (x, y) = (symbol(self, a), symbol(self, b));
+        let (l, r) = (self.relays(a), self.relays(b));
+        self.nodes[a] = None;
+        self.nodes[b] = None;
+        match (x, y) {
+            // Constructors annihilate crosswise and duplicators in order.
+            _ if x == c && y == c => {
+                self.link(l[0], r[1]);
+                self.link(l[1], r[0]);
+            }
+            _ if x == d && y == d => {
+                self.link(l[0], r[0]);
+                self.link(l[1], r[1]);
+            }
+            _ if x == e || y == e => {
+                for end in l.into_iter().chain(r) {
+                    let eraser = self.agent(e);
+                    self.link(Port::new(eraser, 0), end);
+                }
+            }
+            _ => {
+                // The constructor gets duplicators on its auxiliary ports, and the duplicator
+                // gets constructors.
+                let (l, r) = if x == c { (l, r) } else { (r, l) };
+                let ds = [self.agent(d), self.agent(d)];
+                let cs = [self.agent(c), self.agent(c)];
+                for i in 0..2 {
+                    self.link(l[i], Port::new(ds[i], 0));
+                    self.link(r[i], Port::new(cs[i], 0));
+                    for (j, &constructor) in cs.iter().enumerate() {
+                        self.link(Port::new(ds[i], j + 1), Port::new(constructor, i + 1));
+                    }
+                }
+            }
+        }
+        while let Some(w) = (0..self.nodes.len()).find(|&w| self.kind(w) == Some(Kind::Relay)) {
+            let (x, y) = (self.peer(Port::new(w, 0)), self.peer(Port::new(w, 1)));
+            self.nodes[w] = None;
+            // A relay connected to itself is a loop, which disappears.
+            if x != Port::new(w, 1) {
+                self.link(x, y);
+            }
+        }
+    }
+}
+
+/// Reduces a well-formed net of interaction combinators with a reference reducer.
+///
+/// The reducer shares no code with [`Net`] besides the conversions, to check it with
+/// [`matches_oracle`]. Returns the normal form and the number of steps, or `None` if there is no
+/// normal form within `limit` steps.
+pub fn oracle(net: &Net, limit: usize) -> Option<(Net, usize)> {
+    assert_eq!(*net.rules, RuleSet::combinators());
+    let mut ids: Vec<_> = net.nodes().map(|(a, _, _)| a).collect();
+    ids.sort_unstable();
+    let mut oracle = Oracle { nodes: Vec::new() };
+    for &a in &ids {
+        let (symbol, ports) = net.node(a).unwrap();
+        match symbol {
+            None => oracle.add(Kind::Free, 1),
+            Some(symbol) => oracle.add(Kind::Agent(symbol), ports.len()),
+        };
+    }
+    for (i, &a) in ids.iter().enumerate() {
+        let (_, ports) = net.node(a).unwrap();
+        for (p, y) in ports.enumerate() {
+            let j = ids.binary_search(&y.unwrap().agent).unwrap();
+            oracle.link(Port::new(i, p), Port::new(j, y.unwrap().port));
+        }
+    }
+    let mut steps = 0;
+    while let Some((a, b)) = oracle.active_pair() {
+        if steps == limit {
+            return None;
+        }
+        oracle.rewrite(a, b);
+        steps += 1;
+    }
+    let mut normal = Net::empty();
+    let mut ids = Vec::new();
+    for node in &oracle.nodes {
+        ids.push(match node {
+            None => usize::MAX,
+            Some((Kind::Agent(symbol), _)) => normal.add_agent_unchecked(*symbol),
+            Some(_) => normal.add_free(),
+        });
+    }
+    for (a, node) in oracle.nodes.iter().enumerate() {
+        for (p, &y) in node.iter().flat_map(|x| x.1.iter().enumerate()) {
+            if Port::new(a, p) < y {
+                let (x, y) = (Port::new(ids[a], p), Port::new(ids[y.agent], y.port));
+                normal.connect_unchecked(x, y);
+            }
+        }
+    }
+    Some((normal, steps))
+}
+
+/// Checks that the engines reach the same normal form as the [`oracle`] in the same number of
+/// steps.
+///
+/// Nets with no normal form within `limit` steps pass.
+pub fn matches_oracle(net: &Net, limit: usize) -> Result<(), String> {
+    let expected = oracle(net, limit);
+    #[allow(unused_mut)]
+    let mut engines = vec![Engine::Sequential];
+    #[cfg(feature = "atomic")]
+    engines.push(Engine::Atomic);
+    for engine in engines {
+        let mut net = net.clone();
+        net.set_engine(engine);
+        let steps = net.reduce(Some(limit + 1)).steps;
+        let (normal, count) = match &expected {
+            None if steps <= limit => {
+                return Err(format!(
+                    "{:?} took {} steps but the oracle took more than {}",
+                    engine, steps, limit
+                ))
+            }
+            None => continue,
+            Some((normal, count)) => (normal, *count),
+        };
+        if steps != count {
+            return Err(format!(
+                "{:?} took {} steps but the oracle took {}",
+                engine, steps, count
+            ));
+        }
+        if !net.isomorphic(normal) {
+            let diff = normal.diff(&net);
+            let diff = diff.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+            return Err(format!(
+                "{:?} and the oracle reached different normal forms: {}",
+                engine,
+                diff.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,164 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Colors and sizes of the viewer.
+
+use lafont::Symbol;
+
+/// Red, green, and blue components between 0 and 1.
+pub type Color = [f32; 3];
+
+/// How the agents of a symbol are drawn.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct Style {
+    pub color: Color,
+    pub radius: f32,
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style {
+            color: [1., 1., 1.],
+            radius: 1.,
+        }
+    }
+}
+
+/// Colors and sizes of the viewer.
+///
+/// Missing fields of a theme file take their value from the default (dark) theme.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct Theme {
+    pub background: Color,
+    pub construct: Style,
+    pub duplicate: Style,
+    pub erase: Style,
+    /// Agents of other symbols.
+    pub other: Style,
+    /// Wires at principal ports.
+    pub principal: Color,
+    /// Wires of active pairs, which pulse between this color and the principal one.
+    pub active: Color,
+    /// Wires at auxiliary ports, cycling through the colors by port index.
+    pub auxiliary: Vec<Color>,
+    /// Agent picked with the mouse.
+    pub selected: Color,
+    /// Neighbors of the agent picked with the mouse.
+    pub neighbor: Color,
+    /// Free ports and their labels.
+    pub interface: Color,
+    /// Super-nodes of collapsed agents.
+    pub group: Color,
+    /// Agents just rewritten, in the heatmap.
+    pub hot: Color,
+    pub text: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        let style = |color| Style { color, radius: 1. };
+        Theme {
+            background: [0., 0., 0.],
+            construct: style([0., 0., 1.]),
+            duplicate: style([0., 1., 0.]),
+            erase: style([1., 0., 0.]),
+            other: style([1., 0., 0.]),
+            principal: [1., 1., 1.],
+            active: [1., 0., 0.5],
+            auxiliary: vec![[1., 1., 0.], [0., 1., 1.], [1., 0., 1.]],
+            selected: [1., 1., 1.],
+            neighbor: [1., 1., 0.],
+            interface: [1., 0.5, 0.],
+            group: [0.5, 0.5, 0.5],
+            hot: [1., 0.5, 0.],
+            text: [1., 1., 1.],
+        }
+    }
+}
+
+impl Theme {
+    /// Dark agents and wires on a white background.
+    pub fn light() -> Theme {
+        let style = |color| Style { color, radius: 1. };
+        Theme {
+            background: [1., 1., 1.],
+            construct: style([0., 0., 0.6]),
+            duplicate: style([0., 0.5, 0.]),
+            erase: style([0.7, 0., 0.]),
+            other: style([0.7, 0., 0.]),
+            principal: [0., 0., 0.],
+            active: [0.9, 0., 0.4],
+            auxiliary: vec![[0.6, 0.4, 0.], [0., 0.5, 0.5], [0.5, 0., 0.5]],
+            selected: [0., 0., 0.],
+            neighbor: [1., 0.5, 0.],
+            interface: [0.8, 0.3, 0.],
+            group: [0.6, 0.6, 0.6],
+            hot: [1., 0., 0.],
+            text: [0., 0., 0.],
+        }
+    }
+
+    /// Returns the theme `name`: `dark`, `light`, or the path of a TOML file.
+    ///
+    /// A theme file has the fields of [`Theme`], for example:
+    ///
+    /// ```toml
+    /// background = [0.1, 0.1, 0.1]
+    /// auxiliary = [[1.0, 0.5, 0.0]]
+    /// erase = { color = [1.0, 0.0, 0.0], radius = 0.5 }
+    /// ```
+    pub fn load(name: &str) -> Result<Theme, String> {
+        match name {
+            "dark" => Ok(Theme::default()),
+            "light" => Ok(Theme::light()),
+            #[cfg(feature = "serde")]
+            path => {
+                let content =
+                    std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+                toml::from_str(&content).map_err(|e| format!("{}: {}", path, e))
+            }
+            #[cfg(not(feature = "serde"))]
+            path => Err(format!("{}: theme files require the serde feature", path)),
+        }
+    }
+
+    /// Returns how to draw the agents of `symbol`.
+    pub fn style(&self, symbol: Symbol) -> &Style {
+        match symbol {
+            Symbol::CONSTRUCT => &self.construct,
+            Symbol::DUPLICATE => &self.duplicate,
+            Symbol::ERASE => &self.erase,
+            _ => &self.other,
+        }
+    }
+
+    /// Returns the color of a wire at port index `port`.
+    pub fn wire(&self, port: usize) -> Color {
+        match port {
+            0 => self.principal,
+            _ if self.auxiliary.is_empty() => self.principal,
+            _ => self.auxiliary[(port - 1) % self.auxiliary.len()],
+        }
+    }
+}
@@ -0,0 +1,122 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Traces of reductions.
+
+use crate::{Net, Symbol};
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex};
+
+/// Where rewrites are traced, shared by clones of the net.
+#[derive(Clone)]
+pub(crate) struct Tracer(Arc<Mutex<dyn Write + Send>>);
+
+impl fmt::Debug for Tracer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Tracer")
+    }
+}
+
+impl Net {
+    /// Writes a line to `w` for each following rewrite.
+    ///
+    /// A line has the form `LEFT-RIGHT A B -> C...` where `LEFT` and `RIGHT` are the names of
+    /// the symbols of the active pair `A`-`B`, and `C...` are the ids of the created agents in
+    /// the order of the rule, separated by spaces. For example:
+    ///
+    /// ```text
+    /// construct-duplicate 4 7 -> 10 11 12 13
+    /// construct-erase 12 2 -> 14 15
+    /// erase-erase 14 8 ->
+    /// ```
+    ///
+    /// Clones of the net write to the same tracer. Tracing stops at the first write error, and
+    /// undone steps are not traced.
+    pub fn set_tracer(&mut self, w: impl Write + Send + 'static) {
+        self.tracer = Some(Tracer(Arc::new(Mutex::new(w))));
+    }
+
+    /// Stops tracing rewrites.
+    pub fn clear_tracer(&mut self) {
+        self.tracer = None;
+    }
+
+    pub(crate) fn trace(&mut self, rule: (Symbol, Symbol), a: usize, b: usize, agents: &[usize]) {
+        let tracer = match &self.tracer {
+            None => return,
+            Some(tracer) => tracer,
+        };
+        let mut line = format!(
+            "{}-{} {} {} ->",
+            self.rules.name(rule.0),
+            self.rules.name(rule.1),
+            a,
+            b
+        );
+        for c in agents {
+            line.push_str(&format!(" {}", c));
+        }
+        let mut w = tracer.0.lock().unwrap();
+        if writeln!(w, "{}", line).is_err() {
+            drop(w);
+            self.tracer = None;
+        }
+    }
+
+    /// Reduces the active pairs of a trace written by [`Net::set_tracer`] and returns how many.
+    ///
+    /// The net must be in the state it was when tracing started. Fails at the first line that
+    /// does not match the net: an invalid line, a pair that is not active, different symbols,
+    /// or different created agents.
+    pub fn replay(&mut self, reader: impl BufRead) -> Result<usize, String> {
+        let mut count = 0;
+        for (i, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| e.to_string())?;
+            let error = |message: &str| format!("line {}: {}", i + 1, message);
+            let (pair, created) = line.split_once(" ->").ok_or_else(|| error("expected ->"))?;
+            let mut pair = pair.split(' ');
+            let (rule, a, b) = match (pair.next(), pair.next(), pair.next(), pair.next()) {
+                (Some(rule), Some(a), Some(b), None) => (rule, a, b),
+                _ => return Err(error("expected LEFT-RIGHT A B -> C...")),
+            };
+            let id = |x: &str| x.parse::<usize>().map_err(|_| error("invalid id"));
+            let (a, b) = (id(a)?, id(b)?);
+            let created = created
+                .split_whitespace()
+                .map(id)
+                .collect::<Result<Vec<_>, _>>()?;
+            let symbols = match (self.symbol(a), self.symbol(b)) {
+                (Some(x), Some(y)) => (x, y),
+                _ => return Err(error("missing agent")),
+            };
+            let name = format!(
+                "{}-{}",
+                self.rules.name(symbols.0),
+                self.rules.name(symbols.1)
+            );
+            if name != rule {
+                return Err(error(&format!("expected {} rule", name)));
+            }
+            if self.partner(a.min(b)) != Some(a.max(b)) {
+                return Err(error("not an active pair"));
+            }
+            if self.reduce_pair(a, b).unwrap() != created {
+                return Err(error("different created agents"));
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+}
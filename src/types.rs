@@ -0,0 +1,210 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Types of ports, following Lafont's interaction nets.
+//!
+//! A type is an atom with a polarity, an output `A+` or an input `A-`. A [`Signature`] gives the
+//! types of the ports of each symbol, and a wire is well typed when it connects an output to an
+//! input of the same atom. Free ports take the type dual to the port they are connected to.
+//!
+//! When the rules of an interaction system are well typed, see [`Signature::check`], reduction
+//! preserves typing.
+
+use crate::{Net, Port, RuleSet, Slot, Symbol};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// An atom with a polarity.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct Type {
+    pub atom: String,
+    pub output: bool,
+}
+
+impl Type {
+    pub fn output(atom: &str) -> Type {
+        let atom = atom.to_string();
+        Type { atom, output: true }
+    }
+
+    pub fn input(atom: &str) -> Type {
+        let atom = atom.to_string();
+        Type {
+            atom,
+            output: false,
+        }
+    }
+
+    /// Returns the type of the ports that can be connected to ports of this type.
+    pub fn dual(&self) -> Type {
+        Type {
+            atom: self.atom.clone(),
+            output: !self.output,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let polarity = if self.output { '+' } else { '-' };
+        write!(f, "{}{}", self.atom, polarity)
+    }
+}
+
+impl FromStr for Type {
+    type Err = String;
+
+    /// Parses `A+` as an output and `A-` as an input.
+    fn from_str(input: &str) -> Result<Type, String> {
+        match input.char_indices().last() {
+            Some((i, '+')) if i > 0 => Ok(Type::output(&input[..i])),
+            Some((i, '-')) if i > 0 => Ok(Type::input(&input[..i])),
+            _ => Err(format!("invalid type {:?}", input)),
+        }
+    }
+}
+
+/// Types of the ports of symbols.
+#[derive(Clone, Debug, Default)]
+pub struct Signature {
+    types: HashMap<Symbol, Vec<Type>>,
+}
+
+/// A reason for a net or a rule to be ill-typed.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum TypeError {
+    /// The symbol has no types in the signature, or not one per port.
+    Untyped(Symbol),
+    /// The wire between the ports connects the types which are not dual.
+    Wire(Port, Port, Type, Type),
+    /// The rule for the active pair connects the slots whose types are not dual.
+    Rule(Symbol, Symbol, Slot, Slot, Type, Type),
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeError::Untyped(s) => write!(f, "symbol {} is not typed", s.0),
+            TypeError::Wire(x, y, s, t) => write!(
+                f,
+                "port {}.{} of type {} is connected to port {}.{} of type {}",
+                x.agent, x.port, s, y.agent, y.port, t
+            ),
+            TypeError::Rule(l, r, x, y, s, t) => write!(
+                f,
+                "rule {}-{} connects {:?} of type {} to {:?} of type {}",
+                l.0, r.0, x, s, y, t
+            ),
+        }
+    }
+}
+
+impl Signature {
+    pub fn new() -> Signature {
+        Signature::default()
+    }
+
+    /// Sets the types of the ports of `symbol`, principal port first.
+    pub fn set(&mut self, symbol: Symbol, types: Vec<Type>) {
+        self.types.insert(symbol, types);
+    }
+
+    pub fn types(&self, symbol: Symbol) -> Option<&[Type]> {
+        self.types.get(&symbol).map(|x| &x[..])
+    }
+
+    /// Returns the types of the ports of `symbol` if there is one per port.
+    fn ports(&self, rules: &RuleSet, symbol: Symbol) -> Result<&[Type], Symbol> {
+        match self.types(symbol) {
+            Some(types) if types.len() == rules.arity(symbol) + 1 => Ok(types),
+            _ => Err(symbol),
+        }
+    }
+
+    /// Checks that the rules between typed symbols connect dual types.
+    ///
+    /// Rules for active pairs whose principal ports are not dual are not checked, since such
+    /// pairs never occur in well-typed nets. In a slot of the left or right agent stands the port
+    /// connected to it, whose type is the dual of the auxiliary port.
+    ///
+    /// Returns all the problems found, sorted.
+    pub fn check(&self, rules: &RuleSet) -> Result<(), Vec<TypeError>> {
+        let mut errors = Vec::new();
+        for (left, right, rule) in rules.rules().filter(|&(l, r, _)| l <= r) {
+            let (l, r) = match (self.ports(rules, left), self.ports(rules, right)) {
+                (Ok(l), Ok(r)) if l[0] == r[0].dual() => (l, r),
+                _ => continue,
+            };
+            let slot = |x: Slot| -> Result<Type, Symbol> {
+                Ok(match x {
+                    Slot::Left(p) => l[p].dual(),
+                    Slot::Right(p) => r[p].dual(),
+                    Slot::Agent(k, p) => self.ports(rules, rule.agents[k])?[p].clone(),
+                })
+            };
+            for &(x, y) in &rule.wires {
+                match (slot(x), slot(y)) {
+                    (Ok(s), Ok(t)) if s != t.dual() => {
+                        errors.push(TypeError::Rule(left, right, x, y, s, t));
+                    }
+                    (Err(s), _) | (_, Err(s)) => errors.push(TypeError::Untyped(s)),
+                    _ => (),
+                }
+            }
+        }
+        finish(errors)
+    }
+}
+
+impl Net {
+    /// Checks that each wire between agents connects dual types of `signature`.
+    ///
+    /// Returns all the problems found, sorted.
+    pub fn typecheck(&self, signature: &Signature) -> Result<(), Vec<TypeError>> {
+        let mut errors = Vec::new();
+        let rules = &self.rules;
+        let port = |x: Port| -> Result<Type, Symbol> {
+            let symbol = self.agents[&x.agent].symbol;
+            Ok(signature.ports(rules, symbol)?[x.port].clone())
+        };
+        for (&a, agent) in &self.agents {
+            for (p, y) in agent.ports().enumerate() {
+                let x = Port::new(a, p);
+                let y = match y {
+                    Some(y) if x < y && self.agents.contains_key(&y.agent) => y,
+                    _ => {
+                        errors.extend(port(x).err().map(TypeError::Untyped));
+                        continue;
+                    }
+                };
+                match (port(x), port(y)) {
+                    (Ok(s), Ok(t)) if s != t.dual() => errors.push(TypeError::Wire(x, y, s, t)),
+                    (Err(s), _) | (_, Err(s)) => errors.push(TypeError::Untyped(s)),
+                    _ => (),
+                }
+            }
+        }
+        finish(errors)
+    }
+}
+
+fn finish(mut errors: Vec<TypeError>) -> Result<(), Vec<TypeError>> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    errors.sort();
+    errors.dedup();
+    Err(errors)
+}
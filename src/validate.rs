@@ -0,0 +1,105 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Net, Port};
+use std::fmt;
+
+/// A reason for a net to be malformed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum Malformed {
+    /// The port is not connected.
+    Dangling(Port),
+    /// The first port is connected to the second whose agent does not exist.
+    Missing(Port, Port),
+    /// The first port is connected to the second whose index exceeds the arity of its agent.
+    OutOfRange(Port, Port),
+    /// The first port is connected to the second which is connected elsewhere.
+    Asymmetric(Port, Port),
+    /// The principal port of the agent is connected to one of its own ports.
+    SelfConnected(usize),
+}
+
+impl fmt::Display for Malformed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let port = |x: Port| format!("{}.{}", x.agent, x.port);
+        match *self {
+            Malformed::Dangling(x) => write!(f, "port {} is not connected", port(x)),
+            Malformed::Missing(x, y) => {
+                write!(f, "port {} is connected to missing {}", port(x), port(y))
+            }
+            Malformed::OutOfRange(x, y) => {
+                write!(f, "port {} is connected to invalid {}", port(x), port(y))
+            }
+            Malformed::Asymmetric(x, y) => {
+                write!(
+                    f,
+                    "port {} is connected to {} but not back",
+                    port(x),
+                    port(y)
+                )
+            }
+            Malformed::SelfConnected(a) => write!(f, "agent {} is connected to itself", a),
+        }
+    }
+}
+
+impl Net {
+    /// Checks that all ports are connected, and that all wires are symmetric and between
+    /// existing ports.
+    ///
+    /// Returns all the problems found, sorted.
+    pub fn validate(&self) -> Result<(), Vec<Malformed>> {
+        let mut ports = Vec::new();
+        for (&a, agent) in &self.agents {
+            let ports_of_a = agent.ports().enumerate();
+            ports.extend(ports_of_a.map(|(p, y)| (Port::new(a, p), y)));
+        }
+        ports.extend(self.free.iter().map(|(&a, y)| (Port::new(a, 0), y.get())));
+        let mut errors = Vec::new();
+        for (x, y) in ports {
+            let y = match y {
+                None => {
+                    errors.push(Malformed::Dangling(x));
+                    continue;
+                }
+                Some(y) => y,
+            };
+            let back = match self.agents.get(&y.agent) {
+                Some(agent) => agent.wires.get(y.port).map(|x| x.get()),
+                None => match self.free.get(&y.agent) {
+                    Some(back) if y.port == 0 => Some(back.get()),
+                    Some(_) => None,
+                    None => {
+                        errors.push(Malformed::Missing(x, y));
+                        continue;
+                    }
+                },
+            };
+            match back {
+                None => errors.push(Malformed::OutOfRange(x, y)),
+                Some(back) if back != Some(x) => errors.push(Malformed::Asymmetric(x, y)),
+                Some(_) => (),
+            }
+            if x.port == 0 && x.agent == y.agent && self.agents.contains_key(&x.agent) {
+                errors.push(Malformed::SelfConnected(x.agent));
+            }
+        }
+        if errors.is_empty() {
+            return Ok(());
+        }
+        errors.sort();
+        errors.dedup();
+        Err(errors)
+    }
+}
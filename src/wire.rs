@@ -0,0 +1,75 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Packed representation of the wires of a net.
+
+use crate::Port;
+
+/// Number of bits of the port in a [`Wire`], which bounds the arity of symbols.
+pub(crate) const PORT_BITS: u32 = 16;
+
+/// Port connected to a port, packed in a single word.
+///
+/// The agent is in the high bits and the port in the low [`PORT_BITS`] bits. Unconnected ports
+/// are all ones. A wire takes 8 bytes while an `Option<Port>` takes 24 bytes on 64-bit targets.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Wire(u64);
+
+impl Wire {
+    pub(crate) const NONE: Wire = Wire(u64::MAX);
+
+    pub(crate) fn new(x: Option<Port>) -> Wire {
+        match x {
+            None => Wire::NONE,
+            Some(Port { agent, port }) => {
+                debug_assert!(port < 1 << PORT_BITS);
+                Wire(((agent as u64) << PORT_BITS) | port as u64)
+            }
+        }
+    }
+
+    pub(crate) fn get(self) -> Option<Port> {
+        if self == Wire::NONE {
+            return None;
+        }
+        let agent = (self.0 >> PORT_BITS) as usize;
+        Some(Port::new(agent, (self.0 & ((1 << PORT_BITS) - 1)) as usize))
+    }
+}
+
+/// Ports connected to the ports of a node in order, see [`Net::node`](crate::Net::node).
+///
+/// Unconnected ports are `None`.
+#[derive(Clone, Debug)]
+pub struct Ports<'a>(std::slice::Iter<'a, Wire>);
+
+impl<'a> Ports<'a> {
+    pub(crate) fn new(wires: &'a [Wire]) -> Ports<'a> {
+        Ports(wires.iter())
+    }
+}
+
+impl Iterator for Ports<'_> {
+    type Item = Option<Port>;
+
+    fn next(&mut self) -> Option<Option<Port>> {
+        self.0.next().map(|x| x.get())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Ports<'_> {}
@@ -0,0 +1,86 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the atomic engine.
+
+use lafont::gen::{church_term, example};
+use lafont::lambda::{compile, Term};
+use lafont::{Engine, Net, Port};
+
+#[test]
+fn atomic_same_as_reduce() {
+    let power = Term::App(Box::new(church_term(8)), Box::new(church_term(2)));
+    let nets = [
+        example("fibonacci:12").unwrap(),
+        example("ackermann:1,1").unwrap(),
+        compile(&power).unwrap().0,
+        Net::from_sic("r = (a *)\n* = {a b}\n(b c) = (c d)\nd = *").unwrap(),
+    ];
+    for net in nets {
+        let mut expected = net.clone();
+        let stats = expected.reduce(None);
+        let mut actual = net.clone();
+        actual.set_engine(Engine::Atomic);
+        let actual_stats = actual.reduce(None);
+        assert!(actual.isomorphic(&expected));
+        assert_eq!(actual_stats.steps, stats.steps);
+        assert_eq!(actual_stats.rules, stats.rules);
+        assert!(actual.active_pairs().is_empty());
+    }
+}
+
+#[test]
+fn atomic_limit() {
+    let net = example("fibonacci:12").unwrap();
+    let mut expected = net.clone();
+    let stats = expected.reduce(None);
+    let mut actual = net.clone();
+    actual.set_engine(Engine::Atomic);
+    let mut steps = 0;
+    while !actual.active_pairs().is_empty() {
+        let step = actual.reduce(Some(10)).steps;
+        assert!(0 < step && step <= 10);
+        steps += step;
+    }
+    assert_eq!(steps, stats.steps);
+    assert!(actual.isomorphic(&expected));
+}
+
+#[test]
+fn atomic_keep_the_interface() {
+    let mut net = Net::from_sic("r = (a b)\na = {x y}\n{x y} = (c d)\nc = *\nd = b").unwrap();
+    net.set_engine(Engine::Atomic);
+    let r = net.find_free("r").unwrap();
+    net.reduce(None);
+    assert_eq!(net.find_free("r"), Some(r));
+    assert!(net.get_port(Port::new(r, 0)).is_ok());
+    let next = net.next_id();
+    let c = net.add_construct();
+    assert_eq!(c, next);
+}
+
+#[test]
+fn with_engine() {
+    let mut net = Net::with_engine(Engine::Atomic);
+    assert_eq!(net.engine(), Engine::Atomic);
+    let c = net.add_construct();
+    let e = net.add_erase();
+    for p in 1..3 {
+        let f = net.add_free();
+        net.connect(Port::new(f, 0), Port::new(c, p)).unwrap();
+    }
+    net.connect(Port::new(c, 0), Port::new(e, 0)).unwrap();
+    assert_eq!(net.reduce(None).steps, 1);
+    assert_eq!(net.agents().count(), 2);
+}
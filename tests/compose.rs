@@ -0,0 +1,137 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for gluing nets along their free ports.
+
+use lafont::{Malformed, Net, NetError, Port, RuleSet};
+
+/// Returns the id of the free port named `name`.
+fn free(net: &Net, name: &str) -> usize {
+    net.find_free(name).unwrap()
+}
+
+#[test]
+fn glue() {
+    let left = Net::from_sic("r = (a b)").unwrap();
+    let right = Net::from_sic("c = *\nd = {e f}").unwrap();
+    let wiring = [(free(&left, "b"), free(&right, "c"))];
+    let net = left.compose(&right, &wiring).unwrap();
+    assert_eq!(net.validate(), Ok(()));
+    assert!(net.isomorphic(&Net::from_sic("r = (a *)\nd = {e f}").unwrap()));
+    for name in ["r", "a", "d", "e", "f"] {
+        assert!(net.find_free(name).is_some(), "{}", name);
+    }
+    for name in ["b", "c"] {
+        assert_eq!(net.find_free(name), None, "{}", name);
+    }
+    // The ids of the second net are shifted.
+    assert_eq!(free(&net, "d"), free(&right, "d") + left.next_id());
+    assert_eq!(net.next_id(), left.next_id() + right.next_id());
+}
+
+#[test]
+fn glue_active_pair() {
+    let left = Net::from_sic("a = (b c)").unwrap();
+    let right = Net::from_sic("d = (e f)").unwrap();
+    let mut net = left
+        .compose(&right, &[(free(&left, "a"), free(&right, "d"))])
+        .unwrap();
+    assert_eq!(net.active_pairs().len(), 1);
+    net.reduce(None);
+    assert!(net.isomorphic(&Net::from_sic("b = f\nc = e").unwrap()));
+}
+
+#[test]
+fn glue_chains() {
+    // Free ports connected to free ports are followed until reaching an agent.
+    let left = Net::from_sic("r = (a b)\nc = d").unwrap();
+    let right = Net::from_sic("e = f\ng = h").unwrap();
+    let wiring = [
+        (free(&left, "a"), free(&right, "e")),
+        (free(&left, "c"), free(&right, "f")),
+        (free(&left, "d"), free(&right, "g")),
+        (free(&left, "b"), free(&right, "h")),
+    ];
+    let net = left.compose(&right, &wiring).unwrap();
+    assert_eq!(net.validate(), Ok(()));
+    assert_eq!(net.free().count(), 1);
+    assert!(net.isomorphic(&Net::from_sic("r = (a a)").unwrap()));
+    // Glued free ports connected to free ports that are not glued.
+    let left = Net::from_sic("a = b").unwrap();
+    let right = Net::from_sic("c = d").unwrap();
+    let net = left
+        .compose(&right, &[(free(&left, "b"), free(&right, "c"))])
+        .unwrap();
+    assert_eq!(net.validate(), Ok(()));
+    assert!(net.isomorphic(&Net::from_sic("a = d").unwrap()));
+}
+
+#[test]
+fn glue_unconnected() {
+    let left = Net::from_sic("r = (a b)\nc = d").unwrap();
+    let mut right = Net::empty();
+    let (e, f) = (right.add_free(), right.add_free());
+    let wiring = [(free(&left, "b"), e), (free(&left, "c"), f)];
+    let net = left.compose(&right, &wiring).unwrap();
+    let c = left.agents().next().unwrap();
+    let d = free(&left, "d");
+    let mut expected = vec![
+        Malformed::Dangling(Port::new(c, 2)),
+        Malformed::Dangling(Port::new(d, 0)),
+    ];
+    expected.sort();
+    assert_eq!(net.validate(), Err(expected));
+    assert_eq!(net.free().count(), 3);
+}
+
+#[test]
+fn errors() {
+    let left = Net::from_sic("r = (a b)").unwrap();
+    let right = Net::from_sic("c = (d e)").unwrap();
+    let (a, b, c, d) = (
+        free(&left, "a"),
+        free(&left, "b"),
+        free(&right, "c"),
+        free(&right, "d"),
+    );
+    let shift = left.next_id();
+    assert_eq!(
+        left.compose(&right, &[(a, c), (b, c)]).unwrap_err(),
+        NetError::GluedTwice(c + shift)
+    );
+    assert_eq!(
+        left.compose(&right, &[(a, c), (a, d)]).unwrap_err(),
+        NetError::GluedTwice(a)
+    );
+    let agent = left.agents().next().unwrap();
+    assert_eq!(
+        left.compose(&right, &[(agent, c)]).unwrap_err(),
+        NetError::NotFree(agent)
+    );
+    let agent = right.agents().next().unwrap();
+    assert_eq!(
+        left.compose(&right, &[(a, agent)]).unwrap_err(),
+        NetError::NotFree(agent)
+    );
+    assert_eq!(
+        left.compose(&right, &[(right.next_id() + shift, c)])
+            .unwrap_err(),
+        NetError::NotFree(right.next_id() + shift)
+    );
+    let other = Net::from_sic_with(RuleSet::symmetric(), "c = (d e)").unwrap();
+    assert_eq!(
+        left.compose(&other, &[]).unwrap_err(),
+        NetError::DifferentRules
+    );
+}
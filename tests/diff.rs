@@ -0,0 +1,110 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the differences between nets.
+
+use lafont::{Difference, Net, Port};
+
+#[test]
+fn identical() {
+    let net = Net::from_sic("r = (a {b *})\n(a c) = {c b}").unwrap();
+    assert_eq!(net.diff(&net), []);
+    assert_eq!(net.diff(&net.clone()), []);
+}
+
+#[test]
+fn added() {
+    let net = Net::from_sic("r = (a b)").unwrap();
+    let mut other = net.clone();
+    let e = other.add_erase();
+    let f = other.add_free();
+    other.connect(Port::new(e, 0), Port::new(f, 0)).unwrap();
+    let erase = other.symbol(e);
+    assert_eq!(
+        net.diff(&other),
+        [Difference::Added(e, erase), Difference::Added(f, None)]
+    );
+    assert_eq!(
+        other.diff(&net),
+        [Difference::Removed(e, erase), Difference::Removed(f, None)]
+    );
+    assert_eq!(
+        net.diff(&other)[0].to_string(),
+        format!("symbol {} {} was added", erase.unwrap().0, e)
+    );
+}
+
+#[test]
+fn removed() {
+    let net = Net::from_sic("r = (a b)\n* = *").unwrap();
+    let is_erase = |a: usize| net.rules().name(net.symbol(a).unwrap()) == "erase";
+    let mut erasers: Vec<_> = net.agents().filter(|&a| is_erase(a)).collect();
+    erasers.sort_unstable();
+    let mut other = net.clone();
+    other.reduce(None);
+    let erase = net.symbol(erasers[0]);
+    assert_eq!(other.len(), 1);
+    assert_eq!(
+        net.diff(&other),
+        [
+            Difference::Removed(erasers[0], erase),
+            Difference::Removed(erasers[1], erase),
+        ]
+    );
+}
+
+#[test]
+fn rewired() {
+    let net = Net::from_sic("r = (a b)").unwrap();
+    let c = net.agents().next().unwrap();
+    let (a, b) = (net.find_free("a").unwrap(), net.find_free("b").unwrap());
+    let mut other = net.clone();
+    other.connect(Port::new(c, 1), Port::new(b, 0)).unwrap();
+    other.connect(Port::new(c, 2), Port::new(a, 0)).unwrap();
+    let mut expected = vec![
+        Difference::Rewired(
+            Port::new(c, 1),
+            Some(Port::new(a, 0)),
+            Some(Port::new(b, 0)),
+        ),
+        Difference::Rewired(
+            Port::new(c, 2),
+            Some(Port::new(b, 0)),
+            Some(Port::new(a, 0)),
+        ),
+        Difference::Rewired(
+            Port::new(a, 0),
+            Some(Port::new(c, 1)),
+            Some(Port::new(c, 2)),
+        ),
+        Difference::Rewired(
+            Port::new(b, 0),
+            Some(Port::new(c, 2)),
+            Some(Port::new(c, 1)),
+        ),
+    ];
+    expected.sort_by_key(|x| match *x {
+        Difference::Rewired(x, _, _) => x,
+        _ => unreachable!(),
+    });
+    assert_eq!(net.diff(&other), expected);
+    assert_eq!(
+        net.diff(&other)
+            .iter()
+            .find(|x| matches!(x, Difference::Rewired(x, _, _) if x.agent == a))
+            .unwrap()
+            .to_string(),
+        format!("port {a}.0 moved from {c}.1 to {c}.2")
+    );
+}
@@ -0,0 +1,68 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for computing with encoded data end to end.
+
+use lafont::encodings::{self, boolean, evaluate, read_bool, Encoding};
+
+const ENCODINGS: [Encoding; 2] = [Encoding::Church, Encoding::Scott];
+
+#[test]
+fn booleans() {
+    for a in [false, true] {
+        let (net, root) = evaluate(encodings::not(), [boolean(a)]).unwrap();
+        assert_eq!(read_bool(&net, root), Some(!a));
+        for b in [false, true] {
+            let (net, root) = evaluate(encodings::and(), [boolean(a), boolean(b)]).unwrap();
+            assert_eq!(read_bool(&net, root), Some(a && b), "{} and {}", a, b);
+            let (net, root) = evaluate(encodings::or(), [boolean(a), boolean(b)]).unwrap();
+            assert_eq!(read_bool(&net, root), Some(a || b), "{} or {}", a, b);
+        }
+    }
+}
+
+#[test]
+fn naturals() {
+    for e in ENCODINGS {
+        for n in 0..4 {
+            let (net, root) = evaluate(e.nat(n), []).unwrap();
+            assert_eq!(e.read_nat(&net, root), Some(n), "{:?} {}", e, n);
+            let (net, root) = evaluate(e.succ(), [e.nat(n)]).unwrap();
+            assert_eq!(e.read_nat(&net, root), Some(n + 1), "{:?} succ {}", e, n);
+            let (net, root) = evaluate(e.pred(), [e.nat(n)]).unwrap();
+            let pred = n.saturating_sub(1);
+            assert_eq!(e.read_nat(&net, root), Some(pred), "{:?} pred {}", e, n);
+            let (net, root) = evaluate(e.is_zero(), [e.nat(n)]).unwrap();
+            assert_eq!(read_bool(&net, root), Some(n == 0), "{:?} is_zero {}", e, n);
+        }
+    }
+}
+
+#[test]
+fn lists() {
+    for e in ENCODINGS {
+        for items in [vec![], vec![true], vec![false, true, true]] {
+            let list = e.list(items.iter().map(|&b| boolean(b)));
+            let (net, root) = evaluate(list.clone(), []).unwrap();
+            let actual = e.read_list(&net, root, encodings::decode_bool);
+            assert_eq!(actual.as_ref(), Some(&items), "{:?}", e);
+            let (net, root) = evaluate(e.is_nil(), [list]).unwrap();
+            assert_eq!(read_bool(&net, root), Some(items.is_empty()), "{:?}", e);
+        }
+        let list = e.list([e.nat(2)]);
+        let (net, root) = evaluate(e.cons(), [e.nat(1), list]).unwrap();
+        let actual = e.read_list(&net, root, |x| e.decode_nat(x));
+        assert_eq!(actual, Some(vec![1, 2]), "{:?}", e);
+    }
+}
@@ -0,0 +1,136 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the errors of the net API.
+
+use lafont::{Net, NetError, Port, RuleSet, Symbol};
+
+/// Checks that `result` fails with `expected`, displayed as `message`.
+fn check<T: std::fmt::Debug>(result: Result<T, NetError>, expected: NetError, message: &str) {
+    assert_eq!(result.unwrap_err(), expected);
+    assert_eq!(expected.to_string(), message);
+}
+
+#[test]
+fn unknown_symbol() {
+    let mut net = Net::empty();
+    check(
+        net.add_agent(Symbol(3)),
+        NetError::UnknownSymbol(Symbol(3)),
+        "unknown symbol 3",
+    );
+}
+
+#[test]
+fn missing_agent() {
+    let mut net = Net::empty();
+    let c = net.add_construct();
+    check(
+        net.connect(Port::new(c, 0), Port::new(5, 0)),
+        NetError::MissingAgent(5),
+        "missing agent 5",
+    );
+    check(
+        net.get_port(Port::new(5, 0)),
+        NetError::MissingAgent(5),
+        "missing agent 5",
+    );
+}
+
+#[test]
+fn invalid_port() {
+    let mut net = Net::empty();
+    let c = net.add_construct();
+    let f = net.add_free();
+    check(
+        net.connect(Port::new(c, 3), Port::new(f, 0)),
+        NetError::InvalidPort(Port::new(c, 3)),
+        &format!("invalid port {}.3", c),
+    );
+    check(
+        net.connect(Port::new(c, 0), Port::new(f, 1)),
+        NetError::InvalidPort(Port::new(f, 1)),
+        &format!("invalid port {}.1", f),
+    );
+}
+
+#[test]
+fn unconnected() {
+    let mut net = Net::empty();
+    let c = net.add_construct();
+    check(
+        net.get_port(Port::new(c, 1)),
+        NetError::Unconnected(Port::new(c, 1)),
+        &format!("port {}.1 is not connected", c),
+    );
+}
+
+#[test]
+fn not_free() {
+    let mut net = Net::empty();
+    let c = net.add_construct();
+    check(
+        net.set_name(c, "c"),
+        NetError::NotFree(c),
+        &format!("{} is not a free port", c),
+    );
+    let other = Net::from_sic("a = *").unwrap();
+    check(
+        net.compose(&other, &[(c, other.find_free("a").unwrap())]),
+        NetError::NotFree(c),
+        &format!("{} is not a free port", c),
+    );
+}
+
+#[test]
+fn glued_twice() {
+    let net = Net::from_sic("a = (b c)").unwrap();
+    let other = Net::from_sic("d = *").unwrap();
+    let (a, b) = (net.find_free("a").unwrap(), net.find_free("b").unwrap());
+    let d = other.find_free("d").unwrap();
+    // The ids of the second net are shifted.
+    let shifted = d + net.next_id();
+    check(
+        net.compose(&other, &[(a, d), (b, d)]),
+        NetError::GluedTwice(shifted),
+        &format!("free port {} is glued twice", shifted),
+    );
+}
+
+#[test]
+fn different_rules() {
+    let net = Net::from_sic("a = *").unwrap();
+    let other = Net::from_sic_with(RuleSet::symmetric(), "a = *").unwrap();
+    check(
+        net.compose(&other, &[]),
+        NetError::DifferentRules,
+        "nets have different rules",
+    );
+}
+
+#[test]
+fn invalid_name() {
+    let mut net = Net::empty();
+    let f = net.add_free();
+    check(
+        net.set_name(f, "a b"),
+        NetError::InvalidName("a b".to_string()),
+        "invalid name \"a b\"",
+    );
+    check(
+        net.set_name(f, "_"),
+        NetError::InvalidName("_".to_string()),
+        "invalid name \"_\"",
+    );
+}
@@ -0,0 +1,44 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the collection of subnets disconnected from the interface.
+
+use lafont::{GarbageStats, Net, Status};
+
+#[test]
+fn disconnected_components() {
+    // The active pair reduces forever but is not connected to the free port.
+    let mut net = Net::from_sic("r = (a a)\n(b c) = {b c}\nx = {y z}\n(y z) = x").unwrap();
+    let stats = net.collect_garbage();
+    assert_eq!(
+        stats,
+        GarbageStats {
+            agents: 4,
+            components: 2
+        }
+    );
+    assert_eq!(net.len(), 1);
+    assert_eq!(net.status(), Status::NormalForm);
+    assert_eq!(net.collect_garbage(), GarbageStats::default());
+}
+
+#[test]
+fn garbage_after_erasing() {
+    // Erasing the constructor leaves a cycle of duplicators with erasers on their other ports.
+    let mut net = Net::from_sic("* = (a b)\nx = {y a}\ny = {x b}").unwrap();
+    net.reduce(None);
+    assert_eq!(net.len(), 4);
+    assert_eq!(net.collect_garbage().agents, 4);
+    assert!(net.is_empty());
+}
@@ -0,0 +1,103 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the formats shared with other interaction net tools.
+
+use lafont::{hvm, net, DiagramLayout, Net, Port, RuleSet};
+
+#[test]
+fn hvm_church_two_identity() {
+    let program = "
+        // Church numeral two applied to the identity.
+        @id = (a a)
+        @two = ({(b c) (a b)} (a c))
+        @main = r
+          & @two ~ (@id r)
+    ";
+    let (mut net, root) = hvm::import(program).unwrap();
+    assert_eq!(net.free().collect::<Vec<_>>(), vec![root]);
+    net.reduce(None);
+    let identity = Net::from_sic_with(RuleSet::symmetric(), "r = (a a)").unwrap();
+    assert!(net.isomorphic(&identity), "{}", net.to_sic());
+}
+
+#[test]
+fn hvm_unsupported() {
+    for (program, error) in [
+        ("@main = @main", "@main is recursive"),
+        ("@main = @id", "@id is not defined"),
+        ("@id = (a a)\n@id = *", "@id is defined twice"),
+        (
+            "@main = #1",
+            "numbers, operators, and switches are not supported at offset 8",
+        ),
+    ] {
+        assert_eq!(hvm::import(program).map(|_| ()), Err(error.to_string()));
+    }
+}
+
+#[test]
+fn dot_round_trip() {
+    for sic in ["r = (a b)\n(a b) = {c d}\nc = *", "x = (y y)\nx = *", ""] {
+        let net = Net::from_sic(sic).unwrap();
+        let back = Net::from_dot(&net.to_dot()).unwrap();
+        assert!(back.isomorphic(&net), "{}", sic);
+    }
+    let error = Net::from_dot("graph net {\n  n0 -- n1 [];\n}");
+    assert_eq!(
+        error.map(|_| ()),
+        Err("undeclared node at line 2".to_string())
+    );
+}
+
+#[test]
+fn diagrams() {
+    let net = Net::from_sic("r = (a b)\n(a b) = {c d}\nc = *").unwrap();
+    for layout in [DiagramLayout::Layered, DiagramLayout::Circle] {
+        let svg = net.to_svg(layout);
+        assert!(svg.starts_with("<svg ") && svg.ends_with("</svg>\n"));
+        // Triangles for the constructors and the duplicator, a circle for the eraser.
+        assert_eq!(svg.matches("<polygon ").count(), 3);
+        assert_eq!(svg.matches(">γ</text>").count(), 2);
+        assert_eq!(svg.matches(">ε</text>").count(), 1);
+        assert_eq!(svg.matches(">r</text>").count(), 1);
+        // A path per wire, a dot per principal port.
+        assert_eq!(svg.matches("<path ").count(), 6);
+        assert_eq!(svg.matches("r=\"2.0\"/>").count(), 4);
+        let tikz = net.to_tikz(layout);
+        assert!(tikz.starts_with("\\begin{tikzpicture}\n"));
+        assert_eq!(tikz.matches("\\node[above] at").count(), 2);
+        assert_eq!(tikz.matches("$\\delta$").count(), 1);
+        assert_eq!(tikz.matches(" .. controls ").count(), 6);
+    }
+    assert_eq!(
+        Net::empty().to_tikz(DiagramLayout::Layered),
+        "\\begin{tikzpicture}\n\\end{tikzpicture}\n"
+    );
+}
+
+#[test]
+fn net_macro() {
+    let net = net! {
+        // The identity applied to itself.
+        r = (a b)
+        (a b) = (c c)
+    };
+    assert!(net.isomorphic(&Net::from_sic("r = (a b)\n(a b) = (c c)").unwrap()));
+    let mut rules = RuleSet::combinators();
+    let lam = rules.add_symbol("lam", 2);
+    let net = net!(rules; r = lam[x x]);
+    let r = Port::new(net.find_free("r").unwrap(), 0);
+    assert_eq!(net.symbol(net.get_port(r).unwrap().agent), Some(lam));
+}
@@ -0,0 +1,111 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for undoing reduction steps.
+
+use lafont::gen::example;
+use lafont::{Net, NetObserver, Strategy, Symbol};
+
+const STRATEGIES: [Strategy; 7] = [
+    Strategy::PreferErase,
+    Strategy::PreferAnnihilate,
+    Strategy::Fifo,
+    Strategy::Lifo,
+    Strategy::Random,
+    Strategy::Outermost,
+    Strategy::Fair,
+];
+
+/// Records the reduced active pairs.
+#[derive(Default)]
+struct Pairs(Vec<(usize, usize)>);
+
+impl NetObserver for Pairs {
+    fn on_rewrite(&mut self, _: (Symbol, Symbol), a: usize, b: usize) {
+        self.0.push((a, b));
+    }
+}
+
+/// Returns the active pairs reduced by `strategy`, with the random generator seeded.
+fn reduced(net: &Net, strategy: Strategy) -> Vec<(usize, usize)> {
+    let mut net = net.clone();
+    net.disable_journal();
+    net.seed(1);
+    let mut pairs = Pairs::default();
+    net.reduce_observed(strategy, None, &mut pairs);
+    pairs.0
+}
+
+#[test]
+fn undo_all_steps() {
+    let original = example("fibonacci:5").unwrap();
+    for strategy in STRATEGIES {
+        let mut net = original.clone();
+        net.seed(0);
+        net.enable_journal();
+        let steps = net.reduce_with(strategy, None).steps;
+        assert_eq!(net.journal().unwrap().len(), steps, "{:?}", strategy);
+        while net.undo_step() {}
+        assert!(net.isomorphic(&original), "{:?}", strategy);
+        assert_eq!(net.queued(), original.queued(), "{:?}", strategy);
+        // The worklist is restored, so a new reduction picks the same pairs.
+        for strategy in STRATEGIES {
+            assert_eq!(reduced(&net, strategy), reduced(&original, strategy));
+        }
+    }
+}
+
+#[test]
+fn undo_some_steps() {
+    let original = example("fibonacci:5").unwrap();
+    let mut net = original.clone();
+    net.seed(0);
+    net.enable_journal();
+    for _ in 0..5 {
+        assert!(net.step_with(Strategy::Random));
+    }
+    assert!(!net.isomorphic(&original));
+    for _ in 0..5 {
+        assert!(net.undo_step());
+    }
+    assert!(!net.undo_step());
+    assert!(net.isomorphic(&original));
+    assert_eq!(
+        reduced(&net, Strategy::Random),
+        reduced(&original, Strategy::Random)
+    );
+}
+
+#[test]
+fn undo_fair_steps() {
+    // The fair strategy alternates between the two components.
+    let part = example("fibonacci:4").unwrap();
+    let original = part.compose(&part, &[]).unwrap();
+    let mut net = original.clone();
+    net.enable_journal();
+    let mut pairs = Pairs::default();
+    net.reduce_observed(Strategy::Fair, Some(6), &mut pairs);
+    for _ in 0..3 {
+        assert!(net.undo_step());
+    }
+    let mut redone = Pairs::default();
+    net.reduce_observed(Strategy::Fair, Some(3), &mut redone);
+    assert_eq!(redone.0, pairs.0[3..]);
+    while net.undo_step() {}
+    assert!(net.isomorphic(&original));
+    assert_eq!(
+        reduced(&net, Strategy::Fair),
+        reduced(&original, Strategy::Fair)
+    );
+}
@@ -0,0 +1,81 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for compiling lambda terms and reading back their normal forms.
+
+use lafont::lambda::{compile, ReadbackError, Term};
+use lafont::{gen, Net, Port};
+
+fn normalize(term: &str) -> Result<Term, ReadbackError> {
+    let (mut net, root) = compile(&term.parse().unwrap()).unwrap();
+    net.reduce(None);
+    net.readback(Port::new(root, 0))
+}
+
+#[test]
+fn readback_normal_forms() {
+    for (term, normal) in [
+        (r"(\x. x) (\y. y)", r"\x. x"),
+        (r"(\n f x. f (n f x)) (\f x. f (f x))", r"\f x. f (f (f x))"),
+        (r"(\x y. y x) (\z. z)", r"\y. y (\z. z)"),
+    ] {
+        let expected = compile(&normal.parse().unwrap()).unwrap();
+        let actual = compile(&normalize(term).unwrap()).unwrap();
+        assert!(actual.0.isomorphic(&expected.0), "{}", term);
+    }
+}
+
+#[test]
+fn equivalent_terms() {
+    let net = |term: &str| compile(&term.parse().unwrap()).unwrap().0;
+    let three = net(r"(\n f x. f (n f x)) (\f x. f (f x))");
+    assert_eq!(three.equiv(&gen::church(3).0, 100), Some(true));
+    assert_eq!(three.equiv(&gen::church(2).0, 100), Some(false));
+    assert_eq!(three.equiv(&gen::church(3).0, 1), None);
+    let never = gen::example("loop").unwrap();
+    assert_eq!(never.equiv(&never, 100), None);
+}
+
+#[test]
+fn readback_errors() {
+    // The root is connected to an eraser.
+    let mut net = Net::empty();
+    let root = net.add_free();
+    let e = net.add_erase();
+    net.connect(Port::new(root, 0), Port::new(e, 0)).unwrap();
+    let error = net.readback(Port::new(root, 0));
+    assert_eq!(error, Err(ReadbackError::Unexpected(Port::new(e, 0))));
+
+    // The root is connected to another free port.
+    let mut net = Net::empty();
+    let root = net.add_free();
+    let other = net.add_free();
+    net.connect(Port::new(root, 0), Port::new(other, 0))
+        .unwrap();
+    let error = net.readback(Port::new(root, 0));
+    assert_eq!(error, Err(ReadbackError::Free(other)));
+
+    // The root is a copy of an application whose function is the other copy of itself.
+    let mut net = Net::empty();
+    let root = net.add_free();
+    let d = net.add_duplicate();
+    let a = net.add_construct();
+    let e = net.add_erase();
+    net.connect(Port::new(root, 0), Port::new(d, 2)).unwrap();
+    net.connect(Port::new(d, 0), Port::new(a, 1)).unwrap();
+    net.connect(Port::new(d, 1), Port::new(a, 0)).unwrap();
+    net.connect(Port::new(a, 2), Port::new(e, 0)).unwrap();
+    let error = net.readback(Port::new(root, 0));
+    assert!(matches!(error, Err(ReadbackError::Cycle(_))), "{:?}", error);
+}
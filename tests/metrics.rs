@@ -0,0 +1,42 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the graph metrics of nets.
+
+use lafont::Net;
+
+#[test]
+fn chain() {
+    // A chain of 3 constructors from the free port, and a separate active pair.
+    let net = Net::from_sic("r = (a *)\na = (b *)\nb = (* *)\n(x y) = (x y)").unwrap();
+    let mut depths: Vec<_> = net.depths().into_values().collect();
+    depths.sort_unstable();
+    assert_eq!(depths, [1, 2, 2, 3, 3, 4, 4]);
+    let sizes: Vec<_> = net.components().iter().map(|c| c.len()).collect();
+    assert_eq!(sizes, [7, 2]);
+    let (a, b) = net.active_pairs()[0];
+    assert_eq!(net.component(b), net.components()[1]);
+    assert_eq!(net.component(a), [a, b]);
+    assert_eq!(net.diameter(), 4);
+    assert_eq!(net.locality(), Some((b - a) as f64));
+}
+
+#[test]
+fn empty() {
+    let net = Net::empty();
+    assert!(net.depths().is_empty());
+    assert!(net.components().is_empty());
+    assert_eq!(net.diameter(), 0);
+    assert_eq!(net.locality(), None);
+}
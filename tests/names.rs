@@ -0,0 +1,74 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the names of free ports.
+
+use lafont::{Net, NetError, Port};
+
+fn reduced() -> Net {
+    let mut net = Net::from_sic("result = {input0 input1}\n(a a) = *").unwrap();
+    net.reduce(None);
+    net
+}
+
+#[test]
+fn names_survive_reduction() {
+    let net = reduced();
+    let result = net.find_free("result").unwrap();
+    assert_eq!(net.name(result), Some("result"));
+    let back = Net::from_sic(&net.to_sic()).unwrap();
+    assert_eq!(back.to_sic(), net.to_sic());
+    assert_eq!(back.to_sic(), "result = {input0 input1}\n");
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn names_in_json() {
+    let net = reduced();
+    let result = net.find_free("result").unwrap();
+    let back = Net::from_json(&net.to_json()).unwrap();
+    assert_eq!(back.name(result), Some("result"));
+    let input = back.find_free("input1").unwrap();
+    let d = back.get_port(Port::new(result, 0)).unwrap().agent;
+    assert_eq!(back.get_port(Port::new(d, 2)), Ok(Port::new(input, 0)));
+}
+
+#[test]
+fn names_do_not_clash() {
+    let mut net = Net::empty();
+    let c = net.add_construct();
+    for (p, name) in [(0, "x0"), (1, "x0"), (2, "f1")] {
+        let f = net.add_free();
+        net.connect(Port::new(c, p), Port::new(f, 0)).unwrap();
+        net.set_name(f, name).unwrap();
+    }
+    let f = net.add_free();
+    assert_eq!(
+        net.set_name(f, "a b"),
+        Err(NetError::InvalidName("a b".to_string()))
+    );
+    assert_eq!(net.set_name(c, "c"), Err(NetError::NotFree(c)));
+    assert_eq!(net.to_sic(), "x0 = (x0' f1)\n");
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn unnamed_snapshots() {
+    let json = r#"{"V1": {"symbols": [["construct", 2], ["duplicate", 2], ["erase", 0]],
+        "rules": [], "agents": [],
+        "free": [[0, {"agent": 1, "port": 0}], [1, {"agent": 0, "port": 0}]], "next": 2}}"#;
+    let net = Net::from_json(json).unwrap();
+    assert_eq!(net.name(0), None);
+    assert_eq!(net.to_sic(), "f0 = f1\n");
+}
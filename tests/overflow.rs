@@ -0,0 +1,74 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for bounding the size of nets during reduction.
+
+use lafont::{Net, NetObserver, Status, Strategy, Symbol};
+
+#[test]
+fn overflow_stops_growing_net() {
+    // Each commutation creates a new constructor-duplicator pair, so the net grows forever.
+    let mut net = Net::from_sic("(a b) = {a b}").unwrap();
+    let e = net
+        .reduce_bounded(Strategy::PreferErase, None, 1000)
+        .unwrap_err();
+    assert_eq!(e.max_agents, 1000);
+    assert_eq!(e.snapshot.len(), 1002);
+    assert_eq!(e.stats.steps, 500);
+    assert_eq!(e.snapshot.to_sic(), net.to_sic());
+}
+
+#[test]
+fn bounded_reduction_within_limit() {
+    let mut net = Net::from_sic("(a b) = {c d}").unwrap();
+    let stats = net.reduce_bounded(Strategy::PreferErase, None, 4).unwrap();
+    assert_eq!(stats.steps, 1);
+    assert_eq!(net.status(), Status::NormalForm);
+}
+
+#[test]
+fn bounded_sweeps() {
+    // The number of active pairs doubles at each pass, and the limit is checked between passes.
+    let mut net = Net::from_sic("(a b) = {a b}").unwrap();
+    let e = net.reduce_sweeps_bounded(None, 1000).unwrap_err();
+    assert_eq!(e.max_agents, 1000);
+    assert_eq!(e.snapshot.len(), 1024);
+    assert_eq!(e.stats.steps, 511);
+    assert_eq!(e.stats.sweeps, [1, 2, 4, 8, 16, 32, 64, 128, 256]);
+    let mut net = Net::from_sic("(a b) = {c d}").unwrap();
+    let stats = net.reduce_sweeps_bounded(None, 4).unwrap();
+    assert_eq!(stats.sweeps, [1]);
+    assert_eq!(net.status(), Status::NormalForm);
+}
+
+/// Counts rewrites.
+struct Rewrites(usize);
+
+impl NetObserver for Rewrites {
+    fn on_rewrite(&mut self, _: (Symbol, Symbol), _: usize, _: usize) {
+        self.0 += 1;
+    }
+}
+
+#[test]
+fn bounded_observed() {
+    let mut net = Net::from_sic("(a b) = {a b}").unwrap();
+    let mut rewrites = Rewrites(0);
+    let e = net
+        .reduce_bounded_observed(Strategy::PreferErase, None, 1000, &mut rewrites)
+        .unwrap_err();
+    assert_eq!(e.snapshot.len(), 1002);
+    assert_eq!(e.stats.steps, 500);
+    assert_eq!(rewrites.0, 500);
+}
@@ -0,0 +1,56 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the parallel reductions.
+
+use lafont::gen::{church_term, example};
+use lafont::lambda::{compile, Term};
+use lafont::{Net, Port};
+
+#[test]
+fn regions_same_as_reduce() {
+    // 2^8 with Church numerals duplicates 2 many times.
+    let power = Term::App(Box::new(church_term(8)), Box::new(church_term(2)));
+    let nets = [
+        example("fibonacci:12").unwrap(),
+        example("ackermann:1,1").unwrap(),
+        compile(&power).unwrap().0,
+        Net::from_sic("r = (a *)\n* = {a b}\n(b c) = (c d)\nd = *").unwrap(),
+    ];
+    for net in nets {
+        let mut expected = net.clone();
+        let stats = expected.reduce(None);
+        for regions in [1, 2, 7, 64] {
+            let mut actual = net.clone();
+            let actual_stats = actual.reduce_regions(regions);
+            assert!(actual.isomorphic(&expected));
+            // All the reductions of an interaction net to its normal form have the same length.
+            assert_eq!(actual_stats.steps, stats.steps);
+            assert_eq!(actual_stats.rules, stats.rules);
+            assert!(actual.active_pairs().is_empty());
+        }
+    }
+}
+
+#[test]
+fn regions_keep_the_interface() {
+    let mut net = Net::from_sic("r = (a b)\na = {x y}\n{x y} = (c d)\nc = *\nd = b").unwrap();
+    let r = net.find_free("r").unwrap();
+    net.reduce_regions(3);
+    assert_eq!(net.find_free("r"), Some(r));
+    assert!(net.get_port(Port::new(r, 0)).is_ok());
+    let next = net.next_id();
+    let c = net.add_construct();
+    assert_eq!(c, next);
+}
@@ -0,0 +1,54 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for nets sharing their agents with their clones.
+
+use lafont::gen::example;
+use lafont::{PersistentNet, Strategy};
+
+#[test]
+fn same_reduction() {
+    let net = example("fibonacci:8").unwrap();
+    for strategy in [
+        Strategy::PreferErase,
+        Strategy::PreferAnnihilate,
+        Strategy::Fifo,
+        Strategy::Lifo,
+        Strategy::Outermost,
+        Strategy::Fair,
+    ] {
+        let mut persistent = PersistentNet::from(&net);
+        let mut net = net.clone();
+        for limit in [10, 1000] {
+            let expected = net.reduce_with(strategy, Some(limit));
+            let actual = persistent.reduce_with(strategy, Some(limit));
+            assert_eq!(actual.steps, expected.steps, "{:?}", strategy);
+            assert_eq!(actual.peak_agents, expected.peak_agents, "{:?}", strategy);
+            assert_eq!(persistent.to_net().to_sic(), net.to_sic(), "{:?}", strategy);
+        }
+        assert_eq!(persistent.status(), net.status());
+    }
+}
+
+#[test]
+fn forks_are_independent() {
+    let mut net = PersistentNet::from(&example("fibonacci:8").unwrap());
+    net.reduce_with(Strategy::PreferErase, Some(20));
+    let sic = net.to_net().to_sic();
+    let mut fork = net.clone();
+    fork.reduce_with(Strategy::Lifo, None);
+    assert_eq!(net.to_net().to_sic(), sic);
+    net.reduce_with(Strategy::PreferAnnihilate, None);
+    assert!(net.to_net().isomorphic(&fork.to_net()));
+}
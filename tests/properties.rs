@@ -0,0 +1,74 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use lafont::gen::example;
+use lafont::testing::{arb_net, confluent, connect_involutive, matches_oracle, rule_arithmetic};
+use lafont::{Net, Port};
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn generated_nets_are_involutive(net in arb_net(20, 4)) {
+        prop_assert_eq!(connect_involutive(&net), Ok(()));
+    }
+
+    #[test]
+    fn reconnecting_is_involutive(mut net in arb_net(20, 4), i: usize, j: usize) {
+        let ports: Vec<_> = net.agents().map(|a| Port::new(a, 0)).collect();
+        prop_assume!(ports.len() >= 2);
+        let (x, y) = (ports[i % ports.len()], ports[j % ports.len()]);
+        prop_assume!(x != y);
+        // Swap the wires of x and y so that the net stays well-formed.
+        let (u, v) = (net.get_port(x).unwrap(), net.get_port(y).unwrap());
+        prop_assume!(u != y);
+        net.connect(x, v).unwrap();
+        net.connect(y, u).unwrap();
+        prop_assert_eq!(net.get_port(x), Ok(v));
+        prop_assert_eq!(net.get_port(v), Ok(x));
+        prop_assert_eq!(connect_involutive(&net), Ok(()));
+    }
+
+    #[test]
+    fn reduction_follows_rule_arithmetic(mut net in arb_net(20, 4)) {
+        for _ in 0..100 {
+            if !rule_arithmetic(&mut net).map_err(TestCaseError::fail)? {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn sic_round_trips(net in arb_net(20, 4)) {
+        let back = Net::from_sic(&net.to_sic()).map_err(TestCaseError::fail)?;
+        prop_assert!(back.isomorphic(&net));
+    }
+
+    #[test]
+    fn reduction_is_confluent(net in arb_net(12, 3)) {
+        prop_assert_eq!(confluent(&net, 200), Ok(()));
+    }
+
+    #[test]
+    fn reduction_matches_oracle(net in arb_net(12, 3)) {
+        prop_assert_eq!(matches_oracle(&net, 200), Ok(()));
+    }
+}
+
+#[test]
+fn examples_match_oracle() {
+    for name in ["church:2", "ackermann:1,1", "fibonacci:8"] {
+        let net = example(name).unwrap();
+        assert_eq!(matches_oracle(&net, 10_000), Ok(()), "{}", name);
+    }
+}
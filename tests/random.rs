@@ -0,0 +1,54 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for random nets.
+
+use lafont::{Net, Symbol};
+
+const WEIGHTS: [(Symbol, f64); 3] = [
+    (Symbol::CONSTRUCT, 2.),
+    (Symbol::DUPLICATE, 2.),
+    (Symbol::ERASE, 1.),
+];
+
+#[test]
+fn random() {
+    for seed in 0..20 {
+        let net = Net::random(101, &WEIGHTS, 0.5, seed).unwrap();
+        assert_eq!(net.validate(), Ok(()), "{}", seed);
+        assert_eq!(net.free().count(), 0);
+        // An agent is added if the number of ports is odd.
+        assert!(net.len() == 101 || net.len() == 102);
+        assert!(net.isomorphic(&Net::random(101, &WEIGHTS, 0.5, seed).unwrap()));
+    }
+}
+
+#[test]
+fn active_pairs() {
+    let without_erase = &WEIGHTS[..2];
+    for (active, pairs) in [(0., 0), (0.5, 25), (1., 50)] {
+        let net = Net::random(100, without_erase, active, 0).unwrap();
+        assert_eq!(net.active_pairs().len(), pairs, "{}", active);
+    }
+    // Erasers have no auxiliary ports to connect to, so they connect to each other.
+    let net = Net::random(10, &[(Symbol::ERASE, 1.)], 0., 0).unwrap();
+    assert_eq!(net.active_pairs().len(), 5);
+}
+
+#[test]
+fn invalid_weights() {
+    assert!(Net::random(10, &[], 0.5, 0).is_err());
+    assert!(Net::random(10, &[(Symbol::ERASE, 0.)], 0.5, 0).is_err());
+    assert!(Net::random(10, &[(Symbol(3), 1.)], 0.5, 0).is_err());
+}
@@ -0,0 +1,349 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Regression tests for interaction rules.
+//!
+//! The rules of Lafont's interaction combinators are written here independently of
+//! [`RuleSet::combinators`], following the article: constructors annihilate crosswise,
+//! duplicators annihilate straight, constructors and duplicators commute, and erasers erase.
+//! Other interaction systems go through the same rewrite engine.
+
+use lafont::{Net, Port, ReductionStats, Rule, RuleSet, Slot, Symbol};
+use std::collections::HashMap;
+
+const C: Symbol = Symbol::CONSTRUCT;
+const D: Symbol = Symbol::DUPLICATE;
+const E: Symbol = Symbol::ERASE;
+
+/// End of a wire in the expected result.
+#[derive(Clone, Copy, Debug)]
+enum End {
+    /// Free port connected to an auxiliary port of the active pair, left agent first.
+    Free(usize),
+    /// Port of the created agent at the given index.
+    Agent(usize, usize),
+}
+use End::*;
+
+/// Expected result of reducing an active pair whose auxiliary ports are all free.
+struct Case {
+    left: Symbol,
+    right: Symbol,
+    agents: &'static [Symbol],
+    wires: &'static [(End, End)],
+}
+
+const COMMUTE: &[(End, End)] = &[
+    (Free(0), Agent(0, 0)),
+    (Free(1), Agent(1, 0)),
+    (Free(2), Agent(2, 0)),
+    (Free(3), Agent(3, 0)),
+    (Agent(0, 1), Agent(2, 1)),
+    (Agent(0, 2), Agent(3, 1)),
+    (Agent(1, 1), Agent(2, 2)),
+    (Agent(1, 2), Agent(3, 2)),
+];
+
+const ERASE: &[(End, End)] = &[(Free(0), Agent(0, 0)), (Free(1), Agent(1, 0))];
+
+const CASES: &[Case] = &[
+    Case {
+        left: C,
+        right: C,
+        agents: &[],
+        wires: &[(Free(0), Free(3)), (Free(1), Free(2))],
+    },
+    Case {
+        left: D,
+        right: D,
+        agents: &[],
+        wires: &[(Free(0), Free(2)), (Free(1), Free(3))],
+    },
+    Case {
+        left: E,
+        right: E,
+        agents: &[],
+        wires: &[],
+    },
+    Case {
+        left: C,
+        right: D,
+        agents: &[D, D, C, C],
+        wires: COMMUTE,
+    },
+    Case {
+        left: D,
+        right: C,
+        agents: &[C, C, D, D],
+        wires: COMMUTE,
+    },
+    Case {
+        left: C,
+        right: E,
+        agents: &[E, E],
+        wires: ERASE,
+    },
+    Case {
+        left: E,
+        right: C,
+        agents: &[E, E],
+        wires: ERASE,
+    },
+    Case {
+        left: D,
+        right: E,
+        agents: &[E, E],
+        wires: ERASE,
+    },
+    Case {
+        left: E,
+        right: D,
+        agents: &[E, E],
+        wires: ERASE,
+    },
+];
+
+/// Builds the active pair of `case` and returns the net with its free ports.
+fn build(case: &Case) -> (Net, Vec<usize>) {
+    let mut net = Net::empty();
+    let a = net.add_agent(case.left).unwrap();
+    let b = net.add_agent(case.right).unwrap();
+    net.connect(Port::new(a, 0), Port::new(b, 0)).unwrap();
+    let mut free = Vec::new();
+    for x in [a, b] {
+        for p in 1..=net.rules().arity(net.symbol(x).unwrap()) {
+            let f = net.add_free();
+            net.connect(Port::new(f, 0), Port::new(x, p)).unwrap();
+            free.push(f);
+        }
+    }
+    (net, free)
+}
+
+/// Checks that the reduced net is the expected result, up to renaming of created agents.
+fn check(case: &Case, net: &Net, free: &[usize]) {
+    let name = format!("{:?}-{:?}", case.left, case.right);
+    assert_eq!(net.active_pairs(), vec![], "{}", name);
+    assert_eq!(net.agents().count(), case.agents.len(), "{}", name);
+    // Created agents are found from the free ports they are connected to.
+    let mut agents = HashMap::new();
+    for &(x, y) in case.wires {
+        let (f, k, p) = match (x, y) {
+            (Free(f), Agent(k, p)) | (Agent(k, p), Free(f)) => (f, k, p),
+            _ => continue,
+        };
+        let z = net.get_port(Port::new(free[f], 0)).unwrap();
+        assert_eq!(z.port, p, "{}: free port {}", name, f);
+        assert_eq!(net.symbol(z.agent), Some(case.agents[k]), "{}", name);
+        assert_eq!(*agents.entry(k).or_insert(z.agent), z.agent, "{}", name);
+    }
+    let mut ids: Vec<_> = agents.values().collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), case.agents.len(), "{}", name);
+    let port = |x| match x {
+        Free(f) => Port::new(free[f], 0),
+        Agent(k, p) => Port::new(agents[&k], p),
+    };
+    for &(x, y) in case.wires {
+        assert_eq!(net.get_port(port(x)), Ok(port(y)), "{}: {:?}", name, x);
+        assert_eq!(net.get_port(port(y)), Ok(port(x)), "{}: {:?}", name, y);
+    }
+    assert_eq!(net.validate(), Ok(()), "{}", name);
+}
+
+#[test]
+fn step() {
+    for case in CASES {
+        let (mut net, free) = build(case);
+        assert!(net.step());
+        check(case, &net, &free);
+    }
+}
+
+#[test]
+fn reduce_pair() {
+    for case in CASES {
+        let (mut net, free) = build(case);
+        let pair = net.active_pairs()[0];
+        let created = net.reduce_pair(pair.0, pair.1).unwrap();
+        assert_eq!(created.len(), case.agents.len());
+        check(case, &net, &free);
+    }
+}
+
+#[test]
+fn annihilate_through_loop() {
+    // The auxiliary ports of the left constructor are connected together, so annihilation
+    // connects the auxiliary ports of the right constructor together.
+    let mut net = Net::empty();
+    let a = net.add_construct();
+    let b = net.add_construct();
+    let x = net.add_free();
+    let y = net.add_free();
+    net.connect(Port::new(a, 0), Port::new(b, 0)).unwrap();
+    net.connect(Port::new(a, 1), Port::new(a, 2)).unwrap();
+    net.connect(Port::new(b, 1), Port::new(x, 0)).unwrap();
+    net.connect(Port::new(b, 2), Port::new(y, 0)).unwrap();
+    assert!(net.step());
+    assert_eq!(net.agents().count(), 0);
+    assert_eq!(net.get_port(Port::new(x, 0)), Ok(Port::new(y, 0)));
+    assert_eq!(net.validate(), Ok(()));
+}
+
+#[test]
+fn annihilate_through_pair() {
+    // Both auxiliary ports of the left duplicator are connected to the right one, so the wires
+    // close on themselves and nothing is left.
+    let mut net = Net::empty();
+    let a = net.add_duplicate();
+    let b = net.add_duplicate();
+    net.connect(Port::new(a, 0), Port::new(b, 0)).unwrap();
+    net.connect(Port::new(a, 1), Port::new(b, 2)).unwrap();
+    net.connect(Port::new(a, 2), Port::new(b, 1)).unwrap();
+    assert!(net.step());
+    assert_eq!(net.agents().count(), 0);
+    assert_eq!(net.validate(), Ok(()));
+}
+
+#[test]
+fn symmetric_annihilate_straight() {
+    // Symmetric constructors connect their auxiliary ports in order, like duplicators.
+    let mut net = Net::with_rules(RuleSet::symmetric());
+    let a = net.add_construct();
+    let b = net.add_construct();
+    net.connect(Port::new(a, 0), Port::new(b, 0)).unwrap();
+    let mut free = Vec::new();
+    for x in [a, b] {
+        for p in 1..=2 {
+            let f = net.add_free();
+            net.connect(Port::new(f, 0), Port::new(x, p)).unwrap();
+            free.push(Port::new(f, 0));
+        }
+    }
+    assert!(net.step());
+    assert_eq!(net.agents().count(), 0);
+    assert_eq!(net.get_port(free[0]), Ok(free[2]));
+    assert_eq!(net.get_port(free[1]), Ok(free[3]));
+}
+
+#[test]
+fn unary_addition() {
+    use Slot::*;
+    let mut rules = RuleSet::new();
+    let zero = rules.add_symbol("zero", 0);
+    let succ = rules.add_symbol("succ", 1);
+    // The principal port of an addition is its first argument, then come the second argument
+    // and the result.
+    let add = rules.add_symbol("add", 2);
+    let add_zero = Rule {
+        agents: vec![],
+        wires: vec![(Left(1), Left(2))],
+    };
+    let add_succ = Rule {
+        agents: vec![add, succ],
+        wires: vec![
+            (Right(1), Agent(0, 0)),
+            (Left(1), Agent(0, 1)),
+            (Agent(0, 2), Agent(1, 1)),
+            (Agent(1, 0), Left(2)),
+        ],
+    };
+    rules.add_rule(add, zero, add_zero).unwrap();
+    rules.add_rule(add, succ, add_succ).unwrap();
+    let mut net = Net::with_rules(rules);
+    let number = |net: &mut Net, n: usize| {
+        let mut x = Port::new(net.add_agent(zero).unwrap(), 0);
+        for _ in 0..n {
+            let s = net.add_agent(succ).unwrap();
+            net.connect(Port::new(s, 1), x).unwrap();
+            x = Port::new(s, 0);
+        }
+        x
+    };
+    let a = net.add_agent(add).unwrap();
+    let (x, y) = (number(&mut net, 2), number(&mut net, 3));
+    net.connect(Port::new(a, 0), x).unwrap();
+    net.connect(Port::new(a, 1), y).unwrap();
+    let root = net.add_free();
+    net.connect(Port::new(a, 2), Port::new(root, 0)).unwrap();
+    assert_eq!(net.reduce(None).steps, 3);
+    let mut n = 0;
+    let mut x = net.get_port(Port::new(root, 0)).unwrap();
+    while net.symbol(x.agent) == Some(succ) {
+        x = net.get_port(Port::new(x.agent, 1)).unwrap();
+        n += 1;
+    }
+    assert_eq!(net.symbol(x.agent), Some(zero));
+    assert_eq!(n, 5);
+    assert_eq!(net.agents().count(), 6);
+    assert_eq!(net.validate(), Ok(()));
+}
+
+#[test]
+fn explain() {
+    let rules = RuleSet::combinators();
+    assert_eq!(
+        rules.explain((C, D), 5, 9, &[17, 18, 19, 20]),
+        "γδ commutation: constructor 5 passes through duplicator 9, creating agents 17, 18, 19 \
+         and 20"
+    );
+    assert_eq!(
+        rules.explain((D, D), 1, 2, &[]),
+        "δδ annihilation: duplicator 1 and duplicator 2 disappear and connect their neighbors"
+    );
+    assert_eq!(
+        rules.explain((C, E), 3, 4, &[6, 7]),
+        "γε erasure: eraser 4 erases constructor 3, creating agents 6 and 7"
+    );
+    assert_eq!(
+        rules.explain((E, E), 3, 4, &[]),
+        "εε annihilation: eraser 3 and eraser 4 disappear"
+    );
+    let mut rules = RuleSet::new();
+    let (zero, add) = (rules.add_symbol("zero", 0), rules.add_symbol("add", 2));
+    let rule = Rule {
+        agents: vec![],
+        wires: vec![(Slot::Left(1), Slot::Left(2))],
+    };
+    rules.add_rule(add, zero, rule).unwrap();
+    assert_eq!(
+        rules.explain((add, zero), 0, 1, &[]),
+        "add-zero rule: add 0 and zero 1 rewrite"
+    );
+}
+
+#[test]
+fn coverage() {
+    let rules = RuleSet::combinators();
+    let mut stats = ReductionStats::default();
+    for sic in ["(a b) = (a b)", "(a b) = *"] {
+        let mut net = Net::from_sic(sic).unwrap();
+        stats.merge(&net.reduce(None));
+    }
+    let coverage = rules.coverage(&stats);
+    assert_eq!(coverage.counts.len(), 6);
+    assert_eq!(coverage.fired(), 2);
+    assert!(!coverage.is_complete());
+    assert_eq!(
+        coverage.unused().collect::<Vec<_>>(),
+        vec![(C, D), (D, D), (D, E), (E, E)]
+    );
+    assert_eq!(
+        coverage.report(&rules),
+        "coverage: 2 of 6 rules fired\n  construct-construct: 1\n  construct-duplicate: never\n  \
+         construct-erase: 1\n  duplicate-duplicate: never\n  duplicate-erase: never\n  \
+         erase-erase: never\n"
+    );
+}
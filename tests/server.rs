@@ -0,0 +1,114 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the HTTP server of the `serve` subcommand.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command};
+use std::time::Duration;
+
+/// A server running until dropped.
+struct Server {
+    child: Child,
+    port: u16,
+}
+
+impl Server {
+    fn start() -> Server {
+        // The port is free once the listener is dropped, unless taken in the meantime.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let child = Command::new(env!("CARGO_BIN_EXE_lafont"))
+            .args(["serve", &format!("--port={}", port), "--example=church:2"])
+            .spawn()
+            .unwrap();
+        let server = Server { child, port };
+        for _ in 0..100 {
+            if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+                return server;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        panic!("server did not start");
+    }
+
+    /// Sends a request with the given head, and returns the response.
+    fn send(&self, head: &str, body: &str) -> String {
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port)).unwrap();
+        write!(
+            stream,
+            "{}\r\nContent-Length: {}\r\n\r\n{}",
+            head,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn malformed_net() {
+    let server = Server::start();
+    // A constructor and a duplicator in an active pair, whose auxiliary ports are not connected.
+    let json = r#"{"V2": {"symbols": [["construct", 2], ["duplicate", 2], ["erase", 0]],
+        "rules": [], "agents": [[0, 0, [{"agent": 1, "port": 0}, null, null]],
+                                [1, 1, [{"agent": 0, "port": 0}, null, null]]],
+        "free": [], "names": [], "next": 2}}"#;
+    let response = server.send("POST /net HTTP/1.1", json);
+    assert!(response.starts_with("HTTP/1.1 400 "), "{}", response);
+    assert!(
+        response.contains("port 0.1 is not connected"),
+        "{}",
+        response
+    );
+    // The net is unchanged and can still be reduced.
+    let response = server.send("POST /step?n=100 HTTP/1.1", "");
+    assert!(response.starts_with("HTTP/1.1 200 "), "{}", response);
+    assert!(
+        response.contains("\"status\":\"normal form\""),
+        "{}",
+        response
+    );
+}
+
+#[test]
+fn large_body() {
+    let server = Server::start();
+    let mut stream = TcpStream::connect(("127.0.0.1", server.port)).unwrap();
+    write!(
+        stream,
+        "POST /net HTTP/1.1\r\nContent-Length: 4611686018427387904\r\n\r\n"
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 413 "), "{}", response);
+    // The server is still running.
+    let response = server.send("GET /state HTTP/1.1", "");
+    assert!(response.starts_with("HTTP/1.1 200 "), "{}", response);
+}
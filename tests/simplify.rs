@@ -0,0 +1,93 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Tests for the simplification of nets before reduction.
+
+use lafont::{Net, RuleSet};
+
+#[test]
+fn cancelling_patterns() {
+    for (net, removed, simple) in [
+        ("r = {* *}", 2, "r = *"),
+        ("r = ({* *} *)", 4, "r = *"),
+        ("r = (a a)\n* = *", 2, "r = (a a)"),
+        ("* = {* *}", 4, ""),
+        ("r = (a b)\na = {* *}", 2, "r = (* b)"),
+    ] {
+        let mut actual = Net::from_sic(net).unwrap();
+        assert_eq!(actual.simplify(), removed, "{}", net);
+        let expected = Net::from_sic(simple).unwrap();
+        assert!(actual.isomorphic(&expected), "{}", actual.to_sic());
+    }
+}
+
+#[test]
+fn same_normal_form() {
+    for net in [
+        "r = (x y)\n(x y) = {a b}\na = {* *}\nb = *",
+        "r = {x y}\n(x {* *}) = ({* *} y)",
+        "r = (a b)\n(a b) = {c d}\nc = {* *}\nd = {* *}",
+        "r = {a b}\n{a c} = ({* *} d)\nb = (c d)",
+    ] {
+        let mut expected = Net::from_sic(net).unwrap();
+        let mut actual = expected.clone();
+        assert!(actual.simplify() > 0, "{}", net);
+        expected.reduce(None);
+        actual.reduce(None);
+        expected.simplify();
+        actual.simplify();
+        assert!(actual.isomorphic(&expected), "{}", net);
+    }
+}
+
+#[test]
+fn other_rules() {
+    let mut net = Net::from_sic_with(RuleSet::new(), "").unwrap();
+    assert_eq!(net.simplify(), 0);
+}
+
+#[test]
+fn shared_trees() {
+    for (net, removed, shared) in [
+        ("r = {(* *) (* *)}", 4, "r = (* *)"),
+        ("r = {(* *) (* (* *))}", 0, "r = {(* *) (* (* *))}"),
+        ("r = {(* {* *}) (* {* *})}", 8, "r = (* *)"),
+        ("r = {({* *} *) (* *)}", 6, "r = (* *)"),
+        (
+            "r = {(* {* (* *)}) (* {* (* *)})}",
+            0,
+            "r = {(* {* (* *)}) (* {* (* *)})}",
+        ),
+        ("r = (a b)\na = {x x}", 0, "r = (a b)\na = {x x}"),
+    ] {
+        let mut actual = Net::from_sic(net).unwrap();
+        assert_eq!(actual.share(), removed, "{}", net);
+        let expected = Net::from_sic(shared).unwrap();
+        assert!(actual.isomorphic(&expected), "{}", actual.to_sic());
+    }
+    // Whatever interacts with the duplicator gets the same normal form, up to sharing.
+    for net in [
+        "{a b} = {(* *) (* *)}",
+        "(a b) = {(* *) (* *)}",
+        "* = {(* *) (* *)}",
+    ] {
+        let mut expected = Net::from_sic(net).unwrap();
+        let mut actual = expected.clone();
+        assert_eq!(actual.share(), 4, "{}", net);
+        expected.reduce(None);
+        actual.reduce(None);
+        expected.share();
+        actual.share();
+        assert!(actual.isomorphic(&expected), "{}", net);
+    }
+}
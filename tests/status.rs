@@ -0,0 +1,75 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for telling normal forms from deadlocks.
+
+use lafont::gen::example;
+use lafont::{Divergence, Estimate, Net, Status};
+
+#[test]
+fn status() {
+    for (net, status) in [
+        ("(a b) = {c d}", Status::Reducible),
+        ("r = (a a)", Status::NormalForm),
+        // Two constructors wait for each other, and a duplicator waits for itself.
+        (
+            "x = (y a)\ny = (x b)\nz = {z c}",
+            Status::Deadlocked {
+                cycles: vec![vec![0, 1], vec![2]],
+            },
+        ),
+    ] {
+        assert_eq!(Net::from_sic(net).unwrap().status(), status, "{}", net);
+    }
+}
+
+#[test]
+fn deadlock_after_reduction() {
+    // The eraser erases the constructor, leaving a vicious circle of 3 duplicators.
+    let mut net = Net::from_sic("* = (a b)\nx = {y a}\ny = {z b}\nz = {x c}").unwrap();
+    assert_eq!(net.status(), Status::Reducible);
+    net.reduce(None);
+    match net.status() {
+        Status::Deadlocked { cycles } => assert_eq!(cycles.iter().map(Vec::len).sum::<usize>(), 3),
+        status => panic!("{:?}", status),
+    }
+}
+
+#[test]
+fn estimate() {
+    for (net, estimate) in [
+        ("(a b) = {c d}", Estimate::Normalizes { steps: 1 }),
+        (
+            "(a b) = {a b}",
+            Estimate::ProbablyDiverges(Divergence::SelfCommutation { a: 0, b: 1 }),
+        ),
+        // The constructor between the two auxiliary ports hides the loop from the pattern.
+        (
+            "(a x) = {y b}\nx = (y c)",
+            Estimate::ProbablyDiverges(Divergence::Growth { from: 3, to: 20003 }),
+        ),
+    ] {
+        assert_eq!(
+            Net::from_sic(net).unwrap().estimate(10_000),
+            estimate,
+            "{}",
+            net
+        );
+    }
+    let mut net = example("fibonacci:5").unwrap();
+    let steps = net.clone().reduce(None).steps;
+    assert_eq!(net.estimate(usize::MAX), Estimate::Normalizes { steps });
+    net.reduce(Some(3));
+    assert_eq!(net.estimate(0), Estimate::Unknown);
+}
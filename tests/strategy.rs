@@ -0,0 +1,93 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the selection of active pairs.
+
+use lafont::gen::{church_term, example};
+use lafont::lambda::{compile, Term};
+use lafont::{Net, Strategy, Symbol};
+
+#[test]
+fn annihilations_first_keep_the_net_smaller() {
+    // 2^10 with Church numerals duplicates 2 many times.
+    let power = Term::App(Box::new(church_term(10)), Box::new(church_term(2)));
+    for net in [example("fibonacci:12").unwrap(), compile(&power).unwrap().0] {
+        let erase = net.clone().reduce_with(Strategy::PreferErase, None);
+        let annihilate = net.clone().reduce_with(Strategy::PreferAnnihilate, None);
+        assert_eq!(annihilate.steps, erase.steps);
+        let (c, d) = (Symbol::CONSTRUCT, Symbol::DUPLICATE);
+        assert_eq!(annihilate.count(c, d), erase.count(c, d));
+        assert!(annihilate.peak_agents < erase.peak_agents);
+    }
+}
+
+#[test]
+fn annihilations_before_older_commutations() {
+    // The commutation becomes active before the annihilation.
+    let mut net = Net::from_sic("(a b) = {c d}\n(e f) = (g h)").unwrap();
+    net.step_with(Strategy::PreferAnnihilate);
+    assert_eq!(net.len(), 2);
+    let mut net = Net::from_sic("(a b) = {c d}\n(e f) = (g h)").unwrap();
+    net.step_with(Strategy::PreferErase);
+    assert_eq!(net.len(), 6);
+}
+
+#[test]
+fn sweeps_profile() {
+    let net = example("fibonacci:12").unwrap();
+    let mut expected = net.clone();
+    let stats = expected.reduce(None);
+    let mut actual = net.clone();
+    let sweeps = actual.reduce_sweeps(None);
+    assert!(actual.isomorphic(&expected));
+    assert_eq!(sweeps.steps, stats.steps);
+    assert_eq!(sweeps.sweeps.iter().sum::<usize>(), stats.steps);
+    assert!(sweeps.sweeps.iter().all(|&count| count > 0));
+    assert!(sweeps.parallelism() >= 1.);
+    assert!(stats.sweeps.is_empty());
+}
+
+#[test]
+fn fair_serves_all_components() {
+    // The first component annihilates once, the second one grows forever.
+    let sic = "(a b) = (c d)\n(e f) = {e f}";
+    let (c, d) = (Symbol::CONSTRUCT, Symbol::DUPLICATE);
+    let lifo = Net::from_sic(sic)
+        .unwrap()
+        .reduce_with(Strategy::Lifo, Some(100));
+    assert_eq!(lifo.count(c, c), 0);
+    let mut net = Net::from_sic(sic).unwrap();
+    let fair = net.reduce_with(Strategy::Fair, Some(2));
+    assert_eq!((fair.count(c, c), fair.count(c, d)), (1, 1));
+    let fair = net.reduce_with(Strategy::Fair, Some(100));
+    assert_eq!(fair.count(c, d), 100);
+}
+
+#[test]
+fn scanning_strategies_keep_the_worklist_bounded() {
+    for strategy in [Strategy::Outermost, Strategy::Fair] {
+        let mut net = example("fibonacci:12").unwrap();
+        let mut steps = 0;
+        while net.step_with(strategy) {
+            // The reduced pair stays queued until the next step.
+            assert!(
+                net.queued() <= net.active_pairs().len() + 1,
+                "{:?}",
+                strategy
+            );
+            steps += 1;
+        }
+        assert!(steps > 100);
+    }
+}
@@ -0,0 +1,76 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the streaming reduction of erase cascades.
+
+use lafont::Net;
+
+/// Returns a net erasing a complete binary tree of constructors of the given depth, whose leaves
+/// are free ports.
+fn erased_tree(depth: usize) -> String {
+    fn tree(depth: usize, leaf: &mut usize) -> String {
+        if depth == 0 {
+            *leaf += 1;
+            return format!("x{}", *leaf - 1);
+        }
+        format!("({} {})", tree(depth - 1, leaf), tree(depth - 1, leaf))
+    }
+    format!("* = {}", tree(depth, &mut 0))
+}
+
+#[test]
+fn same_as_reduce() {
+    let sources = [
+        erased_tree(6),
+        "* = (a b)\nx = {y a}\ny = {x b}".to_string(),
+        "r = (a *)\n* = {a b}\n(b c) = (c d)\nd = *".to_string(),
+    ];
+    for sic in &sources {
+        let mut expected = Net::from_sic(sic).unwrap();
+        let expected_stats = expected.reduce(None);
+        let mut actual = Net::from_sic(sic).unwrap();
+        let actual_stats = actual.reduce_streaming(None);
+        assert!(actual.isomorphic(&expected), "{sic}");
+        assert_eq!(actual_stats.steps, expected_stats.steps, "{sic}");
+        assert_eq!(actual_stats.rules, expected_stats.rules, "{sic}");
+    }
+}
+
+#[test]
+fn flat_memory() {
+    let sic = erased_tree(10);
+    let mut net = Net::from_sic(&sic).unwrap();
+    let initial = net.len();
+    let stats = net.reduce_streaming(None);
+    assert_eq!(stats.peak_agents, initial);
+    assert_eq!(net.len(), 1 << 10);
+}
+
+#[test]
+fn limit() {
+    let sic = erased_tree(4);
+    let mut expected = Net::from_sic(&sic).unwrap();
+    expected.reduce(None);
+    let mut net = Net::from_sic(&sic).unwrap();
+    let mut steps = 0;
+    loop {
+        let stats = net.reduce_streaming(Some(3));
+        steps += stats.steps;
+        if stats.steps < 3 {
+            break;
+        }
+    }
+    assert_eq!(steps, 15);
+    assert!(net.isomorphic(&expected));
+}
@@ -0,0 +1,91 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for tracing and replaying rewrites.
+
+use lafont::gen::example;
+use lafont::{Net, Strategy};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// A tracer whose lines can be read back.
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn round_trip() {
+    for strategy in [Strategy::PreferErase, Strategy::Lifo, Strategy::Random] {
+        let original = example("fibonacci:8").unwrap();
+        let mut net = original.clone();
+        let buffer = Buffer::default();
+        net.seed(0);
+        net.set_tracer(buffer.clone());
+        let steps = net.reduce_with(strategy, None).steps;
+        let trace = buffer.0.lock().unwrap().clone();
+        let mut replayed = original.clone();
+        assert_eq!(replayed.replay(&trace[..]), Ok(steps));
+        assert!(replayed.isomorphic(&net), "{:?}", strategy);
+        assert_eq!(replayed.active_pairs(), net.active_pairs());
+    }
+}
+
+#[test]
+fn replay_errors() {
+    let net = example("fibonacci:3").unwrap();
+    let (a, b) = net.active_pairs()[0];
+    let line = |a: usize, b: usize| {
+        let name = |x: usize| net.rules().name(net.symbol(x).unwrap()).to_string();
+        format!("{}-{} {} {} ->", name(a), name(b), a, b)
+    };
+    let replay = |trace: &str| net.clone().replay(trace.as_bytes());
+    assert_eq!(replay(""), Ok(0));
+    assert_eq!(replay("oops"), Err("line 1: expected ->".to_string()));
+    assert_eq!(
+        replay(&format!("{} x", line(a, b))),
+        Err("line 1: invalid id".to_string())
+    );
+    let other = (0..net.next_id())
+        .find(|&x| x != a && x != b && net.symbol(x).is_some())
+        .unwrap();
+    assert_eq!(
+        replay(&line(a, other)),
+        Err("line 1: not an active pair".to_string())
+    );
+}
+
+#[test]
+fn compose_keeps_tracer() {
+    let mut net = example("fibonacci:3").unwrap();
+    let buffer = Buffer::default();
+    net.set_tracer(buffer.clone());
+    let sub = Net::from_sic("a = *").unwrap();
+    let f = net.free().next().unwrap();
+    let mut net = net
+        .compose(&sub, &[(f, sub.find_free("a").unwrap())])
+        .unwrap();
+    let steps = net.reduce(None).steps;
+    let trace = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    assert!(steps > 0);
+    assert_eq!(trace.lines().count(), steps);
+}
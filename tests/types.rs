@@ -0,0 +1,127 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for typing nets and rules, on unary addition.
+
+use lafont::{Net, Port, Rule, RuleSet, Signature, Slot, Symbol, Type, TypeError};
+
+struct Addition {
+    rules: RuleSet,
+    signature: Signature,
+    zero: Symbol,
+    succ: Symbol,
+    add: Symbol,
+}
+
+/// Returns the rules of unary addition, where `add_succ` may be wrong.
+fn addition(add_succ: Rule) -> Addition {
+    use Slot::*;
+    let mut rules = RuleSet::new();
+    let zero = rules.add_symbol("zero", 0);
+    let succ = rules.add_symbol("succ", 1);
+    let add = rules.add_symbol("add", 2);
+    let add_zero = Rule {
+        agents: vec![],
+        wires: vec![(Left(1), Left(2))],
+    };
+    rules.add_rule(add, zero, add_zero).unwrap();
+    rules.add_rule(add, succ, add_succ).unwrap();
+    let mut signature = Signature::new();
+    let types = |types: &[&str]| types.iter().map(|x| x.parse().unwrap()).collect();
+    signature.set(zero, types(&["Nat+"]));
+    signature.set(succ, types(&["Nat+", "Nat-"]));
+    signature.set(add, types(&["Nat-", "Nat-", "Nat+"]));
+    Addition {
+        rules,
+        signature,
+        zero,
+        succ,
+        add,
+    }
+}
+
+fn add_succ(add: Symbol, succ: Symbol) -> Rule {
+    use Slot::*;
+    Rule {
+        agents: vec![add, succ],
+        wires: vec![
+            (Right(1), Agent(0, 0)),
+            (Left(1), Agent(0, 1)),
+            (Agent(0, 2), Agent(1, 1)),
+            (Agent(1, 0), Left(2)),
+        ],
+    }
+}
+
+#[test]
+fn well_typed_rules() {
+    let (add, succ) = (Symbol(2), Symbol(1));
+    let addition = addition(add_succ(add, succ));
+    assert_eq!(addition.signature.check(&addition.rules), Ok(()));
+}
+
+#[test]
+fn ill_typed_rule() {
+    use Slot::*;
+    let (add, succ) = (Symbol(2), Symbol(1));
+    let mut rule = add_succ(add, succ);
+    // Swaps the first argument and the result of the created addition.
+    rule.wires[1] = (Left(1), Agent(0, 2));
+    rule.wires[2] = (Agent(0, 1), Agent(1, 1));
+    let addition = addition(rule);
+    let errors = addition.signature.check(&addition.rules).unwrap_err();
+    // The rule is checked as succ-add, where the addition is on the right.
+    let (input, output) = (Type::input("Nat"), Type::output("Nat"));
+    assert_eq!(
+        errors,
+        vec![
+            TypeError::Rule(succ, add, Right(1), Agent(0, 2), output.clone(), output),
+            TypeError::Rule(succ, add, Agent(0, 1), Agent(1, 1), input.clone(), input),
+        ]
+    );
+}
+
+#[test]
+fn typecheck_net() {
+    let (add, succ) = (Symbol(2), Symbol(1));
+    let addition = addition(add_succ(add, succ));
+    let mut net = Net::with_rules(addition.rules.clone());
+    let a = net.add_agent(addition.add).unwrap();
+    let z = net.add_agent(addition.zero).unwrap();
+    let s = net.add_agent(addition.succ).unwrap();
+    let root = net.add_free();
+    net.connect(Port::new(a, 0), Port::new(z, 0)).unwrap();
+    net.connect(Port::new(a, 1), Port::new(s, 0)).unwrap();
+    net.connect(Port::new(a, 2), Port::new(root, 0)).unwrap();
+    let e = net.add_agent(addition.zero).unwrap();
+    net.connect(Port::new(s, 1), Port::new(e, 0)).unwrap();
+    assert_eq!(net.typecheck(&addition.signature), Ok(()));
+    net.reduce(None);
+    assert_eq!(net.typecheck(&addition.signature), Ok(()));
+
+    // A successor is plugged in the result of an addition.
+    let b = net.add_agent(addition.add).unwrap();
+    let t = net.add_agent(addition.succ).unwrap();
+    net.connect(Port::new(b, 2), Port::new(t, 0)).unwrap();
+    let nat = Type::output("Nat");
+    assert_eq!(
+        net.typecheck(&addition.signature),
+        Err(vec![TypeError::Wire(
+            Port::new(b, 2),
+            Port::new(t, 0),
+            nat.clone(),
+            nat
+        )])
+    );
+}
@@ -0,0 +1,115 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the validation of malformed nets.
+
+use lafont::{Malformed, Net, Port};
+
+#[test]
+fn dangling() {
+    let mut net = Net::empty();
+    let c = net.add_construct();
+    let expected = (0..3).map(|p| Malformed::Dangling(Port::new(c, p)));
+    assert_eq!(net.validate(), Err(expected.collect()));
+    let f = net.add_free();
+    net.connect(Port::new(c, 0), Port::new(f, 0)).unwrap();
+    net.connect(Port::new(c, 1), Port::new(c, 2)).unwrap();
+    assert_eq!(net.validate(), Ok(()));
+    let f = net.add_free();
+    assert_eq!(
+        net.validate(),
+        Err(vec![Malformed::Dangling(Port::new(f, 0))])
+    );
+    assert_eq!(
+        Malformed::Dangling(Port::new(f, 0)).to_string(),
+        format!("port {}.0 is not connected", f)
+    );
+}
+
+#[test]
+fn asymmetric() {
+    let mut net = Net::from_sic("r = (a b)").unwrap();
+    assert_eq!(net.validate(), Ok(()));
+    let c = net.agents().next().unwrap();
+    let (a, b) = (net.find_free("a").unwrap(), net.find_free("b").unwrap());
+    // The previous wires of both ports are left pointing at them.
+    net.connect(Port::new(c, 1), Port::new(b, 0)).unwrap();
+    let mut expected = vec![
+        Malformed::Asymmetric(Port::new(a, 0), Port::new(c, 1)),
+        Malformed::Asymmetric(Port::new(c, 2), Port::new(b, 0)),
+    ];
+    expected.sort();
+    assert_eq!(net.validate(), Err(expected));
+    assert_eq!(
+        Malformed::Asymmetric(Port::new(a, 0), Port::new(c, 1)).to_string(),
+        format!("port {}.0 is connected to {}.1 but not back", a, c)
+    );
+}
+
+#[test]
+fn self_connected() {
+    let mut net = Net::empty();
+    let c = net.add_construct();
+    let f = net.add_free();
+    net.connect(Port::new(c, 0), Port::new(c, 1)).unwrap();
+    net.connect(Port::new(c, 2), Port::new(f, 0)).unwrap();
+    assert_eq!(net.validate(), Err(vec![Malformed::SelfConnected(c)]));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn out_of_range() {
+    // The eraser is connected to a fourth port of the constructor, which only has three.
+    let json = r#"{"V2": {"symbols": [["construct", 2], ["duplicate", 2], ["erase", 0]],
+        "rules": [],
+        "agents": [[0, 2, [{"agent": 1, "port": 3}]],
+                   [1, 0, [{"agent": 0, "port": 0}, {"agent": 2, "port": 0},
+                           {"agent": 3, "port": 0}]]],
+        "free": [[2, {"agent": 1, "port": 1}], [3, {"agent": 1, "port": 2}]],
+        "names": [], "next": 4}}"#;
+    let net = Net::from_json(json).unwrap();
+    let (x, y) = (Port::new(0, 0), Port::new(1, 3));
+    assert_eq!(
+        net.validate(),
+        Err(vec![
+            Malformed::OutOfRange(x, y),
+            Malformed::Asymmetric(Port::new(1, 0), x),
+        ])
+    );
+    assert_eq!(
+        Malformed::OutOfRange(x, y).to_string(),
+        "port 0.0 is connected to invalid 1.3"
+    );
+    // Agents with a wrong number of ports are rejected when parsed.
+    let json = json.replace(
+        r#"[0, 2, [{"agent": 1, "port": 3}]]"#,
+        "[0, 2, [null, null]]",
+    );
+    assert!(Net::from_json(&json).is_err());
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn missing() {
+    let json = r#"{"V2": {"symbols": [["construct", 2], ["duplicate", 2], ["erase", 0]],
+        "rules": [], "agents": [[0, 2, [{"agent": 7, "port": 0}]]],
+        "free": [], "names": [], "next": 8}}"#;
+    let net = Net::from_json(json).unwrap();
+    let (x, y) = (Port::new(0, 0), Port::new(7, 0));
+    assert_eq!(net.validate(), Err(vec![Malformed::Missing(x, y)]));
+    assert_eq!(
+        Malformed::Missing(x, y).to_string(),
+        "port 0.0 is connected to missing 7.0"
+    );
+}
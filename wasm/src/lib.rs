@@ -0,0 +1,97 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reduction of interaction nets in the browser.
+//!
+//! A [`Reducer`] holds a net and reduces it on demand. The page in `www/` draws the net on a
+//! canvas from [`Reducer::agents`] and [`Reducer::wires`].
+
+use lafont::{Net, Port};
+use wasm_bindgen::prelude::*;
+
+/// Symbol reported for free ports by [`Reducer::agents`].
+const FREE: u32 = u32::MAX;
+
+#[wasm_bindgen]
+pub struct Reducer {
+    net: Net,
+}
+
+#[wasm_bindgen]
+impl Reducer {
+    /// Creates a reducer for an example net, see [`lafont::gen::example`].
+    #[wasm_bindgen(constructor)]
+    pub fn new(example: &str) -> Result<Reducer, JsError> {
+        let net = lafont::gen::example(example).map_err(|e| JsError::new(&e))?;
+        Ok(Reducer { net })
+    }
+
+    /// Creates a reducer for a net in JSON, see [`Net::from_json`].
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<Reducer, JsError> {
+        let net = Net::from_json(json).map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(Reducer { net })
+    }
+
+    /// Returns the net in JSON, see [`Net::to_json`].
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> String {
+        self.net.to_json()
+    }
+
+    /// Reduces an active pair and returns whether there was one.
+    pub fn step(&mut self) -> bool {
+        self.net.step()
+    }
+
+    /// Reduces all the currently active pairs and returns how many.
+    #[wasm_bindgen(js_name = stepAll)]
+    pub fn step_all(&mut self) -> usize {
+        self.net.step_all()
+    }
+
+    /// Returns the number of active pairs.
+    #[wasm_bindgen(js_name = activePairs)]
+    pub fn active_pairs(&self) -> usize {
+        self.net.active_pairs().len()
+    }
+
+    /// Returns the agents and free ports as consecutive pairs of id and symbol.
+    ///
+    /// The symbol of free ports is `0xffffffff`.
+    pub fn agents(&self) -> Vec<u32> {
+        let mut agents = Vec::new();
+        for (a, symbol, _) in self.net.nodes() {
+            agents.push(a as u32);
+            agents.push(symbol.map_or(FREE, |s| s.0 as u32));
+        }
+        agents
+    }
+
+    /// Returns the wires as consecutive quadruples of id and port for both ends.
+    pub fn wires(&self) -> Vec<u32> {
+        let mut wires = Vec::new();
+        for (a, _, ports) in self.net.nodes() {
+            for (p, y) in ports.enumerate() {
+                match y {
+                    Some(y) if Port::new(a, p) < y => {
+                        wires.extend([a, p, y.agent, y.port].map(|x| x as u32));
+                    }
+                    _ => (),
+                }
+            }
+        }
+        wires
+    }
+}
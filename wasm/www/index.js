@@ -0,0 +1,161 @@
+// Copyright 2018-2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Draws a net reduced by the WebAssembly module built with:
+//
+//     wasm-pack build --target=web
+//
+// Agents move with a force-directed layout in the plane, and active pairs reduce when their
+// agents collide.
+
+import init, { Reducer } from "../pkg/lafont_wasm.js";
+
+const FREE = 0xffffffff;
+const COLORS = ["blue", "lime", "red"];
+
+const canvas = document.getElementById("canvas");
+const context = canvas.getContext("2d");
+const stats = document.getElementById("stats");
+
+let reducer = null;
+let paused = false;
+let reductions = 0;
+// Position and velocity of each agent and free port, by id.
+let nodes = new Map();
+
+function start() {
+  reducer = new Reducer(document.getElementById("example").value);
+  nodes = new Map();
+  reductions = 0;
+}
+
+// Returns the agents and wires of the net, adding nodes for new agents near their neighbors.
+function read() {
+  const raw = reducer.agents();
+  const agents = new Map();
+  for (let i = 0; i < raw.length; i += 2) {
+    agents.set(raw[i], raw[i + 1]);
+  }
+  const flat = reducer.wires();
+  const wires = [];
+  for (let i = 0; i < flat.length; i += 4) {
+    wires.push(flat.slice(i, i + 4));
+  }
+  for (const id of nodes.keys()) {
+    if (!agents.has(id)) nodes.delete(id);
+  }
+  for (const [x, , y] of wires) {
+    for (const [a, b] of [[x, y], [y, x]]) {
+      if (nodes.has(a)) continue;
+      const near = nodes.get(b) || { x: 0, y: 0 };
+      const jitter = () => 2 * Math.random() - 1;
+      nodes.set(a, { x: near.x + jitter(), y: near.y + jitter(), vx: 0, vy: 0 });
+    }
+  }
+  for (const a of agents.keys()) {
+    if (!nodes.has(a)) nodes.set(a, { x: 0, y: 0, vx: 0, vy: 0 });
+  }
+  return { agents, wires };
+}
+
+// Moves the nodes: wires are springs, principal wires are shorter, and nodes repel.
+function layout({ wires }) {
+  const all = [...nodes.values()];
+  for (const n of all) {
+    for (const m of all) {
+      if (n === m) continue;
+      const dx = n.x - m.x, dy = n.y - m.y;
+      const d2 = Math.max(dx * dx + dy * dy, 0.01);
+      if (d2 < 400) {
+        n.vx += dx / d2;
+        n.vy += dy / d2;
+      }
+    }
+  }
+  for (const [a, p, b, q] of wires) {
+    const n = nodes.get(a), m = nodes.get(b);
+    const dx = m.x - n.x, dy = m.y - n.y;
+    const d = Math.max(Math.hypot(dx, dy), 0.01);
+    const length = p === 0 && q === 0 ? 0 : 3;
+    const force = 0.05 * (d - length) / d;
+    n.vx += force * dx; n.vy += force * dy;
+    m.vx -= force * dx; m.vy -= force * dy;
+  }
+  for (const n of all) {
+    n.vx *= 0.9; n.vy *= 0.9;
+    n.x += 0.1 * n.vx; n.y += 0.1 * n.vy;
+  }
+}
+
+// Returns whether an active pair is close enough to reduce.
+function collides({ agents, wires }) {
+  for (const [a, p, b, q] of wires) {
+    if (p !== 0 || q !== 0 || agents.get(a) === FREE || agents.get(b) === FREE) continue;
+    const n = nodes.get(a), m = nodes.get(b);
+    if (Math.hypot(n.x - m.x, n.y - m.y) < 0.5) return true;
+  }
+  return false;
+}
+
+function draw({ agents, wires }) {
+  canvas.width = window.innerWidth;
+  canvas.height = window.innerHeight;
+  context.fillStyle = "black";
+  context.fillRect(0, 0, canvas.width, canvas.height);
+  context.save();
+  context.translate(canvas.width / 2, canvas.height / 2);
+  context.scale(10, 10);
+  context.lineWidth = 0.1;
+  for (const [a, p, b, q] of wires) {
+    const n = nodes.get(a), m = nodes.get(b);
+    context.strokeStyle = p === 0 || q === 0 ? "white" : "gray";
+    context.beginPath();
+    context.moveTo(n.x, n.y);
+    context.lineTo(m.x, m.y);
+    context.stroke();
+  }
+  for (const [a, symbol] of agents) {
+    const n = nodes.get(a);
+    context.fillStyle = symbol === FREE ? "white" : COLORS[symbol] || "yellow";
+    context.beginPath();
+    context.arc(n.x, n.y, symbol === FREE ? 0.3 : 1, 0, 2 * Math.PI);
+    context.fill();
+  }
+  context.restore();
+}
+
+function frame() {
+  if (reducer !== null) {
+    let net = read();
+    if (!paused) {
+      layout(net);
+      // Pairs kept apart by other forces still reduce from time to time.
+      if (collides(net) || (reducer.activePairs() > 0 && Math.random() < 0.02)) {
+        reductions += reducer.stepAll();
+        net = read();
+      }
+    }
+    draw(net);
+    stats.textContent = `agents: ${net.agents.size} active pairs: ${reducer.activePairs()} ` +
+      `reductions: ${reductions}`;
+  }
+  requestAnimationFrame(frame);
+}
+
+await init();
+document.getElementById("start").onclick = start;
+document.getElementById("pause").onclick = () => { paused = !paused; };
+start();
+requestAnimationFrame(frame);